@@ -1,7 +1,7 @@
 pub use color::{ColorContext, ConsoleColor, RESET as RESET_NUM};
 pub use raw::{
 	build_escape, build_string_args, build_string_str, print_args, print_str, println_args,
-	println_str, RESET,
+	println_str, remove_color_definition, RESET,
 };
 
 mod color;
@@ -156,6 +156,8 @@ pub fn remove_color_definition(scr: &str) -> String {
 			ret.push_str(&scr[s..elem.start()]);
 			s = elem.end();
 		}
+
+		ret.push_str(&scr[s..]);
 		ret
 	}
 }
@@ -194,7 +196,7 @@ mod tests {
 			.flat_map(|x| COLORS.iter().map(move |y| (x, y)))
 	}
 
-	static ID_SEED: Lazy<AtomicUsize> = Lazy::new(|| AtomicUsize::default());
+	static ID_SEED: Lazy<AtomicUsize> = Lazy::new(AtomicUsize::default);
 
 	fn build_expected_str(
 		foreground: &Option<&ConsoleColor>,
@@ -204,7 +206,7 @@ mod tests {
 	) -> String {
 		format!(
 			"{}<<contain>>{}{}\x1B[0m",
-			build_escape(&foreground, &background),
+			build_escape(foreground, background),
 			id,
 			text
 		)
@@ -218,6 +220,7 @@ mod tests {
 		assert_eq!(remove_color_definition(""), "");
 		assert_eq!(remove_color_definition("\x1B[96m\x1B[101m"), "");
 		assert_eq!(remove_color_definition("hello\t world"), "hello\t world");
+		assert_eq!(remove_color_definition("\x1B[96mhello\x1B[0m world"), "hello world");
 	}
 
 	#[test]
@@ -401,17 +404,17 @@ mod tests {
 			let id = ID_SEED.fetch_add(1, Relaxed);
 
 			print_args(
-				&Some(&fore),
-				&Some(&back),
+				&Some(fore),
+				&Some(back),
 				format_args!("<<contain>>{}{}", id, "BOTH"),
 			);
-			assert_output(&build_expected_str(&Some(&fore), &Some(&back), id, "BOTH"));
+			assert_output(&build_expected_str(&Some(fore), &Some(back), id, "BOTH"));
 		}
 
 		for col in COLORS.iter() {
 			let id = ID_SEED.fetch_add(1, Relaxed);
-			print_args(&Some(&col), &None, format_args!("<<contain>>{}FORE", id));
-			assert_output(&build_expected_str(&Some(&col), &None, id, "FORE"));
+			print_args(&Some(col), &None, format_args!("<<contain>>{}FORE", id));
+			assert_output(&build_expected_str(&Some(col), &None, id, "FORE"));
 		}
 
 		let id = ID_SEED.fetch_add(1, Relaxed);
@@ -1,10 +1,50 @@
-use syntax::arithmetic_expression::ArithmeticExpression;
-use syntax::binary_operation::{BinaryOperation, Operation};
-use syntax::bracket::Bracket;
+use syntax::binary_operation::Operation;
+use syntax::equation::Equation;
 use syntax::expression::Expression;
-use syntax::number::Number;
+use syntax::expression_arena::{ArenaNode, ExpressionArena, NodeId};
 use syntax::number_value::NumberValue;
 
+// Common view over Expression and ExpressionArena nodes, so that both are formatted by
+// the same iterative writers.
+enum Node<'a, N> {
+	Number(&'a NumberValue),
+	Variable(char),
+	Bracket(N),
+	BinaryOperation(N, N, &'a Operation),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+	Left,
+	Right,
+}
+
+enum Task<'a, N> {
+	Visit(N, Option<(&'a Operation, Side)>),
+	Operator(&'a Operation),
+	Push(char),
+}
+
+fn expression_node(expr: &Expression) -> Node<'_, &Expression> {
+	match expr {
+		Expression::Number(num) => Node::Number(num.number()),
+		Expression::Variable(var) => Node::Variable(var.name()),
+		Expression::Bracket(bracket) => Node::Bracket(bracket.expression()),
+		Expression::BinaryOperation(bin) => {
+			Node::BinaryOperation(bin.left(), bin.right(), bin.operation())
+		}
+	}
+}
+
+fn arena_node(arena: &ExpressionArena, id: NodeId) -> Node<'_, NodeId> {
+	match arena.node(id) {
+		ArenaNode::Number(num) => Node::Number(num),
+		ArenaNode::Variable(name) => Node::Variable(*name),
+		ArenaNode::Bracket(inner) => Node::Bracket(*inner),
+		ArenaNode::BinaryOperation(left, right, op) => Node::BinaryOperation(*left, *right, op),
+	}
+}
+
 fn write_operator(operator: &Operation, buffer: &mut String) {
 	buffer.push(' ');
 	
@@ -18,124 +58,157 @@ fn write_operator(operator: &Operation, buffer: &mut String) {
 	buffer.push(' ');
 }
 
-fn minimal_expression(expr: &Expression, parent: &Option<&Operation>, buffer: &mut String) {
-	match expr {
-		Expression::Number(num) => minimal_number(num, buffer),
-		Expression::Bracket(bracket) => minimal_bracket(bracket, parent, buffer),
-		Expression::BinaryOperation(bin) => minimal_binary_op(bin, parent, buffer),
-	}
-}
-
-fn is_additive(operator: &Operation) -> bool {
-	match operator {
-		Operation::Add => true,
-		Operation::Sub => true,
-		Operation::Mul => false,
-		Operation::Div => false,
+fn write_number(value: &NumberValue, buffer: &mut String) {
+	match value {
+		NumberValue::Integer(int) => buffer.push_str(&format!("{}", int)),
 	}
 }
 
-fn require(expr: &BinaryOperation, parent: &Option<&Operation>) -> bool {
+fn require(operation: &Operation, parent: &Option<&Operation>, right: bool) -> bool {
 	match parent {
 		None => false,
-		Some(p) => {
-			if let Operation::Div = p {
-				true
-			} else if is_additive(p) {
-				false
-			} else {
-				is_additive(expr.operation())
-			}
-		}
+		Some(p) => p.needs_bracket(operation, right),
 	}
 }
 
-fn minimal_binary_op(expr: &BinaryOperation, parent: &Option<&Operation>, buffer: &mut String) {
-	if require(expr, parent) {
-		buffer.push('{');
-	}
-	
-	minimal_expression(expr.left(), &Some(expr.operation()), buffer);
-	
-	write_operator(expr.operation(), buffer);
-	
-	minimal_expression(expr.right(), &Some(expr.operation()), buffer);
+fn minimal_expression<'a, N: Copy>(root: N, view: impl Fn(N) -> Node<'a, N>, buffer: &mut String) {
+	let mut tasks = vec![Task::Visit(root, None)];
 	
-	if require(expr, parent) {
-		buffer.push('}');
+	while let Some(task) = tasks.pop() {
+		match task {
+			Task::Visit(node, parent) => match view(node) {
+				Node::Number(num) => write_number(num, buffer),
+				Node::Variable(name) => buffer.push(name),
+				Node::Bracket(inner) => tasks.push(Task::Visit(inner, parent)),
+				Node::BinaryOperation(left, right, op) => {
+					let required = match parent {
+						Some((p, side)) => require(op, &Some(p), side == Side::Right),
+						None => false,
+					};
+					
+					if required {
+						buffer.push('{');
+						tasks.push(Task::Push('}'));
+					}
+					
+					tasks.push(Task::Visit(right, Some((op, Side::Right))));
+					tasks.push(Task::Operator(op));
+					tasks.push(Task::Visit(left, Some((op, Side::Left))));
+				}
+			},
+			Task::Operator(op) => write_operator(op, buffer),
+			Task::Push(c) => buffer.push(c),
+		}
 	}
 }
 
-fn minimal_bracket(expr: &Bracket, parent: &Option<&Operation>, buffer: &mut String) {
-	minimal_expression(expr.expression(), parent, buffer)
+pub fn minimal_infix_notation(expr: &Expression) -> String {
+	let mut buff = String::default();
+	minimal_expression(expr, expression_node, &mut buff);
+	buff
 }
 
-fn minimal_number(expr: &Number, buffer: &mut String) {
-	match expr.number() {
-		NumberValue::Integer(int) => buffer.push_str(&format!("{}", int)),
-	}
+pub fn equation_notation(equation: &Equation) -> String {
+	format!("{} = {}", minimal_infix_notation(equation.left()), minimal_infix_notation(equation.right()))
 }
 
-pub fn minimal_infix_notation(expr: &Expression) -> String {
+pub fn arena_minimal_infix_notation(arena: &ExpressionArena, id: NodeId) -> String {
 	let mut buff = String::default();
-	minimal_expression(expr, &None, &mut buff);
+	minimal_expression(id, |x| arena_node(arena, x), &mut buff);
 	buff
 }
 
-fn strict_expression(expr: &Expression, buffer: &mut String) {
-	match expr {
-		Expression::Number(num) => strict_number(num, buffer),
-		Expression::Bracket(bracket) => strict_bracket(bracket, buffer),
-		Expression::BinaryOperation(bin_op) => strict_binary_op(bin_op, buffer),
+fn strict_expression<'a, N: Copy>(root: N, view: impl Fn(N) -> Node<'a, N>, buffer: &mut String) {
+	let mut tasks = vec![Task::Visit(root, None)];
+	
+	while let Some(task) = tasks.pop() {
+		match task {
+			Task::Visit(node, _) => match view(node) {
+				Node::Number(num) => write_number(num, buffer),
+				Node::Variable(name) => buffer.push(name),
+				Node::Bracket(inner) => {
+					buffer.push('{');
+					tasks.push(Task::Push('}'));
+					tasks.push(Task::Visit(inner, None));
+				}
+				Node::BinaryOperation(left, right, op) => {
+					buffer.push('{');
+					tasks.push(Task::Push('}'));
+					tasks.push(Task::Visit(right, None));
+					tasks.push(Task::Operator(op));
+					tasks.push(Task::Visit(left, None));
+				}
+			},
+			Task::Operator(op) => write_operator(op, buffer),
+			Task::Push(c) => buffer.push(c),
+		}
 	}
 }
 
-fn strict_number(number: &Number, buffer: &mut String) {
-	match number.number() {
-		NumberValue::Integer(int) => buffer.push_str(&format!("{int}").to_string()),
-	}
+pub fn strict_infix_expression(expr: &Expression) -> String {
+	let mut buff = String::default();
+	strict_expression(expr, expression_node, &mut buff);
+	buff
 }
 
-fn strict_binary_op(binary_operation: &BinaryOperation, buffer: &mut String) {
-	buffer.push('{');
-	
-	strict_expression(binary_operation.left(), buffer);
-	write_operator(binary_operation.operation(), buffer);
-	strict_expression(binary_operation.right(), buffer);
-	
-	buffer.push('}');
+pub fn arena_strict_infix_expression(arena: &ExpressionArena, id: NodeId) -> String {
+	let mut buff = String::default();
+	strict_expression(id, |x| arena_node(arena, x), &mut buff);
+	buff
 }
 
-fn strict_bracket(bracket: &Bracket, buffer: &mut String) {
-	buffer.push('{');
-	
-	strict_expression(bracket.expression(), buffer);
+// Brackets exactly as they appear in the tree, nothing added and nothing removed.
+fn written_expression<'a, N: Copy>(root: N, view: impl Fn(N) -> Node<'a, N>, buffer: &mut String) {
+	let mut tasks = vec![Task::Visit(root, None)];
 	
-	buffer.push('}');
+	while let Some(task) = tasks.pop() {
+		match task {
+			Task::Visit(node, _) => match view(node) {
+				Node::Number(num) => write_number(num, buffer),
+				Node::Variable(name) => buffer.push(name),
+				Node::Bracket(inner) => {
+					buffer.push('{');
+					tasks.push(Task::Push('}'));
+					tasks.push(Task::Visit(inner, None));
+				}
+				Node::BinaryOperation(left, right, op) => {
+					tasks.push(Task::Visit(right, None));
+					tasks.push(Task::Operator(op));
+					tasks.push(Task::Visit(left, None));
+				}
+			},
+			Task::Operator(op) => write_operator(op, buffer),
+			Task::Push(c) => buffer.push(c),
+		}
+	}
 }
 
-pub fn strict_infix_expression(expr: &Expression) -> String {
+pub fn infix_notation(expr: &Expression) -> String {
 	let mut buff = String::default();
-	strict_expression(&(expr.clone().to_expression()), &mut buff);
+	written_expression(expr, expression_node, &mut buff);
 	buff
 }
 
 #[cfg(test)]
 mod tests {
-	use combine::Parser;
-	
+	use syntax::arithmetic_expression::ArithmeticExpression;
 	use syntax::binary_operation::{BinaryOperation, Operation};
+	use syntax::bracket::Bracket;
+	use syntax::expression_arena::ExpressionArena;
 	use syntax::number::Number as NumExpr;
 	use syntax::number_value::NumberValue;
 	
-	use crate::infix::formatter::{minimal_infix_notation, strict_infix_expression};
-	use crate::infix::parser::get_parser;
+	use crate::infix::formatter::{
+		arena_minimal_infix_notation, arena_strict_infix_expression, infix_notation,
+		minimal_infix_notation, strict_infix_expression,
+	};
+	use crate::infix::parser::parse;
 	
 	use super::require;
 	
 	#[test]
 	fn strict() {
-		let expr = get_parser().parse("{30*{1+2}-25}/{10+20+15}").unwrap().0;
+		let expr = parse("{30*{1+2}-25}/{10+20+15}").unwrap().0;
 		let ret = strict_infix_expression(&expr);
 		assert_eq!(ret, "{{{{30 * {{1 + 2}}} - 25}} / {{{10 + 20} + 15}}}");
 	}
@@ -151,38 +224,90 @@ mod tests {
 		}
 		
 		let bin = make_fixture(Operation::Add);
-		assert!(!require(&bin, &Some(&Operation::Add)));
-		assert!(!require(&bin, &Some(&Operation::Sub)));
-		assert!(!require(&bin, &None));
-		assert!(require(&bin, &Some(&Operation::Mul)));
-		assert!(require(&bin, &Some(&Operation::Div)));
+		assert!(!require(bin.operation(), &Some(&Operation::Add), false));
+		assert!(!require(bin.operation(), &Some(&Operation::Sub), false));
+		assert!(!require(bin.operation(), &None, false));
+		assert!(require(bin.operation(), &Some(&Operation::Mul), false));
+		assert!(require(bin.operation(), &Some(&Operation::Div), false));
+		assert!(require(bin.operation(), &Some(&Operation::Sub), true));
 		
 		let bin = make_fixture(Operation::Sub);
-		assert!(!require(&bin, &Some(&Operation::Add)));
-		assert!(!require(&bin, &Some(&Operation::Sub)));
-		assert!(!require(&bin, &None));
-		assert!(require(&bin, &Some(&Operation::Mul)));
-		assert!(require(&bin, &Some(&Operation::Div)));
+		assert!(!require(bin.operation(), &Some(&Operation::Add), false));
+		assert!(!require(bin.operation(), &Some(&Operation::Sub), false));
+		assert!(!require(bin.operation(), &None, false));
+		assert!(require(bin.operation(), &Some(&Operation::Mul), false));
+		assert!(require(bin.operation(), &Some(&Operation::Div), false));
+		assert!(require(bin.operation(), &Some(&Operation::Sub), true));
 		
 		let bin = make_fixture(Operation::Mul);
-		assert!(!require(&bin, &Some(&Operation::Add)));
-		assert!(!require(&bin, &Some(&Operation::Sub)));
-		assert!(!require(&bin, &None));
-		assert!(!require(&bin, &Some(&Operation::Mul)));
-		assert!(require(&bin, &Some(&Operation::Div)));
+		assert!(!require(bin.operation(), &Some(&Operation::Add), false));
+		assert!(!require(bin.operation(), &Some(&Operation::Sub), false));
+		assert!(!require(bin.operation(), &None, false));
+		assert!(!require(bin.operation(), &Some(&Operation::Mul), false));
+		assert!(require(bin.operation(), &Some(&Operation::Div), false));
+		assert!(!require(bin.operation(), &Some(&Operation::Sub), true));
 		
 		let bin = make_fixture(Operation::Div);
-		assert!(!require(&bin, &Some(&Operation::Add)));
-		assert!(!require(&bin, &Some(&Operation::Sub)));
-		assert!(!require(&bin, &None));
-		assert!(!require(&bin, &Some(&Operation::Mul)));
-		assert!(require(&bin, &Some(&Operation::Div)));
+		assert!(!require(bin.operation(), &Some(&Operation::Add), false));
+		assert!(!require(bin.operation(), &Some(&Operation::Sub), false));
+		assert!(!require(bin.operation(), &None, false));
+		assert!(!require(bin.operation(), &Some(&Operation::Mul), false));
+		assert!(require(bin.operation(), &Some(&Operation::Mul), true));
+		assert!(require(bin.operation(), &Some(&Operation::Div), false));
+		assert!(!require(bin.operation(), &Some(&Operation::Sub), true));
 	}
 	
 	#[test]
 	fn minimal() {
-		let expr = get_parser().parse("{{{10+20*3}/{{4-5}*{{6+7}/2}}}}").unwrap().0;
+		let expr = parse("{{{10+20*3}/{{4-5}*{{6+7}/2}}}}").unwrap().0;
 		let ret = minimal_infix_notation(&expr);
-		assert_eq!(ret, "{10 + 20 * 3} / {{4 - 5} * {6 + 7} / 2}");
+		assert_eq!(ret, "{10 + 20 * 3} / {{4 - 5} * {{6 + 7} / 2}}");
+		
+		// 2 * 7 / 2 would be 7 rather than 6.
+		let expr = parse("2*{7/2}").unwrap().0;
+		assert_eq!(minimal_infix_notation(&expr), "2 * {7 / 2}");
+		assert_eq!(minimal_infix_notation(&parse("{2*7}/2").unwrap().0), "{2 * 7} / 2");
+		assert_eq!(minimal_infix_notation(&parse("{7/2}*2").unwrap().0), "7 / 2 * 2");
+		
+		let expr = parse("10-{2+3}-{4*5}-{{6-7}}").unwrap().0;
+		assert_eq!(minimal_infix_notation(&expr), "10 - {2 + 3} - 4 * 5 - {6 - 7}");
+	}
+	
+	#[test]
+	fn written() {
+		let expr = parse("{30*{1+2}-25}/{10+20+15}").unwrap().0;
+		assert_eq!(infix_notation(&expr), "{30 * {1 + 2} - 25} / {10 + 20 + 15}");
+		
+		let expr = parse("1-{2-3}").unwrap().0;
+		assert_eq!(infix_notation(&expr), "1 - {2 - 3}");
+	}
+	
+	#[test]
+	fn arena() {
+		const FORMULA: &str = "{{{10+20*3}/{{4-5}*{{6+7}/2}}}}";
+		let expr = parse(FORMULA).unwrap().0;
+		
+		let mut arena = ExpressionArena::new();
+		let id = arena.insert(&expr);
+		
+		assert_eq!(arena_minimal_infix_notation(&arena, id), minimal_infix_notation(&expr));
+		assert_eq!(arena_strict_infix_expression(&arena, id), strict_infix_expression(&expr));
+	}
+	
+	#[test]
+	fn deep_tree() {
+		const DEPTH: usize = 100_000;
+		let mut expr = NumExpr::from(NumberValue::from(0)).to_expression();
+		
+		for _ in 0..DEPTH {
+			let bin = BinaryOperation::new(NumExpr::from(NumberValue::from(1)), expr, Operation::Mul);
+			expr = Bracket::from(bin.to_expression()).to_expression();
+		}
+		
+		let minimal = minimal_infix_notation(&expr);
+		assert_eq!(minimal, format!("{}0", "1 * ".repeat(DEPTH)));
+		
+		let strict = strict_infix_expression(&expr);
+		assert_eq!(strict.len(), DEPTH * 4 + DEPTH * 4 + 1);
 	}
 }
@@ -1,22 +1,100 @@
-use combine::{chainl1, choice, many1, ParseError, Parser, Stream, token};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use combine::{chainl1, choice, many1, satisfy, ParseError, Parser, Stream, token};
 use combine::error::StringStreamError;
 use combine::parser::char::{char, digit, self as chr};
 
 use syntax::arithmetic_expression::ArithmeticExpression;
 use syntax::binary_operation::{BinaryOperation, Operation};
 use syntax::bracket::Bracket;
+use syntax::equation::Equation;
 use syntax::expression::Expression;
 use syntax::number::Number;
 use syntax::number_value::NumberValue;
+use syntax::variable::Variable;
+
+// Bracket nesting is parsed recursively, so deeper input is rejected before it can exhaust the stack.
+// A long chain such as 1+1+...+1 is read in a loop and is not limited.
+pub const DEFAULT_DEPTH_LIMIT: usize = 128;
+
+pub enum InfixParseError {
+	StreamError(StringStreamError),
+	DepthLimitExceeded(usize),
+}
+
+impl Debug for InfixParseError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			InfixParseError::StreamError(err) => write!(f, "StreamError:{:?}", err),
+			InfixParseError::DepthLimitExceeded(limit) => {
+				write!(f, "Nesting exceeds the depth limit of {}.", limit)
+			}
+		}
+	}
+}
+
+impl Display for InfixParseError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		Debug::fmt(self, f)
+	}
+}
+
+impl Error for InfixParseError {}
 
-pub fn parse(formula: &str) -> Result<(Expression, &str), StringStreamError> {
-	get_parser().parse(formula)
+pub fn parse(formula: &str) -> Result<(Expression, &str), InfixParseError> {
+	parse_with_depth_limit(formula, DEFAULT_DEPTH_LIMIT)
+}
+
+pub fn parse_with_depth_limit(
+	formula: &str,
+	depth_limit: usize,
+) -> Result<(Expression, &str), InfixParseError> {
+	check_depth(formula, depth_limit)?;
+	get_parser().parse(formula).map_err(InfixParseError::StreamError)
+}
+
+// Two formulas joined by =, such as 2*x+3=3*{x-1}.
+pub fn parse_equation(formula: &str) -> Result<(Equation, &str), InfixParseError> {
+	parse_equation_with_depth_limit(formula, DEFAULT_DEPTH_LIMIT)
+}
+
+pub fn parse_equation_with_depth_limit(
+	formula: &str,
+	depth_limit: usize,
+) -> Result<(Equation, &str), InfixParseError> {
+	check_depth(formula, depth_limit)?;
+	let ((left, _, right), rem) = (expr(true), char('='), expr(true)).parse(formula).map_err(InfixParseError::StreamError)?;
+	
+	Ok((Equation::new(left, right), rem))
+}
+
+// Rejects brackets nested deeper than the limit before the recursive parse.
+fn check_depth(formula: &str, depth_limit: usize) -> Result<(), InfixParseError> {
+	let mut depth = 0usize;
+	
+	for c in formula.chars() {
+		match c {
+			'{' => {
+				depth += 1;
+				
+				if depth > depth_limit {
+					return Err(InfixParseError::DepthLimitExceeded(depth_limit));
+				}
+			}
+			'}' => depth = depth.saturating_sub(1),
+			_ => {}
+		}
+	}
+	
+	Ok(())
 }
 
-pub fn get_parser<Input>() -> impl Parser<Input, Output=Expression>
+// Unknowns only belong in equations, so a formula to calculate has none.
+fn get_parser<Input>() -> impl Parser<Input, Output=Expression>
 	where Input: Stream<Token=char>,
 	      Input::Error: ParseError<Input::Token, Input::Range, Input::Position>, {
-	expr::<Input>()
+	expr::<Input>(false)
 }
 
 fn trim<Input, O>(parser: impl Parser<Input, Output=O>) -> impl Parser<Input, Output=O>
@@ -25,7 +103,7 @@ fn trim<Input, O>(parser: impl Parser<Input, Output=O>) -> impl Parser<Input, Ou
 	(chr::spaces(), parser, chr::spaces()).map(|(_, expr, _)| expr)
 }
 
-fn expr_<Input>() -> impl Parser<Input, Output=Expression>
+fn expr_<Input>(variables: bool) -> impl Parser<Input, Output=Expression>
 	where Input: Stream<Token=char>,
 	      Input::Error: ParseError<Input::Token, Input::Range, Input::Position>, {
 	let unsigned = trim(many1(digit()).map(|v: String| {
@@ -34,17 +112,19 @@ fn expr_<Input>() -> impl Parser<Input, Output=Expression>
 	
 	let signed = trim(
 		char('-').with(many1(digit()).map(|v: String| v)).map(|v: String| {
-			Number::from(NumberValue::from(v.parse::<i32>().unwrap() * -1)).to_expression()
+			Number::from(NumberValue::from(-v.parse::<i32>().unwrap())).to_expression()
 		}),
 	);
 	
 	let digit = choice((signed, unsigned));
 	
 	let bracket_expr = trim(
-		(char::<Input>('{'), expr(), char('}')).map(|(_, e, _)| Bracket::from(e).to_expression()),
+		(char::<Input>('{'), expr(variables), char('}')).map(|(_, e, _)| Bracket::from(e).to_expression()),
 	);
 	
-	let primary = choice((digit, bracket_expr));
+	let variable = trim(satisfy(move |c: char| variables && c.is_lowercase()).map(|c| Variable::from(c).to_expression()));
+	
+	let primary = choice((digit, variable, bracket_expr));
 	
 	let op = trim(choice((token::<Input>('*'), token('/'))).map(|c| {
 		if c == '*' {
@@ -72,9 +152,7 @@ fn expr_<Input>() -> impl Parser<Input, Output=Expression>
 	
 	let multitive = trim(chainl1(primary, multitive_chain));
 	
-	let additive = trim(chainl1(multitive, additive_chain));
-	
-	additive
+	trim(chainl1(multitive, additive_chain))
 }
 
 // parser! {
@@ -94,6 +172,7 @@ struct expr<Input>
 	>,
 	      Input: ::combine::stream::Stream,
 	      Input: Stream<Token=char>, {
+	variables: bool,
 	__marker: ::combine::lib::marker::PhantomData<fn(Input) -> Expression>,
 }
 
@@ -133,12 +212,12 @@ impl<Input> ::combine::Parser<Input> for expr<Input>
 		state: &mut Self::PartialState,
 	) -> ::combine::error::ParseResult<Expression, <Input as ::combine::stream::StreamOnce>::Error>
 		where M: ::combine::parser::ParseMode, {
-		let expr { .. } = *self;
+		let expr { variables, .. } = *self;
 		{
 			let _ = state;
 			let mut state = Default::default();
 			let state = &mut state;
-			{ expr_() }.parse_mode(mode, input, state)
+			{ expr_(variables) }.parse_mode(mode, input, state)
 		}
 	}
 	
@@ -147,8 +226,8 @@ impl<Input> ::combine::Parser<Input> for expr<Input>
 		&mut self,
 		errors: &mut ::combine::error::Tracked<<Input as ::combine::stream::StreamOnce>::Error>,
 	) {
-		let expr { .. } = *self;
-		let mut parser = { expr_() };
+		let expr { variables, .. } = *self;
+		let mut parser = { expr_(variables) };
 		{
 			let _: &mut dyn ::combine::Parser<Input, Output=Expression, PartialState=_> = &mut parser;
 		}
@@ -159,8 +238,8 @@ impl<Input> ::combine::Parser<Input> for expr<Input>
 		&mut self,
 		errors: &mut ::combine::error::Tracked<<Input as ::combine::stream::StreamOnce>::Error>,
 	) {
-		let expr { .. } = *self;
-		let mut parser = { expr_() };
+		let expr { variables, .. } = *self;
+		let mut parser = { expr_(variables) };
 		{
 			let _: &mut dyn ::combine::Parser<Input, Output=Expression, PartialState=_> = &mut parser;
 		}
@@ -169,7 +248,7 @@ impl<Input> ::combine::Parser<Input> for expr<Input>
 }
 
 #[inline]
-fn expr<Input>() -> expr<Input>
+fn expr<Input>(variables: bool) -> expr<Input>
 	where <Input as ::combine::stream::StreamOnce>::Error: ParseError<
 		<Input as ::combine::stream::StreamOnce>::Token,
 		<Input as ::combine::stream::StreamOnce>::Range,
@@ -178,6 +257,7 @@ fn expr<Input>() -> expr<Input>
 	      Input: ::combine::stream::Stream,
 	      Input: Stream<Token=char>, {
 	expr {
+		variables,
 		__marker: ::combine::lib::marker::PhantomData,
 	}
 }
@@ -188,8 +268,85 @@ mod tests {
 	
 	#[test]
 	fn parse() {
-		let expr = expr().parse("{ 30       *            {     10+200}-25}/{10+20+15       }").unwrap().0;
+		let expr = expr(false).parse("{ 30       *            {     10+200}-25}/{10+20+15       }").unwrap().0;
 		
 		expr.calc().unwrap().eq_i32(&139)
 	}
+	
+	fn nested(depth: usize) -> String {
+		format!("{}1+2{}", "{".repeat(depth), "}".repeat(depth))
+	}
+	
+	#[test]
+	fn depth_limit() {
+		let formula = nested(DEFAULT_DEPTH_LIMIT);
+		let (expr, rem) = super::parse(&formula).unwrap();
+		assert!(rem.is_empty());
+		expr.calc().unwrap().eq_i32(&3);
+		
+		let err = super::parse(&nested(DEFAULT_DEPTH_LIMIT + 1)).err().unwrap();
+		assert!(matches!(err, InfixParseError::DepthLimitExceeded(DEFAULT_DEPTH_LIMIT)));
+		assert_eq!(
+			format!("{}", err),
+			"Nesting exceeds the depth limit of 128."
+		);
+		
+		assert!(parse_with_depth_limit("{1+{2*3}}", 2).is_ok());
+		assert!(matches!(
+			parse_with_depth_limit("{1+{2*3}}", 1),
+			Err(InfixParseError::DepthLimitExceeded(1))
+		));
+		
+		// A chain without brackets is only as deep as its brackets.
+		let chain = |n: usize| format!("1{}", "+1".repeat(n));
+		let formula = chain(10_000);
+		let (expr, rem) = super::parse(&formula).unwrap();
+		assert!(rem.is_empty());
+		expr.calc().unwrap().eq_i32(&10_001);
+		assert!(parse_equation(&format!("x={}", chain(DEFAULT_DEPTH_LIMIT))).is_ok());
+		
+		assert!(matches!(
+			super::parse("*"),
+			Err(InfixParseError::StreamError(_))
+		));
+	}
+	
+	#[test]
+	fn same_tree_as_expr_macro() {
+		fn assert_same(formula: &str, expected: Expression) {
+			let act = super::parse(formula).unwrap().0;
+			assert!(act == expected, "{}", formula);
+		}
+		
+		assert_same("{1+2}*3", syntax::expr! { {1+2}*3 });
+		assert_same("1 + 2 * 3 - 4 / 5", syntax::expr! { 1 + 2 * 3 - 4 / 5 });
+		assert_same("10 - -2 * {3 - 4}", syntax::expr! { 10 - -2 * {3 - 4} });
+		assert!(super::parse("2*x+3").is_err());
+		assert!(super::parse("x").is_err());
+		assert_same(
+			"{1+2*30}-{{42+4-5}*{{6+7}/2}}*{30+40*{20+4-1}}",
+			syntax::expr! { {1+2*30}-{{42+4-5}*{{6+7}/2}}*{30+40*{20+4-1}} },
+		);
+	}
+	
+	#[test]
+	fn equation() {
+		let (equation, rem) = parse_equation(" 2*x+3 = 3*{x-1} ").unwrap();
+		assert!(rem.is_empty());
+		assert!(equation.left() == &syntax::expr! { 2 * x + 3 });
+		assert!(equation.right() == &syntax::expr! { 3 * {x - 1} });
+		assert_eq!(crate::infix::formatter::equation_notation(&equation), "2 * x + 3 = 3 * {x - 1}");
+		
+		assert!(parse_equation("2*x+3").is_err());
+		assert!(matches!(
+			parse_equation(&format!("x={}", nested(DEFAULT_DEPTH_LIMIT + 1))),
+			Err(InfixParseError::DepthLimitExceeded(DEFAULT_DEPTH_LIMIT))
+		));
+		
+		assert!(parse_equation_with_depth_limit("x={1+{2*3}}", 2).is_ok());
+		assert!(matches!(
+			parse_equation_with_depth_limit("x={1+{2*3}}", 1),
+			Err(InfixParseError::DepthLimitExceeded(1))
+		));
+	}
 }
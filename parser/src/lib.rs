@@ -1,2 +1,3 @@
 pub mod infix;
+pub mod pattern;
 pub mod rpn;
@@ -0,0 +1,138 @@
+use combine::{chainl1, choice, many, many1, ParseError, parser, Parser, Stream, token};
+use combine::error::StringStreamError;
+use combine::parser::char::{alpha_num, char, digit, letter, self as chr};
+
+use syntax::binary_operation::Operation;
+use syntax::pattern::Pattern;
+
+// Same grammar as the infix parser, plus `_` (wildcard) and identifiers (metavariables) as primaries.
+pub fn parse(pattern: &str) -> Result<(Pattern, &str), StringStreamError> {
+	get_parser().parse(pattern)
+}
+
+pub fn get_parser<Input>() -> impl Parser<Input, Output=Pattern>
+	where Input: Stream<Token=char>,
+	      Input::Error: ParseError<Input::Token, Input::Range, Input::Position>, {
+	pattern::<Input>()
+}
+
+fn trim<Input, O>(parser: impl Parser<Input, Output=O>) -> impl Parser<Input, Output=O>
+	where Input: Stream<Token=char>,
+	      Input::Error: ParseError<Input::Token, Input::Range, Input::Position>, {
+	(chr::spaces(), parser, chr::spaces()).map(|(_, expr, _)| expr)
+}
+
+fn pattern_<Input>() -> impl Parser<Input, Output=Pattern>
+	where Input: Stream<Token=char>,
+	      Input::Error: ParseError<Input::Token, Input::Range, Input::Position>, {
+	let unsigned = many1(digit()).map(|v: String| Pattern::number(v.parse::<i32>().unwrap()));
+	
+	let signed = char('-')
+		.with(many1(digit()))
+		.map(|v: String| Pattern::number(-v.parse::<i32>().unwrap()));
+	
+	let wildcard = char('_').map(|_| Pattern::Wildcard);
+	
+	let variable = (letter(), many(choice((alpha_num(), char('_')))))
+		.map(|(head, tail): (char, String)| Pattern::Variable(format!("{}{}", head, tail)));
+	
+	let bracket = (char('{'), pattern(), char('}')).map(|(_, p, _)| Pattern::bracket(p));
+	
+	let primary = trim(choice((signed, unsigned, wildcard, variable, bracket)));
+	
+	let op = trim(choice((token::<Input>('*'), token('/'))).map(|c| {
+		if c == '*' {
+			Operation::Mul
+		} else {
+			Operation::Div
+		}
+	}));
+	
+	let multitive_chain = op.map(|o: Operation| {
+		move |l: Pattern, r: Pattern| Pattern::binary_operation(l, r, o.clone())
+	});
+	
+	let op = trim(choice((token::<Input>('+'), token('-'))).map(|c| {
+		if c == '+' {
+			Operation::Add
+		} else {
+			Operation::Sub
+		}
+	}));
+	
+	let additive_chain = op.map(|o: Operation| {
+		move |l: Pattern, r: Pattern| Pattern::binary_operation(l, r, o.clone())
+	});
+	
+	let multitive = trim(chainl1(primary, multitive_chain));
+	
+	trim(chainl1(multitive, additive_chain))
+}
+
+parser! {
+	fn pattern[Input]()(Input) -> Pattern
+	where [Input: Stream<Token = char>] {
+		pattern_()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use syntax::builder::{bracket, num};
+	
+	use super::*;
+	
+	#[test]
+	fn primary() {
+		assert!(parse("_").unwrap().0 == Pattern::Wildcard);
+		assert!(parse("42").unwrap().0 == Pattern::number(42));
+		assert!(parse("-42").unwrap().0 == Pattern::number(-42));
+		assert!(parse("a").unwrap().0 == Pattern::variable("a"));
+		assert!(parse("  lhs_2 ").unwrap().0 == Pattern::variable("lhs_2"));
+		assert!(parse("{a}").unwrap().0 == Pattern::bracket(Pattern::variable("a")));
+	}
+	
+	#[test]
+	fn operators() {
+		let (act, rem) = parse("_ * 0").unwrap();
+		assert!(rem.is_empty());
+		assert!(act == Pattern::binary_operation(Pattern::Wildcard, Pattern::number(0), Operation::Mul));
+		
+		let act = parse("{a} - {a}").unwrap().0;
+		let expected = Pattern::binary_operation(
+			Pattern::bracket(Pattern::variable("a")),
+			Pattern::bracket(Pattern::variable("a")),
+			Operation::Sub,
+		);
+		assert!(act == expected);
+		
+		let act = parse("a*c + b*c").unwrap().0;
+		let expected = Pattern::binary_operation(
+			Pattern::binary_operation(Pattern::variable("a"), Pattern::variable("c"), Operation::Mul),
+			Pattern::binary_operation(Pattern::variable("b"), Pattern::variable("c"), Operation::Mul),
+			Operation::Add,
+		);
+		assert!(act == expected);
+	}
+	
+	#[test]
+	fn remainder() {
+		let (act, rem) = parse("n / 1 ?").unwrap();
+		assert!(act == Pattern::binary_operation(Pattern::variable("n"), Pattern::number(1), Operation::Div));
+		assert_eq!(rem, "?");
+		
+		assert!(parse("*").is_err());
+	}
+	
+	#[test]
+	fn matches_parsed_expression() {
+		let pattern = parse("{a} - {a}").unwrap().0;
+		let expr = crate::infix::parser::parse("10 * {{1+2} - {1+2}}").unwrap().0;
+		
+		let act = pattern.matches(&expr);
+		assert_eq!(act.len(), 1);
+		assert_eq!(act[0].path().to_string(), "/R/I");
+		assert!(act[0].bindings().get("a").unwrap() == &(num(1) + num(2)));
+		assert!(act[0].path().resolve(&expr).unwrap() == &(bracket(num(1) + num(2)) - bracket(num(1) + num(2))));
+	}
+}
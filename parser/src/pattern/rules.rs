@@ -0,0 +1,122 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use syntax::pattern::Pattern;
+use syntax::rewrite::{Rule, RuleError};
+
+use crate::pattern::parser::parse;
+
+pub enum RulesParseError {
+	Syntax(usize, String),
+	InvalidRule(usize, RuleError),
+}
+
+impl RulesParseError {
+	pub fn line(&self) -> usize {
+		match self {
+			RulesParseError::Syntax(line, _) => *line,
+			RulesParseError::InvalidRule(line, _) => *line,
+		}
+	}
+}
+
+impl Debug for RulesParseError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RulesParseError::Syntax(line, msg) => write!(f, "Syntax({}):{}", line, msg),
+			RulesParseError::InvalidRule(line, err) => write!(f, "InvalidRule({}):{:?}", line, err),
+		}
+	}
+}
+
+impl Display for RulesParseError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RulesParseError::Syntax(line, msg) => write!(f, "line {}: {}", line, msg),
+			RulesParseError::InvalidRule(line, err) => write!(f, "line {}: {}", line, err),
+		}
+	}
+}
+
+impl Error for RulesParseError {}
+
+fn parse_side(scr: &str, line: usize) -> Result<Pattern, RulesParseError> {
+	match parse(scr) {
+		Ok((pattern, rem)) if rem.trim().is_empty() => Ok(pattern),
+		Ok((_, rem)) => Err(RulesParseError::Syntax(line, format!("Unexpected input:{}", rem.trim()))),
+		Err(_) => Err(RulesParseError::Syntax(line, format!("Invalid pattern:{}", scr.trim()))),
+	}
+}
+
+// One `lhs => rhs` rule per line. `#` starts a comment and blank lines are ignored.
+pub fn parse_rules(scr: &str) -> Result<Vec<Rule>, RulesParseError> {
+	let mut vec = Vec::<Rule>::new();
+	
+	for (idx, line) in scr.lines().enumerate() {
+		let line_number = idx + 1;
+		let body = match line.find('#') {
+			Some(pos) => &line[..pos],
+			None => line,
+		};
+		
+		if body.trim().is_empty() {
+			continue;
+		}
+		
+		let sides: Vec<&str> = body.split("=>").collect();
+		
+		if sides.len() != 2 {
+			return Err(RulesParseError::Syntax(line_number, "Expected lhs => rhs".to_string()));
+		}
+		
+		let lhs = parse_side(sides[0], line_number)?;
+		let rhs = parse_side(sides[1], line_number)?;
+		
+		vec.push(Rule::new(lhs, rhs).map_err(|e| RulesParseError::InvalidRule(line_number, e))?);
+	}
+	
+	Ok(vec)
+}
+
+#[cfg(test)]
+mod tests {
+	use syntax::builder::num;
+	
+	use super::*;
+	
+	const RULES: &str = r"# mental math
+x * 1 => x
+_ * 0 => 0   # annihilation
+
+a*c + b*c => {a+b}*c
+";
+	
+	#[test]
+	fn parse_rules_test() {
+		let act = parse_rules(RULES).unwrap();
+		assert_eq!(act.len(), 3);
+		
+		assert_eq!(act[0].to_string(), "x * 1 => x");
+		assert_eq!(act[1].to_string(), "_ * 0 => 0");
+		assert_eq!(act[2].to_string(), "a * c + b * c => {a + b} * c");
+		
+		assert!(act[1].apply_root(&(num(42) * num(0))).unwrap() == num(0));
+		assert!(parse_rules("").unwrap().is_empty());
+	}
+	
+	#[test]
+	fn error() {
+		let act = parse_rules("x * 1 => x\nx + 1").unwrap_err();
+		assert!(matches!(act, RulesParseError::Syntax(2, _)));
+		
+		let act = parse_rules("x => y").unwrap_err();
+		assert!(matches!(act, RulesParseError::InvalidRule(1, RuleError::UnboundVariable(_))));
+		assert_eq!(act.to_string(), "line 1: y is not bound by the left hand side.");
+		
+		let act = parse_rules("\n\nx => x ?").unwrap_err();
+		assert_eq!(act.line(), 3);
+		
+		assert!(parse_rules("x => x => x").is_err());
+		assert!(parse_rules("* => x").is_err());
+	}
+}
@@ -1,9 +1,15 @@
 use std::collections::VecDeque;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 
 use syntax::arithmetic_expression::ArithmeticExpression;
 use syntax::binary_operation::{BinaryOperation, Operation};
+use syntax::explanation::Explanation;
 use syntax::expression::Expression;
 use syntax::number::Number as NumExpr;
+use syntax::node_path::NodePath;
+use syntax::number_value::NumberValue;
+use syntax::step::{StepControl, StepFailure};
 
 pub use super::tokenizer::Token;
 pub use super::tokenizer::tokenize;
@@ -40,13 +46,124 @@ pub fn step_calc(input: &mut VecDeque<Token>, stack: &mut Vec<Expression>) -> bo
 	}
 }
 
+pub struct RpnStep {
+	token: Token,
+	input: VecDeque<Token>,
+	stack: Vec<Expression>,
+	value: Option<NumberValue>,
+}
+
+impl RpnStep {
+	pub fn token(&self) -> &Token {
+		&self.token
+	}
+	
+	// Tokens still to be consumed, the next one at the back.
+	pub fn input(&self) -> &VecDeque<Token> {
+		&self.input
+	}
+	
+	pub fn stack(&self) -> &Vec<Expression> {
+		&self.stack
+	}
+	
+	// Value of the operation just built, None when a number was pushed.
+	pub fn value(&self) -> Option<&NumberValue> {
+		self.value.as_ref()
+	}
+	
+	// None when an operator found too few operands on the stack.
+	pub fn explanation(&self) -> Option<Explanation> {
+		match &self.token {
+			Token::Number(num) => Some(Explanation::Push(num.clone())),
+			Token::Operator(op) => {
+				let value = self.value.clone()?;
+				let bin = match self.stack.last()? {
+					Expression::BinaryOperation(bin) => bin,
+					_ => return None,
+				};
+				
+				let left = bin.left().calc().ok()?;
+				let right = bin.right().calc().ok()?;
+				Some(Explanation::Apply(op.clone(), left, right, value))
+			}
+		}
+	}
+}
+
+// Yields one step per consumed token. A failure is yielded once and ends the iteration.
+pub struct RpnSteps {
+	input: VecDeque<Token>,
+	stack: Vec<Expression>,
+	control: StepControl,
+	failed: bool,
+}
+
+impl RpnSteps {
+	pub fn budget(mut self, budget: usize) -> Self {
+		self.control.set_budget(budget);
+		self
+	}
+	
+	pub fn cancel_on(mut self, flag: Arc<AtomicBool>) -> Self {
+		self.control.set_cancel(flag);
+		self
+	}
+}
+
+impl Iterator for RpnSteps {
+	type Item = Result<RpnStep, StepFailure>;
+	
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.failed || self.input.is_empty() || !self.control.proceed() {
+			return None;
+		}
+		
+		let token = self.input[self.input.len() - 1].clone();
+		step_calc(&mut self.input, &mut self.stack);
+		
+		let value = match (&token, self.stack.last()) {
+			// The operands were evaluated by earlier steps, so the failure is at the new node.
+			(Token::Operator(op), Some(top @ Expression::BinaryOperation(_))) => match top.calc() {
+				Ok(value) => Some(value),
+				Err(error) => {
+					self.failed = true;
+					let operands = vec![error.lhs().clone(), error.rhs().clone()];
+					let failure = StepFailure::new(top.clone(), NodePath::root(), op.clone(), operands, error);
+					return Some(Err(failure));
+				}
+			},
+			_ => None,
+		};
+		
+		Some(Ok(RpnStep {
+			token,
+			input: self.input.clone(),
+			stack: self.stack.clone(),
+			value,
+		}))
+	}
+}
+
+pub fn steps(input: VecDeque<Token>) -> RpnSteps {
+	RpnSteps {
+		input,
+		stack: Vec::default(),
+		control: StepControl::default(),
+		failed: false,
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use syntax::arithmetic_expression::ArithmeticExpression;
 	use syntax::binary_operation::Operation;
 	use syntax::expression::Expression;
 	
-	use crate::rpn::parser::{step_calc, tokenize};
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::sync::Arc;
+	
+	use crate::rpn::parser::{step_calc, steps, tokenize, Token};
 	
 	#[test]
 	fn step_test() {
@@ -58,7 +175,7 @@ mod tests {
 		assert_eq!(stack.len(), 1);
 		
 		assert!(step_calc(&mut input, &mut stack));
-		let a = stack[0].extract_as_number();
+		stack[0].extract_as_number().number().eq_i32(&10);
 		
 		stack[1].extract_as_number().number().eq_i32(&20);
 		assert_eq!(stack.len(), 2);
@@ -75,4 +192,67 @@ mod tests {
 		let result = tmp.calc().unwrap();
 		result.eq_i32(&-10);
 	}
+	
+	#[test]
+	fn steps_test() {
+		let act: Vec<_> = steps(tokenize("4 2 3 * -").0).map(|s| s.unwrap()).collect();
+		assert_eq!(act.len(), 5);
+		
+		assert!(matches!(act[0].token(), Token::Number(_)));
+		assert!(act[0].value().is_none());
+		assert_eq!(act[0].input().len(), 4);
+		
+		assert!(matches!(act[3].token(), Token::Operator(Operation::Mul)));
+		act[3].value().unwrap().eq_i32(&6);
+		assert_eq!(act[3].stack().len(), 2);
+		
+		act[4].value().unwrap().eq_i32(&-2);
+		assert!(act[4].input().is_empty());
+		assert_eq!(act[4].stack().len(), 1);
+		
+		assert_eq!(steps(tokenize("").0).count(), 0);
+	}
+	
+	#[test]
+	fn explanation_test() {
+		use syntax::explanation::Language;
+		
+		let act: Vec<_> = steps(tokenize("4 2 3 * -").0).map(|s| s.unwrap().explanation().unwrap()).collect();
+		
+		assert_eq!(Language::Ja.catalog().message(&act[0]), "4 をスタックに積みます");
+		assert_eq!(Language::En.catalog().message(&act[0]), "Push 4 onto the stack");
+		assert_eq!(
+			Language::En.catalog().message(&act[4]),
+			"Pop 4 and 6, calculate 4 − 6 = -2 and push the result"
+		);
+		
+		let act = steps(tokenize("1 +").0).last().unwrap().unwrap();
+		assert!(act.explanation().is_none());
+	}
+	
+	#[test]
+	fn steps_control() {
+		assert_eq!(steps(tokenize("4 2 3 * -").0).budget(3).count(), 3);
+		
+		let flag = Arc::new(AtomicBool::new(false));
+		let mut iter = steps(tokenize("4 2 3 * -").0).cancel_on(flag.clone());
+		
+		assert!(iter.next().is_some());
+		flag.store(true, Ordering::Relaxed);
+		assert!(iter.next().is_none());
+	}
+	
+	#[test]
+	fn steps_error() {
+		let act: Vec<_> = steps(tokenize("10 5 5 - / 1 +").0).collect();
+		
+		assert_eq!(act.len(), 5);
+		assert!(act[..4].iter().all(|s| s.is_ok()));
+		
+		let failure = act[4].as_ref().err().unwrap();
+		assert!(matches!(failure.operation(), Operation::Div));
+		failure.operands()[0].eq_i32(&10);
+		failure.operands()[1].eq_i32(&0);
+		assert_eq!(failure.to_string(), "cannot divide by zero here");
+	}
 }
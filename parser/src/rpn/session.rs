@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
+use syntax::expression::Expression;
+use syntax::session::{SessionState, StepSession};
+
+use super::parser::{step_calc, Token};
+
+// Input and stack between two tokens. RPN has a single order, so there is nothing to choose.
+#[derive(Clone)]
+pub struct RpnState {
+	input: VecDeque<Token>,
+	stack: Vec<Expression>,
+}
+
+impl RpnState {
+	pub fn new(input: VecDeque<Token>) -> Self {
+		RpnState {
+			input,
+			stack: Vec::default(),
+		}
+	}
+
+	// Tokens still to be consumed, the next one at the back.
+	pub fn input(&self) -> &VecDeque<Token> {
+		&self.input
+	}
+
+	pub fn stack(&self) -> &Vec<Expression> {
+		&self.stack
+	}
+}
+
+impl SessionState for RpnState {
+	type Choice = Infallible;
+
+	fn advance(&self) -> Option<Self> {
+		let mut next = self.clone();
+
+		if step_calc(&mut next.input, &mut next.stack) {
+			Some(next)
+		} else {
+			None
+		}
+	}
+
+	fn advance_with(&self, choice: &Self::Choice) -> Option<Self> {
+		match *choice {}
+	}
+}
+
+pub fn session(input: VecDeque<Token>) -> StepSession<RpnState> {
+	StepSession::new(RpnState::new(input))
+}
+
+#[cfg(test)]
+mod tests {
+	use syntax::arithmetic_expression::ArithmeticExpression;
+
+	use crate::rpn::parser::tokenize;
+
+	use super::*;
+
+	#[test]
+	fn session_test() {
+		let (input, _) = tokenize("4 2 3 4 5 / + * -");
+		let mut session = session(input);
+
+		assert_eq!(session.goto(9).unwrap().stack().len(), 1);
+		session.current().stack()[0].calc().unwrap().eq_i32(&-2);
+		assert!(session.forward().is_none());
+
+		let state = session.goto(3).unwrap();
+		assert_eq!(state.stack().len(), 3);
+		assert_eq!(state.input().len(), 6);
+
+		assert!(session.back().unwrap().stack().len() == 2);
+		assert!(session.reset().stack().is_empty());
+		assert_eq!(session.len(), 10);
+		assert_eq!(session.node_count(), 10);
+		assert_eq!(session.branches().count(), 1);
+	}
+}
@@ -9,7 +9,7 @@ use syntax::number_value::NumberValue;
 
 type CharIterator<'a> = Peekable<CharIndices<'a>>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Token {
 	Number(NumberValue),
 	Operator(Operation),
@@ -32,7 +32,7 @@ fn get_number(iterator: &mut CharIterator) -> Token {
 	let mut buff = String::new();
 	
 	while let Some((_, c)) = iterator.peek() {
-		if NUM.contains(&c) {
+		if NUM.contains(c) {
 			buff.push(*c);
 		} else {
 			break;
@@ -73,7 +73,7 @@ fn single_tokenize(scr: &str) -> (Option<Token>, &str) {
 	skip_whitespace(&mut ite);
 	let token = try_get_token(&mut ite);
 	
-	return if token.is_some() {
+	if token.is_some() {
 		if let Some((idx, _)) = ite.peek() {
 			(token, &scr[*idx..])
 		} else {
@@ -81,7 +81,7 @@ fn single_tokenize(scr: &str) -> (Option<Token>, &str) {
 		}
 	} else {
 		(token, scr)
-	};
+	}
 }
 
 pub fn tokenize(scr: &str) -> (VecDeque<Token>, &str) {
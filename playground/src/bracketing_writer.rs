@@ -0,0 +1,129 @@
+use std::io::Write;
+
+use anyhow::{Error as AnyError, Result as AnyResult};
+
+use parser::infix::formatter::infix_notation;
+use parser::infix::parser::parse;
+use syntax::bracketing::{bracketings, group_by_value, MAX_NUMBERS};
+use syntax::dot_writer::write_dot;
+use syntax::expression::Expression;
+use syntax::number_value::NumberValue;
+
+fn parse_formula(formula: &str) -> AnyResult<Expression> {
+	let (expr, rem) = parse(formula)?;
+
+	if !rem.trim().is_empty() {
+		return Err(AnyError::msg(format!("Unexpected input:{rem}")));
+	}
+
+	Ok(expr)
+}
+
+fn all_bracketings(expr: &Expression) -> AnyResult<Vec<Expression>> {
+	bracketings(expr).ok_or_else(|| AnyError::msg(format!("Too many numbers to bracket, at most {MAX_NUMBERS}.")))
+}
+
+// One row per value, with every bracketing that gives it.
+pub fn write_bracketing_html<T: Write>(formula: &str, mut writer: T) -> AnyResult<()> {
+	let expr = parse_formula(formula)?;
+	let all = all_bracketings(&expr)?;
+
+	writer.write_fmt(format_args!(
+		r##"<!DOCTYPE html>
+<html lang="ja">
+<head>
+<meta charset="UTF-8">
+<title>{}</title>
+</head>
+<style>
+    .bracketings td, .bracketings th{{
+        border: 1px solid black;
+        padding: 4px 12px;
+    }}
+
+    .bracketings{{
+        border-collapse: collapse;
+    }}
+
+</style>
+
+<body>
+<h1>{}</h1>
+<p>{} bracketing(s)</p>
+<table class="bracketings">
+<tr><th>value</th><th>count</th><th>formulas</th></tr>
+"##,
+		formula,
+		infix_notation(&expr),
+		all.len()
+	))?;
+
+	for group in group_by_value(&all) {
+		let value = match group.value() {
+			Some(NumberValue::Integer(i)) => i.to_string(),
+			None => "-".to_string(),
+		};
+		let formulas: Vec<String> = group.expressions().iter().map(infix_notation).collect();
+
+		writeln!(
+			writer,
+			"<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+			value,
+			formulas.len(),
+			formulas.join("<br>")
+		)?;
+	}
+
+	_ = writer.write(b"</table>\n</body></html>")?;
+
+	Ok(())
+}
+
+// The tree of every bracketing, one graph after another in the same file.
+pub fn write_bracketing_dot<T: Write>(formula: &str, mut writer: T) -> AnyResult<()> {
+	let expr = parse_formula(formula)?;
+
+	for tree in all_bracketings(&expr)? {
+		write_dot(&mut writer, &tree)?;
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use super::*;
+
+	#[test]
+	fn html() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_bracketing_html("1-2-3-4", &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+
+		assert!(act.contains("<p>5 bracketing(s)</p>"));
+		assert!(act.contains("<tr><td>-8</td><td>1</td><td>1 - 2 - 3 - 4</td></tr>\n"));
+		assert!(act.contains("<tr><td>-2</td><td>2</td><td>1 - {2 - {3 - 4}}<br>1 - {2 - 3} - 4</td></tr>\n"));
+		assert!(act.ends_with("</table>\n</body></html>"));
+
+		// The division by zero is a row of its own.
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_bracketing_html("6/2-2", &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+
+		assert!(act.contains("<tr><td>-</td><td>1</td><td>6 / {2 - 2}</td></tr>\n"));
+	}
+
+	#[test]
+	fn dot() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_bracketing_dot("1-2-3-4", &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+
+		assert_eq!(act.matches("digraph arithmetic_tree{").count(), 5);
+
+		let act = write_bracketing_dot("1+2+3+4+5+6+7+8+9+10+11", Cursor::<Vec<u8>>::default()).err().unwrap();
+		assert_eq!(act.to_string(), "Too many numbers to bracket, at most 10.");
+	}
+}
@@ -0,0 +1,194 @@
+use std::io::Write;
+
+use anyhow::Result as AnyResult;
+
+use console_qualifier::{build_string_str, ConsoleColor};
+use parser::infix::formatter::{equation_notation, infix_notation};
+use parser::infix::parser::{parse, parse_equation};
+use syntax::equation::{solve_equation, Solution};
+use syntax::explanation::{explain, English, MessageCatalog};
+use syntax::step::{BracketRemoval, Division, StepFailure, Steps};
+use syntax::step_strategy::StepStrategy;
+use syntax::written::{written, Detail};
+
+fn failure_line(failure: &StepFailure) -> String {
+	let text = match failure.path().resolve(failure.expression()) {
+		Some(node) => format!("{}: {}", infix_notation(node), failure),
+		None => failure.to_string(),
+	};
+
+	build_string_str(&Some(&ConsoleColor::BrightRed), &None, &text)
+}
+
+// One line per step, the failing step in red. With written detail the columns of a
+// multi-digit operation are printed above the line of its step.
+pub fn write_console_steps<T: Write>(
+	formula: &str,
+	strategy: &dyn StepStrategy,
+	removal: BracketRemoval,
+	detail: Detail,
+	division: Division,
+	mut writer: T,
+) -> AnyResult<()> {
+	let recent = parse(formula)?.0;
+	writeln!(writer, "{}", infix_notation(&recent))?;
+
+	for step in Steps::new(&recent, strategy).bracket_removal(removal).division(division) {
+		match step {
+			Ok(step) => {
+				if let (Detail::Written, Some(calculation)) = (detail, written(&step)) {
+					writeln!(writer, "{}", calculation.to_text())?;
+				}
+
+				// Fractions get what was done to them in brackets above each line.
+				if let (Division::Fraction, Some(explanation)) = (division, explain(&step)) {
+					writeln!(writer, "({})", English.message(&explanation))?;
+				}
+
+				writeln!(writer, "= {}", infix_notation(step.after()))?
+			}
+			Err(failure) => writeln!(writer, "{}", failure_line(&failure))?,
+		}
+	}
+
+	Ok(())
+}
+
+// The equation after each step, with what was done to it in brackets above.
+pub fn write_console_equation<T: Write>(formula: &str, mut writer: T) -> AnyResult<()> {
+	let equation = parse_equation(formula)?.0;
+	let trace = solve_equation(&equation)?;
+	writeln!(writer, "{}", equation_notation(&equation))?;
+
+	for step in trace.steps() {
+		writeln!(writer, "({})", step)?;
+		writeln!(writer, "{}", equation_notation(step.equation()))?;
+	}
+
+	match trace.solution() {
+		Solution::Unique(_) => {}
+		other => writeln!(writer, "{}", other)?,
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use console_qualifier::remove_color_definition;
+	use syntax::step_strategy::{LeftmostInnermost, PrecedenceSweep};
+
+	use super::*;
+
+	fn write(formula: &str, strategy: &dyn StepStrategy) -> String {
+		write_with(formula, strategy, BracketRemoval::Separate)
+	}
+
+	fn write_with(formula: &str, strategy: &dyn StepStrategy, removal: BracketRemoval) -> String {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_console_steps(formula, strategy, removal, Detail::Operation, Division::Integer, &mut cursor).unwrap();
+		String::from_utf8(cursor.into_inner()).unwrap()
+	}
+
+	#[test]
+	fn steps() {
+		let act = write("1+2*3-4/2", &PrecedenceSweep);
+		assert_eq!(act, "1 + 2 * 3 - 4 / 2\n= 1 + 6 - 4 / 2\n= 1 + 6 - 2\n= 7 - 2\n= 5\n");
+
+		let act = write("{4-5}*{{6+7}/2}", &LeftmostInnermost);
+		assert!(act.starts_with("{4 - 5} * {{6 + 7} / 2}\n= {-1} * {{6 + 7} / 2}\n= -1 * {{6 + 7} / 2}\n"));
+
+		let act = write_with("{4-5}*{{6+7}/2}", &LeftmostInnermost, BracketRemoval::WithReduction);
+		assert_eq!(act, "{4 - 5} * {{6 + 7} / 2}\n= -1 * {{6 + 7} / 2}\n= -1 * {13 / 2}\n= -1 * 6\n= -6\n");
+	}
+
+	#[test]
+	fn written_detail() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_console_steps("47+36-5", &LeftmostInnermost, BracketRemoval::Separate, Detail::Written, Division::Integer, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+
+		assert_eq!(act, "47 + 36 - 5
+  1
+  47
++ 36
+----
+  83
+= 83 - 5
+  7
+  83
+-  5
+----
+  78
+= 78
+");
+	}
+
+	#[test]
+	fn failure() {
+		let act = write("1+10/{5-5}", &LeftmostInnermost);
+		let last = act.lines().last().unwrap();
+
+		assert!(last.starts_with("\x1B["));
+		assert_eq!(remove_color_definition(last), "10 / 0: cannot divide by zero here");
+		assert_eq!(act.lines().count(), 4);
+	}
+
+	#[test]
+	fn fraction() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_console_steps("1/6+1/3", &LeftmostInnermost, BracketRemoval::Separate, Detail::Operation, Division::Fraction, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+
+		assert_eq!(act, "1 / 6 + 1 / 3
+(Rewrite the fractions over 6, the least common multiple of 6 and 3)
+= 1 / 6 + 2 / 6
+(Add the numerators)
+= {1 + 2} / 6
+(Add 1 + 2 first because it is inside brackets)
+= {3} / 6
+(Remove the brackets around 3)
+= 3 / 6
+(Reduce 3/6 by 3)
+= 1 / 2
+");
+
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_console_steps("2/{3/4}", &PrecedenceSweep, BracketRemoval::WithReduction, Detail::Operation, Division::Fraction, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+
+		assert_eq!(act, "2 / {3 / 4}
+(Multiply by the reciprocal of 3/4)
+= 2 * {4 / 3}
+(Multiply the numerators and the denominators)
+= {2 * 4} / 3
+(Multiply 2 × 4 first because it is inside brackets)
+= 8 / 3
+");
+	}
+
+	#[test]
+	fn equation() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_console_equation("2*x+3=3*{x-1}", &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+
+		assert_eq!(act, "2 * x + 3 = 3 * {x - 1}
+(expand both sides)
+2 * x + 3 = 3 * x - 3
+(move the unknown to the left and the numbers to the right, changing their signs)
+2 * x - 3 * x = -3 - 3
+(combine like terms)
+-1 * x = -6
+(divide both sides by -1)
+x = 6
+");
+
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_console_equation("2*x=x+x", &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		assert!(act.ends_with("every number is a solution\n"));
+	}
+}
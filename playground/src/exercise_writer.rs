@@ -0,0 +1,69 @@
+use std::io::Write;
+
+use anyhow::Result as AnyResult;
+
+use parser::infix::formatter::minimal_infix_notation;
+use syntax::arithmetic_expression::ArithmeticExpression;
+use syntax::generator::{ExerciseConstraints, ExerciseGenerator};
+use syntax::number_value::NumberValue;
+
+pub const DEFAULT_EXERCISE_COUNT: usize = 10;
+
+// A numbered worksheet, then the answers under the same numbers.
+// Fewer exercises are written when the constraints cannot be met.
+pub fn write_exercises<T: Write>(
+	seed: u64,
+	count: usize,
+	constraints: ExerciseConstraints,
+	mut writer: T,
+) -> AnyResult<()> {
+	let exercises: Vec<_> = ExerciseGenerator::new(seed, constraints).take(count).collect();
+
+	for (idx, expr) in exercises.iter().enumerate() {
+		writeln!(writer, "{}. {}", idx + 1, minimal_infix_notation(expr))?;
+	}
+
+	writeln!(writer)?;
+
+	for (idx, expr) in exercises.iter().enumerate() {
+		let NumberValue::Integer(answer) = expr.calc()?;
+		writeln!(writer, "{}. {}", idx + 1, answer)?;
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use parser::infix::parser::parse;
+	use syntax::binary_operation::Operation;
+
+	use super::*;
+
+	fn write(seed: u64, constraints: ExerciseConstraints) -> String {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_exercises(seed, 4, constraints, &mut cursor).unwrap();
+		String::from_utf8(cursor.into_inner()).unwrap()
+	}
+
+	#[test]
+	fn worksheet() {
+		let constraints = ExerciseConstraints::default().operators(&[Operation::Add, Operation::Mul]).depth(2);
+		let act = write(3, constraints.clone());
+
+		assert_eq!(act, write(3, constraints));
+
+		let (questions, answers) = act.split_once("\n\n").unwrap();
+		assert_eq!(questions.lines().count(), 4);
+
+		// Each question reads back to its answer.
+		for (question, answer) in questions.lines().zip(answers.lines()) {
+			let (number, formula) = question.split_once(". ").unwrap();
+			let NumberValue::Integer(value) = parse(formula).unwrap().0.calc().unwrap();
+
+			assert_eq!(answer, format!("{}. {}", number, value));
+		}
+	}
+}
@@ -0,0 +1,145 @@
+use std::io::Write;
+
+use anyhow::{Error as AnyError, Result as AnyResult};
+
+use parser::infix::parser::parse;
+use syntax::answer_check::{check_answer, Mistake};
+use syntax::expression::Expression;
+use syntax::misconception::diagnose;
+
+fn parse_line(scr: &str) -> AnyResult<Expression> {
+	let (expr, rem) = parse(scr.trim())?;
+
+	if !rem.trim().is_empty() {
+		return Err(AnyError::msg(format!("Unexpected input:{rem}")));
+	}
+
+	Ok(expr)
+}
+
+// One submission: the name and formula on the first line, then the student's lines.
+fn grade(block: &[&str]) -> (String, bool, String) {
+	let (name, formula) = match block[0].split_once(':') {
+		Some((name, formula)) => (name.trim().to_string(), formula),
+		None => (String::from("?"), block[0]),
+	};
+
+	let formula = match parse_line(formula) {
+		Ok(expr) => expr,
+		Err(e) => return (name, false, format!("formula: {e}")),
+	};
+
+	let mut lines = Vec::<Expression>::new();
+
+	for (idx, line) in block[1..].iter().enumerate() {
+		match parse_line(line) {
+			Ok(expr) => lines.push(expr),
+			Err(e) => return (name, false, format!("line {}: {e}", idx + 1)),
+		}
+	}
+
+	match check_answer(&formula, &lines) {
+		Err(e) => (name, false, format!("formula: {}", e.message())),
+		Ok(check) => match check.error() {
+			Some((line, mistake)) => {
+				let mut message = format!("line {line}: {mistake}");
+
+				// A wrong value may come from a known mistake in the order of calculation.
+				if let Mistake::WrongValue(_, actual) = mistake {
+					let causes: Vec<String> = diagnose(&formula, actual).iter().map(|m| m.to_string()).collect();
+
+					if !causes.is_empty() {
+						message.push_str(&format!(" (perhaps {})", causes.join(" or ")));
+					}
+				}
+
+				(name, false, message)
+			}
+			None if check.is_finished() => (name, true, String::from("ok")),
+			None => (name, false, String::from("not finished")),
+		},
+	}
+}
+
+// Submissions are separated by blank lines. Each starts with "name: formula",
+// followed by one expression per line.
+pub fn write_grade_report<T: Write>(scr: &str, mut writer: T) -> AnyResult<()> {
+	let lines: Vec<&str> = scr.lines().collect();
+	let blocks: Vec<&[&str]> = lines
+		.split(|line| line.trim().is_empty())
+		.filter(|block| !block.is_empty())
+		.collect();
+
+	let mut correct = 0;
+
+	for block in blocks.iter() {
+		let (name, ok, message) = grade(block);
+
+		if ok {
+			correct += 1;
+		}
+
+		writeln!(writer, "{name}: {message}")?;
+	}
+
+	writeln!(writer, "{correct} / {} correct", blocks.len())?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use crate::test_helper::strict_assert_text;
+
+	use super::*;
+
+	const SUBMISSIONS: &str = "alice: 1+2*3
+1+6
+7
+
+bob: 1+2*3
+3*3
+9
+
+frank: 20-{3+5}*2
+20-3+10
+27
+
+carol: {1+2}*3
+3*3
+
+dave: 1+2+3
+1+5
+6
+
+erin: 4/2
+4/
+";
+
+	#[test]
+	fn report() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_grade_report(SUBMISSIONS, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+
+		const EXPECTED: &str = "alice: ok
+bob: line 1: the value changed from 7 to 9 (perhaps calculating from left to right, ignoring that × and ÷ come first)
+frank: line 1: the value changed from 4 to 27 (perhaps ignoring the brackets)
+carol: not finished
+dave: line 1: the previous line does not lead to this one by the rules of calculation
+erin: line 1: StreamError:UnexpectedParse
+1 / 6 correct
+";
+		strict_assert_text(&act, EXPECTED);
+	}
+
+	#[test]
+	fn empty() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_grade_report("\n\n", &mut cursor).unwrap();
+
+		assert_eq!(String::from_utf8(cursor.into_inner()).unwrap(), "0 / 0 correct\n");
+	}
+}
@@ -4,11 +4,19 @@ use std::process::{Command, Stdio};
 
 use anyhow::Result as AnyResult;
 
-use parser::infix::formatter::minimal_infix_notation;
-use parser::infix::parser::parse;
-use syntax::arithmetic_expression::ArithmeticExpression;
-use syntax::dot_writer::write_dot;
+use parser::infix::formatter::{equation_notation, infix_notation, minimal_infix_notation};
+use parser::infix::parser::{parse, parse_equation};
+use syntax::dot_writer::{write_dot, write_equation_dot, write_factor_tree_dot};
+use syntax::equation::{solve_equation, Equation, Solution};
+use syntax::binary_operation::Operation;
+use syntax::explanation::{explain, MessageCatalog};
 use syntax::expression::Expression;
+use syntax::metrics::Metrics;
+use syntax::number_theory::TheoryTrace;
+use syntax::rewrite::{rewrite, RewriteStrategy, Rule};
+use syntax::step::{BracketRemoval, Division, StepFailure, Steps};
+use syntax::step_strategy::StepStrategy;
+use syntax::written::{written, Detail, RowKind, WrittenCalculation};
 
 fn generate_svg(scr: &str) -> IoResult<String> {
 	let mut proc = Command::new("dot").args(&["-Tsvg"]).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
@@ -106,11 +114,29 @@ fn gen_svg(expr: &Expression) -> AnyResult<String> {
 	Ok(txt)
 }
 
-fn write_step(recent: Option<&str>, expr: &Expression, writer: &mut dyn Write) -> AnyResult<()> {
-	let current_expr = minimal_infix_notation(&expr);
+fn write_step(
+	recent: Option<&str>,
+	caption: Option<&str>,
+	explanation: Option<&str>,
+	calculation: Option<&WrittenCalculation>,
+	expr: &Expression,
+	notation: fn(&Expression) -> String,
+	writer: &mut dyn Write,
+) -> AnyResult<()> {
+	let current_expr = notation(expr);
+	_ = writer.write(br##"<div class="step">
+"##)?;
+	
+	if let Some(caption) = caption {
+		writer.write_fmt(format_args!(
+			r#"    <p class="caption">{}</p>
+"#,
+			caption.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+		))?;
+	}
+	
 	_ = writer.write(
-		br##"<div class="step">
-    <h1 class="formula">
+		br##"    <h1 class="formula">
 "##,
 	)?;
 	
@@ -130,6 +156,18 @@ fn write_step(recent: Option<&str>, expr: &Expression, writer: &mut dyn Write) -
 	}
 	_ = writer.write(b"</h1>")?;
 	
+	if let Some(explanation) = explanation {
+		writer.write_fmt(format_args!(
+			r#"<p class="explanation">{}</p>
+"#,
+			explanation.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+		))?;
+	}
+	
+	if let Some(calculation) = calculation {
+		write_written(calculation, writer)?;
+	}
+	
 	let svg = gen_svg(&expr)?;
 	_ = writer.write(svg.as_bytes())?;
 	
@@ -138,7 +176,41 @@ fn write_step(recent: Option<&str>, expr: &Expression, writer: &mut dyn Write) -
 	Ok(())
 }
 
-pub fn write_step_infix_html<T: Write>(formula: &str, mut writer: T) -> AnyResult<()> {
+// One cell per digit, so the columns line up.
+fn write_written(calculation: &WrittenCalculation, writer: &mut dyn Write) -> AnyResult<()> {
+	_ = writer.write(b"<table class=\"written\">\n")?;
+	
+	for row in calculation.rows() {
+		match row.kind() {
+			RowKind::Rule => writer.write_fmt(format_args!(
+				"<tr class=\"rule\"><td colspan=\"{}\"></td></tr>\n",
+				row.cells().len()
+			))?,
+			kind => {
+				let class = if kind == RowKind::Carry { "carry" } else { "number" };
+				let cells: String = row.cells().iter().map(|c| format!("<td>{}</td>", c)).collect();
+				writer.write_fmt(format_args!("<tr class=\"{}\">{}</tr>\n", class, cells))?;
+			}
+		}
+	}
+	
+	_ = writer.write(b"</table>\n")?;
+	
+	Ok(())
+}
+
+// The expression is left as it was, captioned with the node that could not be reduced.
+fn write_failure(failure: &StepFailure, notation: fn(&Expression) -> String, writer: &mut dyn Write) -> AnyResult<()> {
+	let recent_expr = notation(failure.expression());
+	let caption = match failure.path().resolve(failure.expression()) {
+		Some(node) => format!("{}: {}", infix_notation(node), failure),
+		None => failure.to_string(),
+	};
+	
+	write_step(Some(&recent_expr), Some(&caption), None, None, failure.expression(), notation, writer)
+}
+
+fn write_head(formula: &str, writer: &mut dyn Write) -> AnyResult<()> {
 	writer.write_fmt(format_args!(
 		r##"<!DOCTYPE html>
 <html lang="ja">
@@ -156,6 +228,45 @@ pub fn write_step_infix_html<T: Write>(formula: &str, mut writer: T) -> AnyResul
         margin-bottom: 10px;
         border-bottom: 5px solid darkgray;
     }}
+
+    .explanation{{
+        font-size: large;
+    }}
+
+    .written td{{
+        width: 1em;
+        text-align: center;
+        font-family: monospace;
+        font-size: x-large;
+    }}
+
+    .written .carry td{{
+        font-size: small;
+        color: red;
+    }}
+
+    .written .rule td{{
+        border-top: 2px solid black;
+    }}
+
+    .metrics{{
+        color: dimgray;
+    }}
+
+    .fraction{{
+        display: inline-block;
+        vertical-align: middle;
+        text-align: center;
+    }}
+
+    .fraction .numerator{{
+        display: block;
+        border-bottom: 2px solid black;
+    }}
+
+    .fraction .denominator{{
+        display: block;
+    }}
     
 </style>
 
@@ -163,17 +274,128 @@ pub fn write_step_infix_html<T: Write>(formula: &str, mut writer: T) -> AnyResul
 		formula
 	))?;
 	
-	let mut recent = parse(formula)?.0.simplify();
-	write_step(None, &recent, &mut writer)?;
+	Ok(())
+}
+
+// The header shows the metrics of the formula as one line.
+fn write_step_header(formula: &str, expr: &Expression, writer: &mut dyn Write) -> AnyResult<()> {
+	write_head(formula, writer)?;
+	
+	writer.write_fmt(format_args!(
+		r#"<p class="metrics">{}</p>
+"#,
+		Metrics::of(expr).to_string().replace('\n', ", ")
+	))?;
+	
+	Ok(())
+}
+
+// Brackets are kept as written and stay until their content is a number.
+pub fn write_step_infix_html<T: Write>(
+	formula: &str,
+	strategy: &dyn StepStrategy,
+	removal: BracketRemoval,
+	catalog: &dyn MessageCatalog,
+	detail: Detail,
+	division: Division,
+	mut writer: T,
+) -> AnyResult<()> {
+	let recent = parse(formula)?.0;
+	write_step_header(formula, &recent, &mut writer)?;
+	
+	// Fractions are drawn stacked.
+	let notation = match division {
+		Division::Integer => infix_notation,
+		Division::Fraction => fraction_notation,
+	};
+	
+	write_step(None, None, None, None, &recent, notation, &mut writer)?;
+	
+	for step in Steps::new(&recent, strategy).bracket_removal(removal).division(division) {
+		match step {
+			Ok(step) => {
+				let recent_expr = notation(step.before());
+				let explanation = explain(&step).map(|e| catalog.message(&e));
+				let calculation = match detail {
+					Detail::Operation => None,
+					Detail::Written => written(&step),
+				};
+				
+				write_step(
+					Some(&recent_expr),
+					None,
+					explanation.as_deref(),
+					calculation.as_ref(),
+					step.after(),
+					notation,
+					&mut writer,
+				)?;
+			}
+			Err(failure) => write_failure(&failure, notation, &mut writer)?,
+		}
+	}
+	
+	_ = writer.write(br"</body></html>")?;
+	
+	Ok(())
+}
+
+// A quotient is drawn as a stacked fraction, whose numerator and denominator need no brackets.
+fn fraction_notation(expr: &Expression) -> String {
+	fn stacked(expr: &Expression) -> String {
+		match expr {
+			Expression::Bracket(bracket) => fraction_notation(bracket.expression()),
+			_ => fraction_notation(expr),
+		}
+	}
+	
+	match expr {
+		Expression::BinaryOperation(bin) if bin.operation() == &Operation::Div => format!(
+			r#"<span class="fraction"><span class="numerator">{}</span><span class="denominator">{}</span></span>"#,
+			stacked(bin.left()),
+			stacked(bin.right())
+		),
+		Expression::BinaryOperation(bin) => {
+			let op = match bin.operation() {
+				Operation::Add => "+",
+				Operation::Sub => "-",
+				Operation::Mul => "*",
+				Operation::Div => "/",
+			};
+			
+			format!("{} {} {}", fraction_notation(bin.left()), op, fraction_notation(bin.right()))
+		}
+		Expression::Bracket(bracket) => format!("{{{}}}", fraction_notation(bracket.expression())),
+		_ => infix_notation(expr),
+	}
+}
+
+// Each step reduces every ready node at once and lists them in the caption.
+pub fn write_parallel_step_infix_html<T: Write>(
+	formula: &str,
+	removal: BracketRemoval,
+	mut writer: T,
+) -> AnyResult<()> {
+	let mut recent = parse(formula)?.0;
+	write_step_header(formula, &recent, &mut writer)?;
+	
+	write_step(None, None, None, None, &recent, infix_notation, &mut writer)?;
 	
 	loop {
-		let (expr, is_proceed) = recent.step_calc();
+		let (expr, reduced) = recent.parallel_step_with(removal);
 		
-		if !is_proceed {
+		if reduced.is_empty() {
+			// Only failing operations are left, so show the first of them.
+			if let Some(Err(failure)) = recent.steps().next() {
+				write_failure(&failure, infix_notation, &mut writer)?;
+			}
+			
 			break;
 		}
-		let recent_expr = minimal_infix_notation(&recent);
-		write_step(Some(&recent_expr), &expr, &mut writer)?;
+		
+		let recent_expr = infix_notation(&recent);
+		let caption = reduced.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ");
+		write_step(Some(&recent_expr), Some(&caption), None, None, &expr, infix_notation, &mut writer)?;
 		
 		recent = expr;
 	}
@@ -183,12 +405,124 @@ pub fn write_step_infix_html<T: Write>(formula: &str, mut writer: T) -> AnyResul
 	Ok(())
 }
 
+// Every rule firing becomes one step, captioned with the rule that fired.
+pub fn write_rewrite_infix_html<T: Write>(
+	formula: &str,
+	rules: &[Rule],
+	strategy: RewriteStrategy,
+	mut writer: T,
+) -> AnyResult<()> {
+	let expr = parse(formula)?.0;
+	write_step_header(formula, &expr, &mut writer)?;
+	
+	write_step(None, None, None, None, &expr, minimal_infix_notation, &mut writer)?;
+	
+	let (_, steps) = rewrite(&expr, rules, strategy);
+	
+	for step in steps.iter() {
+		let recent_expr = minimal_infix_notation(step.before());
+		let caption = format!("{} at {}", rules[step.rule()], step.path());
+		write_step(Some(&recent_expr), Some(&caption), None, None, step.after(), minimal_infix_notation, &mut writer)?;
+	}
+	
+	_ = writer.write(br"</body></html>")?;
+	
+	Ok(())
+}
+
+fn write_equation_step(caption: Option<&str>, equation: &Equation, tree: bool, writer: &mut dyn Write) -> AnyResult<()> {
+	_ = writer.write(br##"<div class="step">
+"##)?;
+	
+	if let Some(caption) = caption {
+		writer.write_fmt(format_args!(
+			r#"    <p class="caption">{}</p>
+"#,
+			caption
+		))?;
+	}
+	
+	writer.write_fmt(format_args!(
+		r#"    <h1 class="formula">
+{}
+		</h1>"#,
+		equation_notation(equation)
+	))?;
+	
+	if tree {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_equation_dot(&mut cursor, equation)?;
+		let svg = extract_svg_element(&generate_svg(&String::from_utf8(cursor.into_inner())?)?)?;
+		_ = writer.write(svg.as_bytes())?;
+	}
+	
+	_ = writer.write(b"</div>")?;
+	
+	Ok(())
+}
+
+// One step per transformation of the equation, captioned with what was done to both sides.
+pub fn write_equation_html<T: Write>(formula: &str, mut writer: T) -> AnyResult<()> {
+	let equation = parse_equation(formula)?.0;
+	let trace = solve_equation(&equation)?;
+	write_head(formula, &mut writer)?;
+	
+	write_equation_step(None, &equation, true, &mut writer)?;
+	
+	for step in trace.steps() {
+		write_equation_step(Some(&step.to_string()), step.equation(), true, &mut writer)?;
+	}
+	
+	let solution = match trace.solution() {
+		Solution::Unique(value) => format!("{} = {}", trace.unknown(), value),
+		other => other.to_string(),
+	};
+	writer.write_fmt(format_args!(
+		r#"<p class="solution">{}</p>
+"#,
+		solution
+	))?;
+	
+	_ = writer.write(br"</body></html>")?;
+	
+	Ok(())
+}
+
+// The rows of gcd, lcm or factor without trees, then the factor tree if there is one.
+pub fn write_theory_html<T: Write>(title: &str, trace: &TheoryTrace, mut writer: T) -> AnyResult<()> {
+	write_head(title, &mut writer)?;
+	
+	for step in trace.steps() {
+		write_equation_step(Some(step.caption()), step.equation(), false, &mut writer)?;
+	}
+	
+	writer.write_fmt(format_args!(
+		r#"<p class="solution">{}</p>
+"#,
+		trace.result()
+	))?;
+	
+	if let Some(tree) = trace.tree() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_factor_tree_dot(&mut cursor, tree)?;
+		let svg = extract_svg_element(&generate_svg(&String::from_utf8(cursor.into_inner())?)?)?;
+		_ = writer.write(svg.as_bytes())?;
+	}
+	
+	_ = writer.write(br"</body></html>")?;
+	
+	Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 	use std::fs::File;
 	
 	use once_cell::sync::Lazy;
 	
+	use syntax::explanation::{English, Japanese};
+	use syntax::step_strategy::{LeftmostInnermost, PrecedenceSweep};
+	
 	use crate::test_helper::strict_assert_text;
 	use crate::test_writer::TestWriterEnvelope;
 	
@@ -269,20 +603,25 @@ mod tests {
 		strict_assert_text(&act, expected)
 	}
 	
+	// The artifact keeps the text only, so it does not depend on the graphviz version.
+	fn strip_svg(scr: &str) -> String {
+		regex::Regex::new(r"(?s)<svg.*?</svg>").unwrap().replace_all(scr, "").to_string()
+	}
+	
 	#[test]
 	fn step() {
 		fn reference() {
 			const FORMULA: &str = "{1+2*30}-{{42+4-5}*{{6+7}/2}}*{30+40*{20+4-1}}";
 			let mut cursor = create_cursor();
 			
-			write_step_infix_html(FORMULA, &mut cursor).unwrap();
+			write_step_infix_html(FORMULA, &LeftmostInnermost, BracketRemoval::Separate, &Japanese, Detail::Operation, Division::Integer, &mut cursor).unwrap();
 			
 			let act = String::from_utf8(cursor.into_inner()).unwrap();
 			let mut file = File::open("./test_artifacts/step_output.txt").unwrap();
 			let mut expected = String::default();
 			file.read_to_string(&mut expected).unwrap();
 			
-			strict_assert_text(&act, &expected);
+			strict_assert_text(&strip_svg(&act), &expected);
 		}
 		
 		fn entity() {
@@ -290,7 +629,7 @@ mod tests {
 			
 			const FORMULA: &str = "{1+2*30}-{{42+4-5}*{{6+7}/2}}*{30+40*{20+4-1}}";
 			
-			write_step_infix_html(FORMULA, envelope.publish().unwrap()).unwrap();
+			write_step_infix_html(FORMULA, &LeftmostInnermost, BracketRemoval::Separate, &Japanese, Detail::Operation, Division::Integer, envelope.publish().unwrap()).unwrap();
 			
 			let mut act = String::new();
 			envelope.read_string(&mut act).unwrap();
@@ -300,10 +639,208 @@ mod tests {
 			let mut expected = String::default();
 			file.read_to_string(&mut expected).unwrap();
 			
-			strict_assert_text(&act, &expected);
+			strict_assert_text(&strip_svg(&act), &expected);
 		}
 		
 		reference();
 		entity();
 	}
+	
+	#[test]
+	fn rewrite_step() {
+		const RULES: &str = "x * 1 => x\na*c + b*c => {a+b}*c";
+		let rules = parser::pattern::rules::parse_rules(RULES).unwrap();
+		
+		let mut cursor = create_cursor();
+		write_rewrite_infix_html("{2*7+3*7}*1", &rules, RewriteStrategy::Fixpoint(100), &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		assert_eq!(act.matches(r#"<div class="step">"#).count(), 3);
+		assert!(act.contains(r#"<p class="caption">a * c + b * c =&gt; {a + b} * c at /L/I</p>"#));
+		assert!(act.contains(r#"<p class="caption">x * 1 =&gt; x at /</p>"#));
+		assert!(act.ends_with("</body></html>"));
+	}
+	
+	#[test]
+	fn precedence_step() {
+		let mut cursor = create_cursor();
+		write_step_infix_html("1+2*3-4/2", &PrecedenceSweep, BracketRemoval::Separate, &Japanese, Detail::Operation, Division::Integer, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		let formulas: Vec<&str> = act
+			.lines()
+			.filter(|line| line.ends_with("<br/>"))
+			.map(|line| line.trim_end_matches("<br/>"))
+			.collect();
+		
+		assert_eq!(formulas, vec!["1 + 2 * 3 - 4 / 2", "1 + 6 - 4 / 2", "1 + 6 - 2", "7 - 2"]);
+		assert!(act.contains(r#"<p class="explanation">2 × 3 を先に計算します（かけ算はたし算より先）</p>"#));
+		assert!(act.contains(r#"<p class="explanation">4 ÷ 2 を先に計算します（わり算はひき算より先）</p>"#));
+	}
+	
+	#[test]
+	fn written_step() {
+		let mut cursor = create_cursor();
+		write_step_infix_html("47*36+5", &LeftmostInnermost, BracketRemoval::Separate, &Japanese, Detail::Written, Division::Integer, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		// 1692 + 5 is written out, 47 * 36 as well.
+		assert_eq!(act.matches(r#"<table class="written">"#).count(), 2);
+		assert!(act.contains(r#"<tr class="number"><td>×</td><td></td><td></td><td></td><td>3</td><td>6</td></tr>"#));
+		assert!(act.contains(r#"<tr class="number"><td></td><td></td><td></td><td>2</td><td>8</td><td>2</td></tr>"#));
+		assert!(act.contains(r#"<tr class="rule"><td colspan="6"></td></tr>"#));
+		
+		let mut cursor = create_cursor();
+		write_step_infix_html("47*36+5", &LeftmostInnermost, BracketRemoval::Separate, &Japanese, Detail::Operation, Division::Integer, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		assert!(!act.contains(r#"<table class="written">"#));
+	}
+	
+	#[test]
+	fn metrics_header() {
+		let mut cursor = create_cursor();
+		write_step_infix_html("1+2", &LeftmostInnermost, BracketRemoval::Separate, &Japanese, Detail::Operation, Division::Integer, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		assert!(act.contains(r#"<body><p class="metrics">depth: 2, nodes: 3, brackets: 0, "#));
+		assert!(act.contains(r#"carries: 0, difficulty: 5</p>"#));
+	}
+	
+	#[test]
+	fn explanation_step() {
+		let mut cursor = create_cursor();
+		write_step_infix_html("{1+2}*3", &LeftmostInnermost, BracketRemoval::Separate, &English, Detail::Operation, Division::Integer, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		let explanations: Vec<&str> = regex::Regex::new(r#"<p class="explanation">(.*?)</p>"#)
+			.unwrap()
+			.captures_iter(&act)
+			.map(|c| c.get(1).unwrap().as_str())
+			.collect();
+		
+		assert_eq!(
+			explanations,
+			vec!["Add 1 + 2 first because it is inside brackets", "Remove the brackets around 3", "Multiply 3 × 3"]
+		);
+	}
+	
+	#[test]
+	fn failure_step() {
+		let mut cursor = create_cursor();
+		write_step_infix_html("1+10/{5-5}", &LeftmostInnermost, BracketRemoval::Separate, &Japanese, Detail::Operation, Division::Integer, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		assert_eq!(act.matches(r#"<div class="step">"#).count(), 4);
+		assert!(act.contains(r#"<p class="caption">10 / 0: cannot divide by zero here</p>"#));
+		assert!(act.contains("1 + 10 / {0}<br/>"));
+		assert!(act.ends_with("</body></html>"));
+		
+		let mut cursor = create_cursor();
+		write_parallel_step_infix_html("1+10/{5-5}", BracketRemoval::Separate, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		assert!(act.contains(r#"<p class="caption">10 / 0: cannot divide by zero here</p>"#));
+		assert!(act.ends_with("</body></html>"));
+	}
+	
+	#[test]
+	fn equation_step() {
+		let mut cursor = create_cursor();
+		write_equation_html("2*x+3=3*{x-1}", &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		assert_eq!(act.matches(r#"<div class="step">"#).count(), 5);
+		assert!(act.contains("2 * x + 3 = 3 * {x - 1}\n"));
+		assert!(act.contains(r#"<p class="caption">divide both sides by -1</p>"#));
+		assert!(act.contains("x = 6\n\t\t</h1>"));
+		assert!(act.contains(r#"<p class="solution">x = 6</p>"#));
+		assert!(act.ends_with("</body></html>"));
+		
+		let mut cursor = create_cursor();
+		write_equation_html("x+1=x", &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		assert!(act.contains(r#"<p class="solution">no solution</p>"#));
+		
+		assert!(write_equation_html("x*x=1", create_cursor()).is_err());
+	}
+	
+	#[test]
+	fn theory_step() {
+		let mut cursor = create_cursor();
+		write_theory_html("gcd(48,18)", &syntax::number_theory::gcd(48, 18).unwrap(), &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		assert_eq!(act.matches(r#"<div class="step">"#).count(), 3);
+		assert!(act.contains(r#"<p class="caption">divide 18 by 12</p>"#));
+		assert!(act.contains("18 = 1 * 12 + 6\n"));
+		assert!(act.contains(r#"<p class="solution">gcd(48, 18) = 6</p>"#));
+		assert!(!act.contains("<svg"));
+		
+		let mut cursor = create_cursor();
+		write_theory_html("factor(12)", &syntax::number_theory::factor(12).unwrap(), &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		assert!(act.contains(r#"<p class="solution">12 = 2^2 * 3</p>"#));
+		assert_eq!(act.matches("<svg").count(), 1);
+		assert!(act.ends_with("</body></html>"));
+	}
+	
+	#[test]
+	fn fraction_step() {
+		let mut cursor = create_cursor();
+		write_step_infix_html("1/3+1/4", &LeftmostInnermost, BracketRemoval::Separate, &Japanese, Detail::Operation, Division::Fraction, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		let stacked = |n: i32, d: i32| {
+			format!(r#"<span class="fraction"><span class="numerator">{n}</span><span class="denominator">{d}</span></span>"#)
+		};
+		
+		assert_eq!(act.matches(r#"<div class="step">"#).count(), 5);
+		assert!(act.contains(r#"<p class="explanation">3 と 4 の最小公倍数 12 で通分します</p>"#));
+		assert!(act.contains(&format!("{} + {}\n", stacked(4, 12), stacked(3, 12))));
+		assert!(act.contains(r#"<span class="fraction"><span class="numerator">4 + 3</span><span class="denominator">12</span></span>"#));
+		assert!(act.contains(&format!("{}\n\t\t</h1>", stacked(7, 12))));
+		assert!(!act.contains(" / "));
+		assert!(act.ends_with("</body></html>"));
+		
+		let mut cursor = create_cursor();
+		write_step_infix_html("1/3+1/0", &LeftmostInnermost, BracketRemoval::Separate, &Japanese, Detail::Operation, Division::Fraction, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		assert!(act.contains(r#"<p class="caption">1 / 0: cannot divide by zero here</p>"#));
+		
+		let mut cursor = create_cursor();
+		write_step_infix_html("2/{3/4}", &LeftmostInnermost, BracketRemoval::Separate, &English, Detail::Operation, Division::Fraction, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		assert!(act.contains(&format!("2 * {{{}}}\n", stacked(4, 3))));
+		assert!(act.contains(r#"<p class="explanation">Multiply by the reciprocal of 3/4</p>"#));
+		assert!(act.contains(&format!("{}\n\t\t</h1>", stacked(8, 3))));
+	}
+	
+	#[test]
+	fn parallel_step() {
+		const FORMULA: &str = "{1+2*30}-{{42+4-5}*{{6+7}/2}}*{30+40*{20+4-1}}";
+		
+		let mut cursor = create_cursor();
+		write_parallel_step_infix_html(FORMULA, BracketRemoval::Separate, &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		let mut cursor = create_cursor();
+		write_step_infix_html(FORMULA, &LeftmostInnermost, BracketRemoval::Separate, &Japanese, Detail::Operation, Division::Integer, &mut cursor).unwrap();
+		let single = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		let count = act.matches(r#"<div class="step">"#).count();
+		assert_eq!(count, 9);
+		assert!(count < single.matches(r#"<div class="step">"#).count());
+		assert!(act.contains(r#"<p class="caption">/L/I/R, /R/L/I/L/I/L, /R/L/I/R/I/L/I, /R/R/I/R/R/I/L</p>"#));
+		assert!(act.contains(r#"<p class="caption">/L/I, /R/L/I/L/I, /R/L/I/R/I/L, /R/R/I/R/R/I</p>"#));
+		
+		let mut cursor = create_cursor();
+		write_parallel_step_infix_html(FORMULA, BracketRemoval::WithReduction, &mut cursor).unwrap();
+		let merged = String::from_utf8(cursor.into_inner()).unwrap();
+		
+		assert!(merged.matches(r#"<div class="step">"#).count() < count);
+		assert!(merged.contains(r#"<p class="caption">/L/I/R, /R/L/I/L/I/L, /R/L/I/R/I/L/I, /R/L/I/R/I/L, /R/R/I/R/R/I/L</p>"#));
+	}
 }
@@ -0,0 +1,50 @@
+use std::io::Write;
+
+use anyhow::{Error as AnyError, Result as AnyResult};
+
+use parser::infix::formatter::infix_notation;
+use parser::infix::parser::parse;
+use syntax::metrics::Metrics;
+
+pub fn write_metrics<T: Write>(formula: &str, mut writer: T) -> AnyResult<()> {
+	let (expr, rem) = parse(formula)?;
+
+	if !rem.trim().is_empty() {
+		return Err(AnyError::msg(format!("Unexpected input:{rem}")));
+	}
+
+	writeln!(writer, "{}", infix_notation(&expr))?;
+	writeln!(writer, "{}", Metrics::of(&expr))?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use crate::test_helper::strict_assert_text;
+
+	use super::*;
+
+	#[test]
+	fn metrics() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_metrics("47-3*{8+5}", &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+
+		const EXPECTED: &str = "47 - 3 * {8 + 5}
+depth: 5
+nodes: 8
+brackets: 1
+steps (leftmost): 4
+steps (precedence): 4
+steps (rightmost): 4
+max magnitude: 47
+precedence traps: 1
+carries: 1
+difficulty: 21
+";
+		strict_assert_text(&act, EXPECTED);
+	}
+}
@@ -0,0 +1,110 @@
+use std::io::Write;
+
+use anyhow::{Error as AnyError, Result as AnyResult};
+
+use parser::infix::formatter::equation_notation;
+use syntax::dot_writer::write_factor_tree_dot;
+use syntax::number_theory::{factor, gcd, lcm, TheoryTrace};
+
+use crate::puzzle_writer::parse_numbers;
+
+// gcd(48,18), lcm(4,6,10) or factor(60).
+pub fn parse_operation(input: &str) -> AnyResult<TheoryTrace> {
+	let invalid = || AnyError::msg(format!("Invalid operation:{input}"));
+	let (name, rest) = input.trim().split_once('(').ok_or_else(invalid)?;
+	let numbers = parse_numbers(rest.strip_suffix(')').ok_or_else(invalid)?)?;
+
+	let trace = match (name.trim(), numbers.as_slice()) {
+		("gcd", [a, b]) => gcd(*a, *b)?,
+		("lcm", _) => lcm(&numbers)?,
+		("factor", [n]) => factor(*n)?,
+		_ => return Err(invalid()),
+	};
+
+	Ok(trace)
+}
+
+// The row after each step, with what was done to it in brackets above.
+pub fn write_console_theory<T: Write>(input: &str, mut writer: T) -> AnyResult<()> {
+	let trace = parse_operation(input)?;
+
+	for step in trace.steps() {
+		writeln!(writer, "({})", step.caption())?;
+		writeln!(writer, "{}", equation_notation(step.equation()))?;
+	}
+
+	writeln!(writer, "{}", trace.result())?;
+
+	Ok(())
+}
+
+pub fn write_factor_dot<T: Write>(input: &str, mut writer: T) -> AnyResult<()> {
+	let trace = parse_operation(input)?;
+	let tree = trace
+		.tree()
+		.ok_or_else(|| AnyError::msg("Only factor has a factor tree."))?;
+
+	write_factor_tree_dot(&mut writer, tree)?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use crate::test_helper::strict_assert_text;
+
+	use super::*;
+
+	fn write(input: &str) -> String {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_console_theory(input, &mut cursor).unwrap();
+		String::from_utf8(cursor.into_inner()).unwrap()
+	}
+
+	#[test]
+	fn console() {
+		strict_assert_text(
+			&write("gcd(48, 18)"),
+			"(divide 48 by 18)
+48 = 2 * 18 + 12
+(divide 18 by 12)
+18 = 1 * 12 + 6
+(divide 12 by 6)
+12 = 2 * 6 + 0
+gcd(48, 18) = 6
+",
+		);
+		strict_assert_text(
+			&write("lcm(4,6)"),
+			"(factor 4)
+4 = 2 * 2
+(factor 6)
+6 = 2 * 3
+(multiply the highest power of each prime: 2^2 * 3)
+2 * 2 * 3 = 12
+lcm(4, 6) = 12
+",
+		);
+		assert!(write("factor(12)").ends_with("12 = 2 * 2 * 3\n12 = 2^2 * 3\n"));
+	}
+
+	#[test]
+	fn operation() {
+		assert!(parse_operation("gcd(1,2,3)").is_err());
+		assert!(parse_operation("sqrt(4)").is_err());
+		assert!(parse_operation("factor 12").is_err());
+		assert_eq!(parse_operation("factor(1)").err().unwrap().to_string(), "1 has no prime factors");
+	}
+
+	#[test]
+	fn dot() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_factor_dot("factor(12)", &mut cursor).unwrap();
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+
+		assert!(act.starts_with("digraph arithmetic_tree{"));
+		assert!(write_factor_dot("gcd(4,6)", Cursor::<Vec<u8>>::default()).is_err());
+	}
+}
@@ -9,6 +9,21 @@ pub struct CommandOptions {
     rpn_expression: Option<String>,
     infix_expression: Option<String>,
     output_path: Option<String>,
+    query_pattern: Option<String>,
+    rules_path: Option<String>,
+    strategy: Option<String>,
+    bracket_removal: Option<String>,
+    language: Option<String>,
+    detail: Option<String>,
+    division: Option<String>,
+    submissions_path: Option<String>,
+    seed: Option<String>,
+    metrics: bool,
+    puzzle_numbers: Option<String>,
+    target: Option<String>,
+    all: bool,
+    bracketings: bool,
+    number_theory: Option<String>,
 }
 
 impl CommandOptions {
@@ -23,13 +38,88 @@ impl CommandOptions {
     pub fn output_path(&self) -> Option<String> {
         self.output_path.clone()
     }
+
+    pub fn query_pattern(&self) -> Option<String> {
+        self.query_pattern.clone()
+    }
+
+    pub fn rules_path(&self) -> Option<String> {
+        self.rules_path.clone()
+    }
+
+    pub fn strategy(&self) -> Option<String> {
+        self.strategy.clone()
+    }
+
+    pub fn bracket_removal(&self) -> Option<String> {
+        self.bracket_removal.clone()
+    }
+
+    pub fn language(&self) -> Option<String> {
+        self.language.clone()
+    }
+
+    pub fn detail(&self) -> Option<String> {
+        self.detail.clone()
+    }
+
+    pub fn division(&self) -> Option<String> {
+        self.division.clone()
+    }
+
+    pub fn submissions_path(&self) -> Option<String> {
+        self.submissions_path.clone()
+    }
+
+    pub fn seed(&self) -> Option<String> {
+        self.seed.clone()
+    }
+
+    pub fn metrics(&self) -> bool {
+        self.metrics
+    }
+
+    pub fn puzzle_numbers(&self) -> Option<String> {
+        self.puzzle_numbers.clone()
+    }
+
+    pub fn target(&self) -> Option<String> {
+        self.target.clone()
+    }
+
+    pub fn all(&self) -> bool {
+        self.all
+    }
+
+    pub fn bracketings(&self) -> bool {
+        self.bracketings
+    }
+
+    pub fn number_theory(&self) -> Option<String> {
+        self.number_theory.clone()
+    }
+}
+
+const FLAGS: [&str; 18] = [
+    "-r", "-i", "-o", "-q", "-w", "-s", "-b", "-l", "-d", "-v", "-g", "-x", "-m", "-p", "-t", "-a", "-e", "-n",
+];
+
+fn is_flag(elem: &str) -> bool {
+    FLAGS.contains(&elem)
+}
+
+fn parse_value(input: &mut Peekable<Iter<String>>, name: &str) -> AnyResult<String> {
+    match input.next() {
+        Some(value) if !is_flag(value) => Ok(value.to_string()),
+        _ => Err(AnyError::msg(format!("{name} is not specified."))),
+    }
 }
 
 fn parse_rpn(input: &mut Peekable<Iter<String>>) -> AnyResult<String> {
     let mut ret = String::default();
 
     while let Some(elem) = input.peek() {
-        if elem == &"-o" {
+        if is_flag(elem) {
             break;
         } else {
             ret.push_str(elem);
@@ -47,6 +137,21 @@ pub fn parse_command_options(input: Vec<String>) -> AnyResult<CommandOptions> {
         rpn_expression: None,
         infix_expression: None,
         output_path: None,
+        query_pattern: None,
+        rules_path: None,
+        strategy: None,
+        bracket_removal: None,
+        language: None,
+        detail: None,
+        division: None,
+        submissions_path: None,
+        seed: None,
+        metrics: false,
+        puzzle_numbers: None,
+        target: None,
+        all: false,
+        bracketings: false,
+        number_theory: None,
     };
 
     let mut iter: Peekable<Iter<String>> = input.iter().peekable();
@@ -56,19 +161,196 @@ pub fn parse_command_options(input: Vec<String>) -> AnyResult<CommandOptions> {
         if elem == &"-r" {
             iter.next();
             opt.rpn_expression = Some(parse_rpn(&mut iter)?);
+        } else if elem == &"-i" {
+            iter.next();
+            opt.infix_expression = Some(parse_value(&mut iter, "Infix expression")?);
         } else if elem == &"-o" {
             iter.next();
-            let cur = iter.next();
-            println!("Hit:{:?}", &cur);
-            if let Some(path) = cur {
-                opt.output_path = Some(path.to_string())
-            } else {
-                return Err(AnyError::msg("Path is not specified."));
-            }
-
+            opt.output_path = Some(parse_value(&mut iter, "Path")?);
+        } else if elem == &"-q" {
+            iter.next();
+            opt.query_pattern = Some(parse_value(&mut iter, "Query pattern")?);
+        } else if elem == &"-w" {
+            iter.next();
+            opt.rules_path = Some(parse_value(&mut iter, "Rules path")?);
+        } else if elem == &"-s" {
+            iter.next();
+            opt.strategy = Some(parse_value(&mut iter, "Strategy")?);
+        } else if elem == &"-b" {
+            iter.next();
+            opt.bracket_removal = Some(parse_value(&mut iter, "Bracket removal")?);
+        } else if elem == &"-l" {
+            iter.next();
+            opt.language = Some(parse_value(&mut iter, "Language")?);
+        } else if elem == &"-d" {
+            iter.next();
+            opt.detail = Some(parse_value(&mut iter, "Detail")?);
+        } else if elem == &"-v" {
+            iter.next();
+            opt.division = Some(parse_value(&mut iter, "Division")?);
+        } else if elem == &"-g" {
+            iter.next();
+            opt.submissions_path = Some(parse_value(&mut iter, "Submissions path")?);
+        } else if elem == &"-x" {
+            iter.next();
+            opt.seed = Some(parse_value(&mut iter, "Seed")?);
+        } else if elem == &"-m" {
+            iter.next();
+            opt.metrics = true;
+        } else if elem == &"-p" {
+            iter.next();
+            opt.puzzle_numbers = Some(parse_value(&mut iter, "Puzzle numbers")?);
+        } else if elem == &"-t" {
             iter.next();
+            opt.target = Some(parse_value(&mut iter, "Target")?);
+        } else if elem == &"-a" {
+            iter.next();
+            opt.all = true;
+        } else if elem == &"-e" {
+            iter.next();
+            opt.bracketings = true;
+        } else if elem == &"-n" {
+            iter.next();
+            opt.number_theory = Some(parse_value(&mut iter, "Number theory operation")?);
+        } else {
+            return Err(AnyError::msg(format!("Unknown option:{elem}")));
         }
     }
 
     Ok(opt)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(scr: &[&str]) -> Vec<String> {
+        let mut vec = vec!["playground".to_string()];
+        vec.extend(scr.iter().map(|s| s.to_string()));
+        vec
+    }
+
+    #[test]
+    fn rpn_and_output() {
+        let act = parse_command_options(args(&["-r", "1", "2", "+", "-o", "out.html"])).unwrap();
+
+        assert_eq!(act.rpn_expression().unwrap(), "1 2 + ");
+        assert_eq!(act.output_path().unwrap(), "out.html");
+        assert!(act.infix_expression().is_none());
+        assert!(act.query_pattern().is_none());
+    }
+
+    #[test]
+    fn query() {
+        let act = parse_command_options(args(&["-i", "{1+2}*0", "-q", "_ * 0"])).unwrap();
+
+        assert_eq!(act.infix_expression().unwrap(), "{1+2}*0");
+        assert_eq!(act.query_pattern().unwrap(), "_ * 0");
+        assert!(act.output_path().is_none());
+    }
+
+    #[test]
+    fn rewrite() {
+        let act = parse_command_options(args(&["-i", "2*1", "-w", "mental.rules", "-s", "once", "-o", "out.html"])).unwrap();
+
+        assert_eq!(act.rules_path().unwrap(), "mental.rules");
+        assert_eq!(act.strategy().unwrap(), "once");
+        assert_eq!(act.output_path().unwrap(), "out.html");
+        assert!(act.bracket_removal().is_none());
+    }
+
+    #[test]
+    fn bracket_removal() {
+        let act = parse_command_options(args(&["-i", "{1+2}*3", "-b", "merged"])).unwrap();
+
+        assert_eq!(act.bracket_removal().unwrap(), "merged");
+        assert!(parse_command_options(args(&["-i", "1", "-b"])).is_err());
+    }
+
+    #[test]
+    fn language() {
+        let act = parse_command_options(args(&["-i", "1+2", "-l", "en", "-o", "out.html"])).unwrap();
+
+        assert_eq!(act.language().unwrap(), "en");
+        assert!(parse_command_options(args(&["-i", "1", "-l", "-o", "out.html"])).is_err());
+    }
+
+    #[test]
+    fn detail() {
+        let act = parse_command_options(args(&["-i", "47*36", "-d", "written"])).unwrap();
+
+        assert_eq!(act.detail().unwrap(), "written");
+        assert!(act.language().is_none());
+    }
+
+    #[test]
+    fn division() {
+        let act = parse_command_options(args(&["-i", "1/3+1/4", "-v", "fraction"])).unwrap();
+
+        assert_eq!(act.division().unwrap(), "fraction");
+        assert!(act.detail().is_none());
+        assert!(parse_command_options(args(&["-i", "1", "-v"])).is_err());
+    }
+
+    #[test]
+    fn grade() {
+        let act = parse_command_options(args(&["-g", "answers.txt", "-o", "report.txt"])).unwrap();
+
+        assert_eq!(act.submissions_path().unwrap(), "answers.txt");
+        assert_eq!(act.output_path().unwrap(), "report.txt");
+        assert!(parse_command_options(args(&["-g"])).is_err());
+    }
+
+    #[test]
+    fn exercises() {
+        let act = parse_command_options(args(&["-x", "42"])).unwrap();
+
+        assert_eq!(act.seed().unwrap(), "42");
+        assert!(act.output_path().is_none());
+    }
+
+    #[test]
+    fn metrics() {
+        let act = parse_command_options(args(&["-m", "-i", "1+2"])).unwrap();
+
+        assert!(act.metrics());
+        assert_eq!(act.infix_expression().unwrap(), "1+2");
+        assert!(!parse_command_options(args(&["-i", "1+2"])).unwrap().metrics());
+    }
+
+    #[test]
+    fn puzzle() {
+        let act = parse_command_options(args(&["-p", "1,1,5,8", "-t", "10", "-a"])).unwrap();
+
+        assert_eq!(act.puzzle_numbers().unwrap(), "1,1,5,8");
+        assert_eq!(act.target().unwrap(), "10");
+        assert!(act.all());
+        assert!(!parse_command_options(args(&["-p", "1,2"])).unwrap().all());
+        assert!(parse_command_options(args(&["-p", "1,2", "-t"])).is_err());
+    }
+
+    #[test]
+    fn bracketings() {
+        let act = parse_command_options(args(&["-e", "-i", "1-2-3-4", "-o", "trees.dot"])).unwrap();
+
+        assert!(act.bracketings());
+        assert_eq!(act.output_path().unwrap(), "trees.dot");
+        assert!(!act.metrics());
+    }
+
+    #[test]
+    fn number_theory() {
+        let act = parse_command_options(args(&["-n", "gcd(48,18)", "-o", "gcd.html"])).unwrap();
+
+        assert_eq!(act.number_theory().unwrap(), "gcd(48,18)");
+        assert_eq!(act.output_path().unwrap(), "gcd.html");
+        assert!(parse_command_options(args(&["-n", "-o", "gcd.html"])).is_err());
+    }
+
+    #[test]
+    fn error() {
+        assert!(parse_command_options(args(&["-o"])).is_err());
+        assert!(parse_command_options(args(&["-q", "-i", "1"])).is_err());
+        assert!(parse_command_options(args(&["-x"])).is_err());
+    }
+}
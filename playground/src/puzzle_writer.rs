@@ -0,0 +1,73 @@
+use std::io::Write;
+
+use anyhow::{Error as AnyError, Result as AnyResult};
+
+use parser::infix::formatter::minimal_infix_notation;
+use syntax::puzzle::{solve, solve_minimal, DEFAULT_SEARCH_LIMIT, MAX_NUMBERS};
+
+// Numbers separated by commas or spaces, as in "1,1,5,8".
+pub fn parse_numbers(input: &str) -> AnyResult<Vec<i32>> {
+	input
+		.split(|c: char| c == ',' || c.is_whitespace())
+		.filter(|s| !s.is_empty())
+		.map(|s| s.parse::<i32>().map_err(|_| AnyError::msg(format!("Invalid number:{s}"))))
+		.collect()
+}
+
+// One solution per line, or only those with the fewest brackets unless all are asked for.
+pub fn write_puzzle<T: Write>(numbers: &[i32], target: i32, all: bool, mut writer: T) -> AnyResult<()> {
+	let solutions = if all {
+		solve(numbers, target, DEFAULT_SEARCH_LIMIT)
+	} else {
+		solve_minimal(numbers, target, DEFAULT_SEARCH_LIMIT)
+	}
+	.ok_or_else(|| {
+		if numbers.len() > MAX_NUMBERS {
+			AnyError::msg("Too many numbers to search.")
+		} else {
+			AnyError::msg("The search is too large.")
+		}
+	})?;
+
+	if solutions.is_empty() {
+		writeln!(writer, "no solution")?;
+	}
+
+	for expr in solutions.iter() {
+		writeln!(writer, "{} = {}", minimal_infix_notation(expr), target)?;
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use crate::test_helper::strict_assert_text;
+
+	use super::*;
+
+	fn write(numbers: &str, target: i32, all: bool) -> String {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_puzzle(&parse_numbers(numbers).unwrap(), target, all, &mut cursor).unwrap();
+		String::from_utf8(cursor.into_inner()).unwrap()
+	}
+
+	#[test]
+	fn make_ten() {
+		strict_assert_text(&write("1,1,5,8", 10, false), "8 / {1 - 1 / 5} = 10\n");
+		strict_assert_text(&write("2 3 4", 14, false), "2 + 3 * 4 = 14\n");
+		strict_assert_text(&write("2 3 4", 14, true), "2 + 3 * 4 = 14\n2 * {3 + 4} = 14\n");
+		strict_assert_text(&write("1,2", 100, true), "no solution\n");
+
+		let act = write_puzzle(&[1; 9], 9, true, Cursor::<Vec<u8>>::default()).err().unwrap();
+		assert_eq!(act.to_string(), "Too many numbers to search.");
+	}
+
+	#[test]
+	fn numbers() {
+		assert_eq!(parse_numbers(" 1, 1 5,8").unwrap(), vec![1, 1, 5, 8]);
+		assert!(parse_numbers("1,x").is_err());
+	}
+}
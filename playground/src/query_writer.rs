@@ -0,0 +1,76 @@
+use std::io::Write;
+
+use anyhow::{Error as AnyError, Result as AnyResult};
+
+use parser::infix::formatter::infix_notation;
+use parser::infix::parser::parse;
+use parser::pattern::parser::parse as parse_pattern;
+
+pub fn write_query<T: Write>(formula: &str, pattern: &str, mut writer: T) -> AnyResult<()> {
+	let (expr, rem) = parse(formula)?;
+
+	if !rem.trim().is_empty() {
+		return Err(AnyError::msg(format!("Unexpected input:{rem}")));
+	}
+
+	let (pat, rem) = parse_pattern(pattern)?;
+
+	if !rem.trim().is_empty() {
+		return Err(AnyError::msg(format!("Unexpected pattern:{rem}")));
+	}
+
+	let matches = pat.matches(&expr);
+
+	writeln!(writer, "{}", infix_notation(&expr))?;
+	writeln!(writer, "{} match(es)", matches.len())?;
+
+	for m in matches.iter() {
+		let target = m.path().resolve(&expr).unwrap();
+		writeln!(writer, "{}\t{}", m.path(), infix_notation(target))?;
+
+		for (name, bound) in m.bindings().iter() {
+			writeln!(writer, "\t{} = {}", name, infix_notation(bound))?;
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use crate::test_helper::strict_assert_text;
+
+	use super::*;
+
+	fn query(formula: &str, pattern: &str) -> String {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_query(formula, pattern, &mut cursor).unwrap();
+		String::from_utf8(cursor.into_inner()).unwrap()
+	}
+
+	#[test]
+	fn wildcard() {
+		const EXPECTED: &str = "{1 + 2} * 0 + 4 * 0\n2 match(es)\n/L\t{1 + 2} * 0\n/R\t4 * 0\n";
+		strict_assert_text(&query("{1+2}*0+4*0", "_ * 0"), EXPECTED);
+	}
+
+	#[test]
+	fn bindings() {
+		const EXPECTED: &str = "10 * {{1 + 2} - {1 + 2}}\n1 match(es)\n/R/I\t{1 + 2} - {1 + 2}\n\ta = 1 + 2\n";
+		strict_assert_text(&query("10*{{1+2}-{1+2}}", "{a} - {a}"), EXPECTED);
+	}
+
+	#[test]
+	fn no_match() {
+		strict_assert_text(&query("6/2", "n / 1"), "6 / 2\n0 match(es)\n");
+	}
+
+	#[test]
+	fn error() {
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		assert!(write_query("1+", "_", &mut cursor).is_err());
+		assert!(write_query("1+2", "_ ?", &mut cursor).is_err());
+	}
+}
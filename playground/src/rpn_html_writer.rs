@@ -1,19 +1,87 @@
 use std::collections::VecDeque;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
 
 use anyhow::{Error as AnyError, Result as AnyResult};
 
 use parser::infix as Infix;
+use parser::pattern::rules::parse_rules;
 use parser::rpn::parser as Rpn;
 use syntax::arithmetic_expression::ArithmeticExpression;
 use syntax::binary_operation::Operation;
+use syntax::explanation::{Language, MessageCatalog};
 use syntax::expression::Expression;
+use syntax::generator::ExerciseConstraints;
 use syntax::number_value::NumberValue;
+use syntax::rewrite::{RewriteStrategy, DEFAULT_STEP_LIMIT};
+use syntax::step::{BracketRemoval, Division, StepFailure};
+use syntax::step_strategy::{LeftmostInnermost, PrecedenceSweep, Rightmost, StepStrategy};
+use syntax::written::Detail;
+use crate::bracketing_writer::{write_bracketing_dot, write_bracketing_html};
+use crate::console_step_writer::{write_console_equation, write_console_steps};
+use crate::exercise_writer::{write_exercises, DEFAULT_EXERCISE_COUNT};
+use crate::grade_writer::write_grade_report;
+use crate::infix_html_writer::{
+    write_equation_html, write_parallel_step_infix_html, write_rewrite_infix_html, write_step_infix_html,
+    write_theory_html,
+};
+use crate::metrics_writer::write_metrics;
+use crate::number_theory_writer::{parse_operation, write_console_theory, write_factor_dot};
 use crate::option_parser::parse_command_options;
+use crate::puzzle_writer::{parse_numbers, write_puzzle};
+use crate::query_writer::write_query;
 use crate::rpn_html_writer;
 
+fn parse_strategy(strategy: Option<String>) -> AnyResult<RewriteStrategy> {
+    match strategy.as_deref() {
+        None | Some("fixpoint") => Ok(RewriteStrategy::Fixpoint(DEFAULT_STEP_LIMIT)),
+        Some("once") => Ok(RewriteStrategy::Once),
+        Some("innermost") => Ok(RewriteStrategy::InnermostFirst),
+        Some(other) => Err(AnyError::msg(format!("Unknown strategy:{other}"))),
+    }
+}
+
+fn parse_step_strategy(strategy: Option<String>) -> AnyResult<Box<dyn StepStrategy>> {
+    match strategy.as_deref() {
+        None | Some("leftmost") => Ok(Box::new(LeftmostInnermost)),
+        Some("precedence") => Ok(Box::new(PrecedenceSweep)),
+        Some("rightmost") => Ok(Box::new(Rightmost)),
+        Some(other) => Err(AnyError::msg(format!("Unknown strategy:{other}"))),
+    }
+}
+
+fn parse_language(language: Option<String>) -> AnyResult<Language> {
+    match language.as_deref() {
+        None => Ok(Language::Ja),
+        Some(tag) => Language::from_tag(tag).ok_or_else(|| AnyError::msg(format!("Unknown language:{tag}"))),
+    }
+}
+
+fn parse_detail(detail: Option<String>) -> AnyResult<Detail> {
+    match detail.as_deref() {
+        None | Some("operation") => Ok(Detail::Operation),
+        Some("written") => Ok(Detail::Written),
+        Some(other) => Err(AnyError::msg(format!("Unknown detail:{other}"))),
+    }
+}
+
+fn parse_division(division: Option<String>) -> AnyResult<Division> {
+    match division.as_deref() {
+        None | Some("integer") => Ok(Division::Integer),
+        Some("fraction") => Ok(Division::Fraction),
+        Some(other) => Err(AnyError::msg(format!("Unknown division:{other}"))),
+    }
+}
+
+fn parse_bracket_removal(removal: Option<String>) -> AnyResult<BracketRemoval> {
+    match removal.as_deref() {
+        None | Some("separate") => Ok(BracketRemoval::Separate),
+        Some("merged") => Ok(BracketRemoval::WithReduction),
+        Some(other) => Err(AnyError::msg(format!("Unknown bracket removal:{other}"))),
+    }
+}
+
 pub fn procedure()->AnyResult<()>
 {
     let args: Vec<String> = env::args().collect();
@@ -24,16 +92,137 @@ pub fn procedure()->AnyResult<()>
     
     println!("{:?}", &opt);
     
+    if let Some(pattern) = opt.query_pattern() {
+        let formula = opt
+            .infix_expression()
+            .ok_or_else(|| AnyError::msg("Infix expression is not specified."))?;
+        
+        return match opt.output_path() {
+            Some(path) => write_query(&formula, &pattern, File::create(path)?),
+            None => write_query(&formula, &pattern, std::io::stdout()),
+        };
+    }
+    
+    if let Some(seed) = opt.seed() {
+        let seed = seed.parse::<u64>().map_err(|_| AnyError::msg(format!("Invalid seed:{seed}")))?;
+        let constraints = ExerciseConstraints::default();
+        
+        return match opt.output_path() {
+            Some(path) => write_exercises(seed, DEFAULT_EXERCISE_COUNT, constraints, File::create(path)?),
+            None => write_exercises(seed, DEFAULT_EXERCISE_COUNT, constraints, std::io::stdout()),
+        };
+    }
+    
+    if let Some(numbers) = opt.puzzle_numbers() {
+        let numbers = parse_numbers(&numbers)?;
+        let target = opt
+            .target()
+            .ok_or_else(|| AnyError::msg("Target is not specified."))?;
+        let target = target.parse::<i32>().map_err(|_| AnyError::msg(format!("Invalid target:{target}")))?;
+        
+        return match opt.output_path() {
+            Some(path) => write_puzzle(&numbers, target, opt.all(), File::create(path)?),
+            None => write_puzzle(&numbers, target, opt.all(), std::io::stdout()),
+        };
+    }
+    
+    // A .dot output gets the factor tree, any other the steps.
+    if let Some(operation) = opt.number_theory() {
+        return match opt.output_path() {
+            Some(path) if path.ends_with(".dot") => write_factor_dot(&operation, File::create(path)?),
+            Some(path) => write_theory_html(&operation, &parse_operation(&operation)?, File::create(path)?),
+            None => write_console_theory(&operation, std::io::stdout()),
+        };
+    }
+    
+    if let Some(submissions_path) = opt.submissions_path() {
+        let submissions = fs::read_to_string(submissions_path)?;
+        
+        return match opt.output_path() {
+            Some(path) => write_grade_report(&submissions, File::create(path)?),
+            None => write_grade_report(&submissions, std::io::stdout()),
+        };
+    }
+    
+    if opt.metrics() {
+        let formula = opt
+            .infix_expression()
+            .ok_or_else(|| AnyError::msg("Infix expression is not specified."))?;
+        
+        return match opt.output_path() {
+            Some(path) => write_metrics(&formula, File::create(path)?),
+            None => write_metrics(&formula, std::io::stdout()),
+        };
+    }
+    
+    // A .dot output gets the trees, any other the table.
+    if opt.bracketings() {
+        let formula = opt
+            .infix_expression()
+            .ok_or_else(|| AnyError::msg("Infix expression is not specified."))?;
+        
+        return match opt.output_path() {
+            Some(path) if path.ends_with(".dot") => write_bracketing_dot(&formula, File::create(path)?),
+            Some(path) => write_bracketing_html(&formula, File::create(path)?),
+            None => write_bracketing_html(&formula, std::io::stdout()),
+        };
+    }
+    
+    if let Some(rules_path) = opt.rules_path() {
+        let formula = opt
+            .infix_expression()
+            .ok_or_else(|| AnyError::msg("Infix expression is not specified."))?;
+        let path = opt
+            .output_path()
+            .ok_or_else(|| AnyError::msg("Output path is not specified."))?;
+        
+        let rules = parse_rules(&fs::read_to_string(rules_path)?)?;
+        let strategy = parse_strategy(opt.strategy())?;
+        
+        return write_rewrite_infix_html(&formula, &rules, strategy, File::create(path)?);
+    }
+    
+    // A formula with "=" is an equation to solve rather than a formula to calculate.
+    if let Some(formula) = opt.infix_expression().filter(|f| f.contains('=')) {
+        return match opt.output_path() {
+            Some(path) => write_equation_html(&formula, File::create(path)?),
+            None => write_console_equation(&formula, std::io::stdout()),
+        };
+    }
+    
+    // Without an output file the infix steps are printed to the console.
+    if let (None, Some(formula)) = (opt.output_path(), opt.infix_expression()) {
+        let strategy = parse_step_strategy(opt.strategy())?;
+        let removal = parse_bracket_removal(opt.bracket_removal())?;
+        let detail = parse_detail(opt.detail())?;
+        let division = parse_division(opt.division())?;
+        return write_console_steps(&formula, strategy.as_ref(), removal, detail, division, std::io::stdout());
+    }
+    
     if opt.output_path().is_none() {
         println!("Output path is not specified.");
         return Err(AnyError::msg("Output path is not specified."));
     }
     
     let file = File::create(opt.output_path().unwrap())?;
+    let catalog = parse_language(opt.language())?.catalog();
+    
+    if let Some(formula) = opt.infix_expression() {
+        let removal = parse_bracket_removal(opt.bracket_removal())?;
+        
+        if opt.strategy().as_deref() == Some("parallel") {
+            return write_parallel_step_infix_html(&formula, removal, file);
+        }
+        
+        let strategy = parse_step_strategy(opt.strategy())?;
+        let detail = parse_detail(opt.detail())?;
+        let division = parse_division(opt.division())?;
+        return write_step_infix_html(&formula, strategy.as_ref(), removal, catalog, detail, division, file);
+    }
     
     let a = &opt.rpn_expression().unwrap().replace('"', "");
     
-    rpn_html_writer::write_html(a, file)?;
+    rpn_html_writer::write_html(a, catalog, file)?;
     
     Ok(())
     
@@ -114,6 +303,10 @@ fn write_header(input: &VecDeque<Rpn::Token>, writer: &mut dyn Write) -> AnyResu
             margin-bottom: 20px;
             border-bottom: 3px solid darkgray;
         }
+
+        .explanation {
+            margin-top: 0;
+        }
     </style>
     <title>"#,
     )?;
@@ -135,12 +328,10 @@ fn write_expression(expression: &Expression, writer: &mut dyn Write, calc: bool)
       .replace('}', ")");
     
     if calc {
-        let ans = expression.calc()?;
-        let ans = match ans {
-            NumberValue::Integer(i) => i.to_string(),
-        };
-        
-        writer.write_fmt(format_args!("{} = {}", expr, ans))?
+        match expression.calc() {
+            Ok(NumberValue::Integer(i)) => writer.write_fmt(format_args!("{} = {}", expr, i))?,
+            Err(e) => writer.write_fmt(format_args!("{} : {}", expr, e))?,
+        }
     } else {
         writer.write_fmt(format_args!("{expr}"))?;
     }
@@ -152,6 +343,7 @@ fn write_state(
     recent: &str,
     input: &VecDeque<Rpn::Token>,
     stack: &Vec<Expression>,
+    explanation: Option<&str>,
     writer: &mut dyn Write,
 ) -> AnyResult<String> {
     let formula = token_to_string(&input);
@@ -166,9 +358,18 @@ fn write_state(
     _ = writer.write(b"</h2>\n");
     
     writer.write_fmt(format_args!(
-        "<h3>Recent:{recent}</h3>\n<table>\n<tr>\n<th>TOP</th>\n</tr>\n"
+        "<h3>Recent:{recent}</h3>\n"
     ))?;
     
+    if let Some(explanation) = explanation {
+        writer.write_fmt(format_args!(
+            "<p class=\"explanation\">{}</p>\n",
+            explanation.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+        ))?;
+    }
+    
+    _ = writer.write(b"<table>\n<tr>\n<th>TOP</th>\n</tr>\n")?;
+    
     let mut iter = stack.iter().rev();
     
     for expr in iter {
@@ -178,7 +379,7 @@ fn write_state(
                 write_expression(expr, writer, false)?;
                 _ = writer.write(b"\n</td>\n</tr>\n")?;
             }
-            Expression::Bracket(_) => unreachable!(),
+            Expression::Bracket(_) | Expression::Variable(_) => unreachable!(),
             Expression::BinaryOperation(_) => {
                 _ = writer.write(b"<tr>\n<td>\n")?;
                 write_expression(expr, writer, true)?;
@@ -199,6 +400,23 @@ fn write_state(
     Ok(formula)
 }
 
+fn write_failure(recent: &str, failure: &StepFailure, writer: &mut dyn Write) -> AnyResult<()> {
+    _ = writer.write(
+        br#"<div class="step">
+<h2>"#,
+    )?;
+    
+    write_expression(failure.expression(), writer, false)?;
+    
+    _ = writer.write(b"</h2>\n");
+    
+    writer.write_fmt(format_args!(
+        "<h3>Recent:{recent}</h3>\n<p class=\"failure\">{failure}</p>\n</div>\n"
+    ))?;
+    
+    Ok(())
+}
+
 fn write_footer(writer: &mut dyn Write) -> AnyResult<()> {
     _ = writer.write(
         br#"</body>
@@ -207,23 +425,24 @@ fn write_footer(writer: &mut dyn Write) -> AnyResult<()> {
     Ok(())
 }
 
-pub fn write_html<T: std::io::Write>(input: &str, mut writer: T) -> AnyResult<()> {
-    let (mut stream, remainder) = Rpn::tokenize(input);
+pub fn write_html<T: std::io::Write>(input: &str, catalog: &dyn MessageCatalog, mut writer: T) -> AnyResult<()> {
+    let (stream, remainder) = Rpn::tokenize(input);
     
     if !remainder.trim().is_empty() {
         return Err(anyhow::Error::msg("Tokenize error"));
     }
     
-    let mut stack = Vec::<Expression>::default();
-    
     write_header(&stream, &mut writer)?;
     let mut recent = token_to_string(&stream);
+    recent = write_state(&recent, &stream, &Vec::default(), None, &mut writer)?;
     
-    loop {
-        recent = write_state(&recent, &stream, &stack, &mut writer)?;
-        
-        if !Rpn::step_calc(&mut stream, &mut stack) {
-            break;
+    for step in Rpn::steps(stream) {
+        match step {
+            Ok(step) => {
+                let explanation = step.explanation().map(|e| catalog.message(&e));
+                recent = write_state(&recent, step.input(), step.stack(), explanation.as_deref(), &mut writer)?;
+            }
+            Err(failure) => write_failure(&recent, &failure, &mut writer)?,
         }
     }
     
@@ -235,6 +454,7 @@ pub fn write_html<T: std::io::Write>(input: &str, mut writer: T) -> AnyResult<()
 mod tests {
     use std::io::Cursor;
     
+    use syntax::explanation::{English, Japanese};
     use syntax::expression::Expression;
     
     use crate::test_helper::{assert_text, TrimOption};
@@ -250,6 +470,53 @@ mod tests {
         ret
     }
     
+    #[test]
+    fn parse_strategy_test() {
+        assert_eq!(parse_strategy(None).unwrap(), RewriteStrategy::Fixpoint(DEFAULT_STEP_LIMIT));
+        assert_eq!(parse_strategy(Some("once".to_string())).unwrap(), RewriteStrategy::Once);
+        assert_eq!(
+            parse_strategy(Some("innermost".to_string())).unwrap(),
+            RewriteStrategy::InnermostFirst
+        );
+        assert!(parse_strategy(Some("random".to_string())).is_err());
+    }
+    
+    #[test]
+    fn parse_step_strategy_test() {
+        let act = parse_step_strategy(Some("precedence".to_string())).unwrap();
+        let expr = Infix::parser::parse("1+2*3").unwrap().0;
+        assert_eq!(act.select(&expr).unwrap().to_string(), "/R");
+        
+        assert!(parse_step_strategy(None).is_ok());
+        assert!(parse_step_strategy(Some("rightmost".to_string())).is_ok());
+        assert!(parse_step_strategy(Some("once".to_string())).is_err());
+    }
+    
+    #[test]
+    fn parse_detail_test() {
+        assert_eq!(parse_detail(None).unwrap(), Detail::Operation);
+        assert_eq!(parse_detail(Some("written".to_string())).unwrap(), Detail::Written);
+        assert!(parse_detail(Some("fraction".to_string())).is_err());
+        assert!(parse_detail(Some("digits".to_string())).is_err());
+    }
+    
+    #[test]
+    fn parse_division_test() {
+        assert_eq!(parse_division(None).unwrap(), Division::Integer);
+        assert_eq!(parse_division(Some("fraction".to_string())).unwrap(), Division::Fraction);
+        assert!(parse_division(Some("rational".to_string())).is_err());
+    }
+    
+    #[test]
+    fn parse_bracket_removal_test() {
+        assert_eq!(parse_bracket_removal(None).unwrap(), BracketRemoval::Separate);
+        assert_eq!(
+            parse_bracket_removal(Some("merged".to_string())).unwrap(),
+            BracketRemoval::WithReduction
+        );
+        assert!(parse_bracket_removal(Some("never".to_string())).is_err());
+    }
+    
     #[test]
     fn token_to_string_test() {
         let tokens = gen_token_stream();
@@ -303,6 +570,10 @@ mod tests {
             margin-bottom: 20px;
             border-bottom: 3px solid darkgray;
         }
+
+        .explanation {
+            margin-top: 0;
+        }
     </style>
     <title>4 2 3 4 5 / + * -</title>
 </head>
@@ -317,6 +588,42 @@ mod tests {
         assert_text(&act, EXPECTED, Some(&[TrimOption::Both]), true)
     }
     
+    #[test]
+    fn write_failure_test() {
+        const EXPECTED: &str = r#"<div class="step">
+    <h2>10 / (5 - 5)</h2>
+    <h3>Recent:/ +</h3>
+    <p class="failure">cannot divide by zero here</p>
+</div>"#;
+        
+        let mut cursor = create_cursor();
+        write_html("1 10 5 5 - / +", &Japanese, &mut cursor).unwrap();
+        let act = String::from_utf8(cursor.into_inner()).unwrap();
+        
+        assert_eq!(act.matches(r#"<div class="step">"#).count(), 7);
+        
+        let failure = &act[act.rfind(r#"<div class="step">"#).unwrap()..act.rfind("</body>").unwrap()];
+        assert_text(failure, EXPECTED, Some(&[TrimOption::Both]), true);
+    }
+    
+    #[test]
+    fn write_explanation_test() {
+        let mut cursor = create_cursor();
+        write_html("4 2 3 * -", &English, &mut cursor).unwrap();
+        let act = String::from_utf8(cursor.into_inner()).unwrap();
+        
+        assert_eq!(act.matches(r#"<p class="explanation">"#).count(), 5);
+        assert!(act.contains(r#"<p class="explanation">Push 3 onto the stack</p>"#));
+        assert!(act.contains(r#"<p class="explanation">Pop 2 and 3, calculate 2 × 3 = 6 and push the result</p>"#));
+    }
+    
+    #[test]
+    fn parse_language_test() {
+        assert_eq!(parse_language(None).unwrap(), Language::Ja);
+        assert_eq!(parse_language(Some("en".to_string())).unwrap(), Language::En);
+        assert!(parse_language(Some("fr".to_string())).is_err());
+    }
+    
     #[test]
     fn write_footer_test() {
         const EXPECTED: &str = r#"</body>
@@ -335,6 +642,7 @@ mod tests {
         const EXPECTED: &str = r#"<div class="step">
     <h2>3 4 5 / + * -</h2>
     <h3>Recent:2 3 4 5 / + * -</h3>
+    <p class="explanation">2 をスタックに積みます</p>
     <table>
         <tr>
             <th>TOP</th>
@@ -361,9 +669,15 @@ mod tests {
         assert!(Rpn::step_calc(&mut stream, &mut stack));
         assert!(Rpn::step_calc(&mut stream, &mut stack));
         let mut cursor = create_cursor();
-        write_state("2 3 4 5 / + * -", &stream, &stack, &mut cursor).unwrap();
+        write_state("2 3 4 5 / + * -", &stream, &stack, Some("2 をスタックに積みます"), &mut cursor).unwrap();
         
         let act = String::from_utf8(cursor.into_inner()).unwrap();
         assert_text(&act, EXPECTED, Some(&[TrimOption::Both]), true);
+        
+        let mut cursor = create_cursor();
+        write_state("", &stream, &stack, Some("1 < 2 & 3 > 2"), &mut cursor).unwrap();
+        
+        let act = String::from_utf8(cursor.into_inner()).unwrap();
+        assert!(act.contains(r#"<p class="explanation">1 &lt; 2 &amp; 3 &gt; 2</p>"#));
     }
 }
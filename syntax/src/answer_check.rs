@@ -0,0 +1,222 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::expression::Expression;
+use crate::number_value::{ArithmeticError, NumberValue};
+
+// What is wrong with a line of a worked answer.
+pub enum Mistake {
+	// The value of the formula, and the value of the line.
+	WrongValue(NumberValue, NumberValue),
+	// The value is right, but the rules of calculation do not lead from the previous line to it.
+	NotReachable,
+	Uncomputable(ArithmeticError),
+}
+
+fn number(value: &NumberValue) -> String {
+	let NumberValue::Integer(i) = value;
+	i.to_string()
+}
+
+impl Clone for Mistake {
+	fn clone(&self) -> Self {
+		match self {
+			Mistake::WrongValue(expected, actual) => Mistake::WrongValue(expected.clone(), actual.clone()),
+			Mistake::NotReachable => Mistake::NotReachable,
+			Mistake::Uncomputable(error) => Mistake::Uncomputable(error.clone()),
+		}
+	}
+}
+
+impl Debug for Mistake {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Mistake::WrongValue(expected, actual) => write!(f, "WrongValue:{:?},{:?}", expected, actual),
+			Mistake::NotReachable => write!(f, "NotReachable"),
+			Mistake::Uncomputable(error) => write!(f, "Uncomputable:{:?}", error),
+		}
+	}
+}
+
+impl Display for Mistake {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Mistake::WrongValue(expected, actual) => {
+				write!(f, "the value changed from {} to {}", number(expected), number(actual))
+			}
+			Mistake::NotReachable => write!(f, "the previous line does not lead to this one by the rules of calculation"),
+			Mistake::Uncomputable(error) if error.is_division_by_zero() => write!(f, "this line divides by zero"),
+			Mistake::Uncomputable(error) => write!(f, "this line cannot be calculated: {}", error.message()),
+		}
+	}
+}
+
+impl Error for Mistake {}
+
+// The result of checking a worked answer. Lines are counted from 1, the formula not included.
+#[derive(Clone)]
+pub struct AnswerCheck {
+	lines: usize,
+	error: Option<(usize, Mistake)>,
+	finished: bool,
+}
+
+impl AnswerCheck {
+	pub fn lines(&self) -> usize {
+		self.lines
+	}
+
+	// The first invalid line and what is wrong with it. The lines after it are not checked.
+	pub fn error(&self) -> Option<(usize, &Mistake)> {
+		self.error.as_ref().map(|(line, mistake)| (*line, mistake))
+	}
+
+	pub fn is_valid(&self) -> bool {
+		self.error.is_none()
+	}
+
+	// Whether every line is valid and the last one is the value.
+	pub fn is_finished(&self) -> bool {
+		self.finished
+	}
+}
+
+fn check_line(value: &NumberValue, previous: &Expression, line: &Expression) -> Result<(), Mistake> {
+	let actual = line.calc().map_err(Mistake::Uncomputable)?;
+
+	if actual != *value {
+		return Err(Mistake::WrongValue(value.clone(), actual));
+	}
+
+	if reachable(previous, line) {
+		Ok(())
+	} else {
+		Err(Mistake::NotReachable)
+	}
+}
+
+// A reduction only ever replaces a part of the formula by a number, so the line keeps the
+// shape of the previous one except where a part is replaced by its value.
+fn reachable(previous: &Expression, line: &Expression) -> bool {
+	let mut stack = vec![(previous, line)];
+
+	while let Some((previous, line)) = stack.pop() {
+		match (previous, line) {
+			(_, Expression::Number(number)) => match previous.calc() {
+				Ok(value) if &value == number.number() => {}
+				_ => return false,
+			},
+			(Expression::Variable(p), Expression::Variable(l)) if p.name() == l.name() => {}
+			(Expression::Bracket(p), Expression::Bracket(l)) => stack.push((p.expression(), l.expression())),
+			(Expression::BinaryOperation(p), Expression::BinaryOperation(l)) if p.operation() == l.operation() => {
+				stack.push((p.right(), l.right()));
+				stack.push((p.left(), l.left()));
+			}
+			_ => return false,
+		}
+	}
+
+	true
+}
+
+// Checks each line against the one before it: same value, and reachable by reductions of
+// brackets and operations over two numbers, any number of them at once. A line may also
+// repeat the previous one.
+pub fn check_answer(formula: &Expression, lines: &[Expression]) -> Result<AnswerCheck, ArithmeticError> {
+	let value = formula.calc()?;
+	let mut previous = formula;
+
+	for (idx, line) in lines.iter().enumerate() {
+		if let Err(mistake) = check_line(&value, previous, line) {
+			return Ok(AnswerCheck {
+				lines: lines.len(),
+				error: Some((idx + 1, mistake)),
+				finished: false,
+			});
+		}
+
+		previous = line;
+	}
+
+	Ok(AnswerCheck {
+		lines: lines.len(),
+		error: None,
+		finished: previous.is_number(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::builder::num;
+	use crate::expr;
+
+	use super::*;
+
+	#[test]
+	fn correct() {
+		let act = check_answer(&expr!(1 + 2 * 3 - 4), &[expr!(1 + 6 - 4), expr!(7 - 4), expr!(3)]).unwrap();
+
+		assert!(act.is_valid());
+		assert!(act.is_finished());
+		assert_eq!(act.lines(), 3);
+
+		// Several reductions in one line, and a line copied as it was.
+		let act = check_answer(&expr!({ 1 + 2 } * 3), &[expr!(3 * 3), expr!(3 * 3)]).unwrap();
+		assert!(act.is_valid());
+		assert!(!act.is_finished());
+
+		// The steps the tool itself shows, and an addition that does not wait for 3 * 4.
+		let lines = [expr!(1 + 6 - 4 / 2), expr!(7 - 4 / 2), expr!(7 - 2), expr!(5)];
+		assert!(check_answer(&expr!(1 + 2 * 3 - 4 / 2), &lines).unwrap().is_valid());
+		assert!(check_answer(&expr!(1 + 2 - 3 * 4), &[expr!(3 - 3 * 4)]).unwrap().is_valid());
+	}
+
+	#[test]
+	fn wide() {
+		// Every product of 1*2+3*4+...+27*28 reduced in the same line.
+		let products = (0..14).map(|i| num(2 * i + 1) * num(2 * i + 2)).reduce(|l, r| l + r).unwrap();
+		let reduced = (0..14).map(|i| num((2 * i + 1) * (2 * i + 2))).reduce(|l, r| l + r).unwrap();
+
+		let act = check_answer(&products, &[reduced.clone(), num(3850)]).unwrap();
+		assert!(act.is_valid());
+		assert!(act.is_finished());
+
+		// Added up before the products, which no reduction does.
+		let swapped = (0..14).rev().map(|i| num((2 * i + 1) * (2 * i + 2))).reduce(|l, r| l + r).unwrap();
+		let act = check_answer(&products, &[swapped]).unwrap();
+		assert!(matches!(act.error().unwrap().1, Mistake::NotReachable));
+	}
+
+	#[test]
+	fn wrong_value() {
+		let act = check_answer(&expr!(1 + 2 * 3), &[expr!(3 * 3), expr!(9)]).unwrap();
+		let (line, mistake) = act.error().unwrap();
+
+		assert_eq!(line, 1);
+		assert!(matches!(mistake, Mistake::WrongValue(_, _)));
+		assert_eq!(mistake.to_string(), "the value changed from 7 to 9");
+	}
+
+	#[test]
+	fn not_reachable() {
+		// Rearranged instead of calculated.
+		let act = check_answer(&expr!(1 + 2 + 3), &[expr!(1 + 5)]).unwrap();
+		let (line, mistake) = act.error().unwrap();
+
+		assert_eq!(line, 1);
+		assert!(matches!(mistake, Mistake::NotReachable));
+
+		// Same value, but 12 was taken back apart again.
+		let act = check_answer(&expr!(1 + 2 - 3 * 4), &[expr!(1 + 2 - 12), expr!(3 - 3 * 4)]).unwrap();
+		assert_eq!(act.error().unwrap().0, 2);
+	}
+
+	#[test]
+	fn uncomputable() {
+		let act = check_answer(&expr!(4 / 2), &[expr!(4 / 0)]).unwrap();
+		assert_eq!(act.error().unwrap().1.to_string(), "this line divides by zero");
+
+		assert!(check_answer(&expr!(1 / 0), &[]).is_err());
+	}
+}
@@ -3,7 +3,7 @@ use crate::expression::Expression;
 use crate::number::Number;
 use crate::number_value::NumberResult;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Operation {
 	Add,
 	Sub,
@@ -11,6 +11,24 @@ pub enum Operation {
 	Div,
 }
 
+impl Operation {
+	pub fn is_additive(&self) -> bool {
+		matches!(self, Operation::Add | Operation::Sub)
+	}
+	
+	// Whether an operation as the given operand of this one has to be written in brackets.
+	pub fn needs_bracket(&self, child: &Operation, right: bool) -> bool {
+		match self {
+			Operation::Add => false,
+			// a - {b + c} is not a - b + c.
+			Operation::Sub => right && child.is_additive(),
+			// 2 * {7 / 2} is not 2 * 7 / 2.
+			Operation::Mul => child.is_additive() || (right && *child == Operation::Div),
+			Operation::Div => true,
+		}
+	}
+}
+
 pub struct BinaryOperation {
 	left: Box<Expression>,
 	right: Box<Expression>,
@@ -41,6 +59,16 @@ impl BinaryOperation {
 	pub fn operation(&self) -> &Operation {
 		&self.operation
 	}
+	
+	pub(crate) fn detach(&mut self, stack: &mut Vec<Expression>) {
+		if !self.left.is_number() {
+			stack.push(std::mem::replace(self.left.as_mut(), Expression::placeholder()));
+		}
+		
+		if !self.right.is_number() {
+			stack.push(std::mem::replace(self.right.as_mut(), Expression::placeholder()));
+		}
+	}
 }
 
 impl Clone for BinaryOperation {
@@ -94,9 +122,11 @@ impl ArithmeticExpression for BinaryOperation {
 			);
 		}
 		
-		let tmp = self.calc().unwrap();
-		
-		(Number::from(tmp).to_expression(), true)
+		// A failing operation is left as it is; Step::reduce reports the error.
+		match self.calc() {
+			Ok(value) => (Number::from(value).to_expression(), true),
+			Err(_) => (self.clone().to_expression(), false),
+		}
 	}
 }
 
@@ -276,8 +306,8 @@ mod tests {
 		let mut fixture = BinaryOperation::new(left, right, Operation::Add);
 		let act = fixture.clone();
 		
-		fixture.left = Box::new(NumberExpr::from(NumberValue::from(2)).to_expression());
-		fixture.right = Box::new(NumberExpr::from(NumberValue::from(3)).to_expression());
+		*fixture.left = NumberExpr::from(NumberValue::from(2)).to_expression();
+		*fixture.right = NumberExpr::from(NumberValue::from(3)).to_expression();
 		fixture.operation = Operation::Sub;
 		
 		fixture.calc().unwrap().eq_i32(&-1);
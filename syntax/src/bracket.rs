@@ -30,8 +30,14 @@ impl ArithmeticExpression for Bracket {
 		//*self.0.clone()
 	}
 	
+	// The bracket stays until its content is a single number.
 	fn step_calc(&self) -> (Expression, bool) {
-		(*self.0.clone(), true)
+		if self.0.is_number() {
+			return (*self.0.clone(), true);
+		}
+		
+		let (inner, proceed) = self.0.step_calc();
+		(Bracket::from(inner).to_expression(), proceed)
 	}
 }
 
@@ -39,6 +45,12 @@ impl Bracket {
 	pub fn expression(&self) -> &Expression {
 		&self.0
 	}
+	
+	pub(crate) fn detach(&mut self, stack: &mut Vec<Expression>) {
+		if !self.0.is_number() {
+			stack.push(std::mem::replace(self.0.as_mut(), Expression::placeholder()));
+		}
+	}
 }
 
 #[cfg(test)]
@@ -58,6 +70,16 @@ mod tests {
 		
 		assert!(fixture.1);
 		fixture.0.extract_as_number().number().eq_i32(&300);
+		
+		let bin = BinaryOperation::new(
+			Number::from(NumberValue::from(4)),
+			Number::from(NumberValue::from(5)),
+			Operation::Sub,
+		);
+		let fixture = Bracket::from(bin.to_expression()).step_calc();
+		
+		assert!(fixture.1);
+		fixture.0.extract_as_bracket().expression().extract_as_number().number().eq_i32(&-1);
 	}
 	
 	#[test]
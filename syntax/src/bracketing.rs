@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::{BinaryOperation, Operation};
+use crate::builder::bracket;
+use crate::expression::Expression;
+use crate::misconception::{FlatFormula, FlatToken};
+use crate::number::Number;
+use crate::number_value::NumberValue;
+use crate::variable::Variable;
+
+fn precedence(operation: &Operation) -> u8 {
+	match operation {
+		Operation::Add | Operation::Sub => 0,
+		Operation::Mul | Operation::Div => 1,
+	}
+}
+
+// Brackets wherever the formula would be read differently without them, so that
+// different trees are always written differently.
+fn operand(child: Expression, parent: &Operation, right: bool) -> Expression {
+	match &child {
+		Expression::BinaryOperation(bin) => {
+			let (child_precedence, parent_precedence) = (precedence(bin.operation()), precedence(parent));
+
+			if child_precedence < parent_precedence || (right && child_precedence == parent_precedence) {
+				bracket(child)
+			} else {
+				child
+			}
+		}
+		_ => child,
+	}
+}
+
+// More numbers than this are not bracketed; 10 numbers already give 4862 ways.
+pub const MAX_NUMBERS: usize = 10;
+
+// The bracketings of every run of operands, shortest runs first, so that each run is built once
+// from the ones inside it. trees[start][end] holds those of operands[start..=end].
+fn build(operands: &[Expression], operators: &[Operation]) -> Vec<Expression> {
+	let n = operands.len();
+	let mut trees: Vec<Vec<Vec<Expression>>> = vec![vec![vec![]; n]; n];
+
+	for (i, operand) in operands.iter().enumerate() {
+		trees[i][i] = vec![operand.clone()];
+	}
+
+	for length in 2..=n {
+		for start in 0..=(n - length) {
+			let end = start + length - 1;
+			let mut built = vec![];
+
+			// The operator at split is the last one calculated.
+			for split in start..end {
+				let operation = &operators[split];
+
+				for left in trees[start][split].iter() {
+					for right in trees[split + 1][end].iter() {
+						let left = operand(left.clone(), operation, false);
+						let right = operand(right.clone(), operation, true);
+						built.push(BinaryOperation::new(left, right, operation.clone()).to_expression());
+					}
+				}
+			}
+
+			trees[start][end] = built;
+		}
+	}
+
+	trees[0].pop().unwrap_or_default()
+}
+
+// Every way to put brackets into the formula read as a row of numbers and operators,
+// the brackets already in it ignored. n numbers give the (n - 1)th Catalan number of them.
+// None when there are more than MAX_NUMBERS numbers.
+pub fn bracketings(expression: &Expression) -> Option<Vec<Expression>> {
+	let mut operands = vec![];
+	let mut operators = vec![];
+
+	for token in FlatFormula::from(expression).tokens() {
+		match token {
+			FlatToken::Number(value) => operands.push(Number::from(value.clone()).to_expression()),
+			FlatToken::Variable(name) => operands.push(Variable::from(*name).to_expression()),
+			FlatToken::Operator(operation) => operators.push(operation.clone()),
+			FlatToken::Open | FlatToken::Close => {}
+		}
+	}
+
+	if operands.len() > MAX_NUMBERS {
+		return None;
+	}
+
+	Some(build(&operands, &operators))
+}
+
+// Bracketings with the same value. None for those that cannot be calculated.
+pub struct ValueGroup {
+	value: Option<NumberValue>,
+	expressions: Vec<Expression>,
+}
+
+impl ValueGroup {
+	pub fn value(&self) -> Option<&NumberValue> {
+		self.value.as_ref()
+	}
+
+	pub fn expressions(&self) -> &[Expression] {
+		&self.expressions
+	}
+}
+
+// Smallest value first, the ones that cannot be calculated last.
+pub fn group_by_value(expressions: &[Expression]) -> Vec<ValueGroup> {
+	let mut groups: BTreeMap<(bool, i32), ValueGroup> = BTreeMap::new();
+
+	for expression in expressions {
+		let value = expression.calc().ok();
+		let key = match value {
+			Some(NumberValue::Integer(i)) => (false, i),
+			None => (true, 0),
+		};
+
+		groups
+			.entry(key)
+			.or_insert_with(|| ValueGroup { value, expressions: vec![] })
+			.expressions
+			.push(expression.clone());
+	}
+
+	groups.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::expr;
+
+	use super::*;
+
+	fn value(group: &ValueGroup) -> Option<i32> {
+		group.value().map(|v| {
+			let NumberValue::Integer(i) = v;
+			*i
+		})
+	}
+
+	#[test]
+	fn catalan() {
+		assert_eq!(bracketings(&expr!(7)).unwrap().len(), 1);
+		assert_eq!(bracketings(&expr!(1 - 2)).unwrap().len(), 1);
+		assert_eq!(bracketings(&expr!(1 - 2 - 3 - 4)).unwrap().len(), 5);
+		assert_eq!(bracketings(&expr!(1 + 2 * 3 - 4 / 5 + 6)).unwrap().len(), 42);
+	}
+
+	#[test]
+	fn limits() {
+		assert_eq!(bracketings(&expr!(1 + 2 + 3 + 4 + 5 + 6 + 7 + 8 + 9 + 10)).unwrap().len(), 4862);
+		assert!(bracketings(&expr!(1 + 2 + 3 + 4 + 5 + 6 + 7 + 8 + 9 + 10 + 11)).is_none());
+	}
+
+	#[test]
+	fn trees() {
+		let act = bracketings(&expr!(1 - { 2 - 3 } - 4)).unwrap();
+
+		assert!(act.contains(&expr!(1 - 2 - 3 - 4)));
+		assert!(act.contains(&expr!(1 - { 2 - 3 } - 4)));
+		assert!(act.contains(&expr!(1 - 2 - { 3 - 4 })));
+		assert!(act.contains(&expr!(1 - { 2 - 3 - 4 })));
+		assert!(act.contains(&expr!(1 - { 2 - { 3 - 4 } })));
+
+		let act = bracketings(&expr!(1 + 2 * 3)).unwrap();
+		assert!(act.contains(&expr!(1 + 2 * 3)));
+		assert!(act.contains(&expr!({ 1 + 2 } * 3)));
+	}
+
+	#[test]
+	fn groups() {
+		let act = group_by_value(&bracketings(&expr!(1 - 2 - 3 - 4)).unwrap());
+
+		assert_eq!(act.iter().map(value).collect::<Vec<_>>(), vec![Some(-8), Some(-2), Some(0), Some(6)]);
+		assert_eq!(act[1].expressions().len(), 2);
+
+		let act = group_by_value(&bracketings(&expr!(6 / 2 - 2)).unwrap());
+		assert_eq!(act.iter().map(value).collect::<Vec<_>>(), vec![Some(1), None]);
+	}
+}
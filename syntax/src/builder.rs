@@ -0,0 +1,203 @@
+use std::ops::{Add, Div, Mul, Sub};
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::{BinaryOperation, Operation};
+use crate::bracket::Bracket;
+use crate::expression::Expression;
+use crate::number::Number;
+use crate::number_value::NumberValue;
+use crate::variable::Variable;
+
+pub fn num(value: i32) -> Expression {
+	Number::from(NumberValue::from(value)).to_expression()
+}
+
+pub fn var(name: char) -> Expression {
+	Variable::from(name).to_expression()
+}
+
+pub fn bracket(expression: impl ArithmeticExpression) -> Expression {
+	Bracket::from(expression.to_expression()).to_expression()
+}
+
+impl Add<Self> for Expression {
+	type Output = Expression;
+
+	fn add(self, rhs: Self) -> Self::Output {
+		BinaryOperation::new(self, rhs, Operation::Add).to_expression()
+	}
+}
+
+impl Sub<Self> for Expression {
+	type Output = Expression;
+
+	fn sub(self, rhs: Self) -> Self::Output {
+		BinaryOperation::new(self, rhs, Operation::Sub).to_expression()
+	}
+}
+
+impl Mul<Self> for Expression {
+	type Output = Expression;
+
+	fn mul(self, rhs: Self) -> Self::Output {
+		BinaryOperation::new(self, rhs, Operation::Mul).to_expression()
+	}
+}
+
+impl Div<Self> for Expression {
+	type Output = Expression;
+
+	fn div(self, rhs: Self) -> Self::Output {
+		BinaryOperation::new(self, rhs, Operation::Div).to_expression()
+	}
+}
+
+// Builds an Expression from infix notation at compile time, e.g. `expr! { {1 + 2} * -3 }`.
+// Precedence and associativity follow parser::infix::parser, and `{...}` becomes a Bracket.
+//
+// Internally `@p` expects a primary and `@o` an operator or the end of input.
+// The first bracket holds the pending additive term, the second the pending multiplicative one.
+#[macro_export]
+macro_rules! expr {
+	(@p [$($s:tt)*] [$($m:tt)*] - $n:literal $($rest:tt)*) => {
+		$crate::expr!(@push [$($s)*] [$($m)*] ($crate::builder::num(-$n)) $($rest)*)
+	};
+	(@p [$($s:tt)*] [$($m:tt)*] $n:literal $($rest:tt)*) => {
+		$crate::expr!(@push [$($s)*] [$($m)*] ($crate::builder::num($n)) $($rest)*)
+	};
+	(@p [$($s:tt)*] [$($m:tt)*] $v:ident $($rest:tt)*) => {
+		$crate::expr!(@push [$($s)*] [$($m)*] ($crate::builder::var(stringify!($v).chars().next().unwrap())) $($rest)*)
+	};
+	(@p [$($s:tt)*] [$($m:tt)*] {$($inner:tt)+} $($rest:tt)*) => {
+		$crate::expr!(@push [$($s)*] [$($m)*] ($crate::builder::bracket($crate::expr!($($inner)+))) $($rest)*)
+	};
+
+	(@push [$($s:tt)*] [] $p:tt $($rest:tt)*) => {
+		$crate::expr!(@o [$($s)*] [$p] $($rest)*)
+	};
+	(@push [$($s:tt)*] [$m:tt $op:tt] $p:tt $($rest:tt)*) => {
+		$crate::expr!(@o [$($s)*] [($m $op $p)] $($rest)*)
+	};
+
+	(@o [$($s:tt)*] [$m:tt] * $($rest:tt)+) => {
+		$crate::expr!(@p [$($s)*] [$m *] $($rest)+)
+	};
+	(@o [$($s:tt)*] [$m:tt] / $($rest:tt)+) => {
+		$crate::expr!(@p [$($s)*] [$m /] $($rest)+)
+	};
+	(@o [] [$m:tt] + $($rest:tt)+) => {
+		$crate::expr!(@p [$m +] [] $($rest)+)
+	};
+	(@o [] [$m:tt] - $($rest:tt)+) => {
+		$crate::expr!(@p [$m -] [] $($rest)+)
+	};
+	(@o [$s:tt $op:tt] [$m:tt] + $($rest:tt)+) => {
+		$crate::expr!(@p [($s $op $m) +] [] $($rest)+)
+	};
+	(@o [$s:tt $op:tt] [$m:tt] - $($rest:tt)+) => {
+		$crate::expr!(@p [($s $op $m) -] [] $($rest)+)
+	};
+	(@o [] [$m:tt]) => {
+		$m
+	};
+	(@o [$s:tt $op:tt] [$m:tt]) => {
+		($s $op $m)
+	};
+
+	(@ $($t:tt)*) => {
+		compile_error!("Invalid arithmetic expression.")
+	};
+	($($t:tt)+) => {
+		$crate::expr!(@p [] [] $($t)+)
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::arithmetic_expression::ArithmeticExpression;
+	use crate::binary_operation::{BinaryOperation, Operation};
+	use crate::bracket::Bracket;
+	use crate::expression::Expression;
+	use crate::number::Number;
+	use crate::number_value::NumberValue;
+
+	use super::*;
+
+	fn number(value: i32) -> Number {
+		Number::from(NumberValue::from(value))
+	}
+
+	fn binary(left: impl ArithmeticExpression, right: impl ArithmeticExpression, op: Operation) -> Expression {
+		BinaryOperation::new(left, right, op).to_expression()
+	}
+
+	#[test]
+	fn num_and_bracket() {
+		num(42).extract_as_number().number().eq_i32(&42);
+
+		let act = bracket(num(42));
+		act.extract_as_bracket().expression().extract_as_number().number().eq_i32(&42);
+
+		let act = bracket(number(1));
+		assert!(act == Bracket::from(num(1)).to_expression());
+	}
+
+	#[test]
+	fn operators() {
+		let act = num(2) * (num(3) + num(4));
+		let expected = binary(number(2), binary(number(3), number(4), Operation::Add), Operation::Mul);
+		assert!(act == expected);
+		act.calc().unwrap().eq_i32(&14);
+
+		let act = num(10) - num(4) / num(2);
+		let expected = binary(number(10), binary(number(4), number(2), Operation::Div), Operation::Sub);
+		assert!(act == expected);
+		act.calc().unwrap().eq_i32(&8);
+
+		let act = num(2) * bracket(num(3) + num(4));
+		act.extract_as_binary_operation().right().extract_as_bracket();
+	}
+
+	#[test]
+	fn macro_number() {
+		assert!(expr!(42) == num(42));
+		assert!(expr!(-42) == num(-42));
+		assert!(expr! {{42}} == bracket(num(42)));
+		assert!(expr!(x) == var('x'));
+		assert!(expr! { 2 * {x - 1} } == num(2) * bracket(var('x') - num(1)));
+	}
+
+	#[test]
+	fn macro_precedence() {
+		let act = expr! { 1 + 2 * 3 };
+		assert!(act == num(1) + num(2) * num(3));
+
+		let act = expr! { 1 * 2 + 3 };
+		assert!(act == num(1) * num(2) + num(3));
+
+		let act = expr! { 1 - 2 - 3 };
+		assert!(act == (num(1) - num(2)) - num(3));
+
+		let act = expr! { 8 / 4 / 2 };
+		assert!(act == (num(8) / num(4)) / num(2));
+
+		let act = expr! { 1 + 2 * 3 / 4 - 5 };
+		assert!(act == (num(1) + (num(2) * num(3)) / num(4)) - num(5));
+
+		let act = expr! { 1 - -2 * -3 };
+		assert!(act == num(1) - num(-2) * num(-3));
+	}
+
+	#[test]
+	fn macro_bracket() {
+		let act = expr! { {1+2}*3 };
+		assert!(act == bracket(num(1) + num(2)) * num(3));
+		act.calc().unwrap().eq_i32(&9);
+
+		let act = expr! { {1 + 2 * 3} / {{4 - 5} * {{6 + 7} / 2}} };
+		let expected = bracket(num(1) + num(2) * num(3))
+			/ bracket(bracket(num(4) - num(5)) * bracket(bracket(num(6) + num(7)) / num(2)));
+		assert!(act == expected);
+		act.calc().unwrap().eq_i32(&-1);
+	}
+}
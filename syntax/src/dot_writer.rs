@@ -3,12 +3,15 @@ use std::io;
 use std::io::prelude::*;
 
 use crate::arithmetic_expression::ArithmeticExpression;
-use crate::binary_operation::{BinaryOperation, Operation};
-use crate::bracket::Bracket;
+use crate::binary_operation::Operation;
 use crate::expression::Expression;
+use crate::equation::Equation;
+use crate::expression_arena::{ArenaNode, ExpressionArena, NodeId};
 use crate::id_dispatcher::IdDispatcher;
 use crate::number::Number as NumberExpr;
+use crate::number_theory::FactorTree;
 use crate::number_value::NumberValue;
+use crate::state_space::StateSpace;
 
 use super::id_dispatcher::IdDispatcherError;
 
@@ -74,16 +77,40 @@ fn write_footer<W: Write>(writer: &mut W) -> WriterResult {
 	writeln!(writer, "}}").map_err(|x| x.map())
 }
 
+enum Task<N> {
+	Visit(N),
+	CloseBracket,
+	CloseBinaryOperation,
+}
+
 fn write_expression<W: Write>(
 	writer: &mut W,
 	dispatcher: &mut IdDispatcher,
 	expression: &Expression,
 ) -> WriterResult {
-	match expression {
-		Expression::Number(num) => write_number(writer, dispatcher, &num),
-		Expression::Bracket(bracket) => write_bracket(writer, dispatcher, &bracket),
-		Expression::BinaryOperation(bin) => write_binary_operation(writer, dispatcher, &bin),
+	let mut tasks = vec![Task::Visit(expression)];
+	
+	while let Some(task) = tasks.pop() {
+		match task {
+			Task::Visit(Expression::Number(num)) => write_number(writer, dispatcher, num)?,
+			Task::Visit(Expression::Variable(var)) => write_variable(writer, dispatcher, var.name())?,
+			Task::Visit(Expression::Bracket(bracket)) => {
+				open_bracket(writer, dispatcher)?;
+				tasks.push(Task::CloseBracket);
+				tasks.push(Task::Visit(bracket.expression()));
+			}
+			Task::Visit(Expression::BinaryOperation(bin)) => {
+				open_binary_operation(writer, dispatcher, bin.operation())?;
+				tasks.push(Task::CloseBinaryOperation);
+				tasks.push(Task::Visit(bin.right()));
+				tasks.push(Task::Visit(bin.left()));
+			}
+			Task::CloseBracket => close_bracket(writer, dispatcher)?,
+			Task::CloseBinaryOperation => close_binary_operation(dispatcher)?,
+		}
 	}
+	
+	Ok(())
 }
 
 fn write_direction<W: Write>(writer: &mut W, dispatcher: &IdDispatcher) -> WriterResult {
@@ -100,9 +127,17 @@ fn write_number<W: Write>(
 	writer: &mut W,
 	dispatcher: &mut IdDispatcher,
 	number: &NumberExpr,
+) -> WriterResult {
+	write_number_value(writer, dispatcher, number.number())
+}
+
+fn write_number_value<W: Write>(
+	writer: &mut W,
+	dispatcher: &mut IdDispatcher,
+	number: &NumberValue,
 ) -> WriterResult {
 	let id = dispatcher.get().map_err(|x| x.map())?;
-	let NumberValue::Integer(num) = number.number();
+	let NumberValue::Integer(num) = number;
 	
 	writeln!(writer, "\t{} [label=\"{}\",shape=\"box\"]", id, num).map_err(|x| x.map())?;
 	write_direction(writer, dispatcher)?;
@@ -111,30 +146,36 @@ fn write_number<W: Write>(
 	Ok(())
 }
 
-fn write_bracket<W: Write>(
-	writer: &mut W,
-	dispatcher: &mut IdDispatcher,
-	bracket: &Bracket,
-) -> WriterResult {
-	let id = dispatcher.get().map_err(|err| err.map())?;
+fn write_variable<W: Write>(writer: &mut W, dispatcher: &mut IdDispatcher, name: char) -> WriterResult {
+	let id = dispatcher.get().map_err(|x| x.map())?;
 	
-	writeln!(writer, r#"	{} [label="{{...}}",shape = "house"]"#, id).map_err(|err| err.map())?;
+	writeln!(writer, "\t{} [label=\"{}\",shape=\"ellipse\"]", id, name).map_err(|x| x.map())?;
+	write_direction(writer, dispatcher)?;
 	
-	write_expression(writer, dispatcher, bracket.expression())?;
+	_ = dispatcher.pop().map_err(|x| x.map());
+	Ok(())
+}
+
+fn open_bracket<W: Write>(writer: &mut W, dispatcher: &mut IdDispatcher) -> WriterResult {
+	let id = dispatcher.get().map_err(|err| err.map())?;
 	
+	writeln!(writer, r#"	{} [label="{{...}}",shape = "house"]"#, id).map_err(|err| err.map())
+}
+
+fn close_bracket<W: Write>(writer: &mut W, dispatcher: &mut IdDispatcher) -> WriterResult {
 	write_direction(writer, dispatcher)?;
 	_ = dispatcher.pop().map_err(|err| err.map())?;
 	Ok(())
 }
 
-fn write_binary_operation<W: Write>(
+fn open_binary_operation<W: Write>(
 	writer: &mut W,
 	dispatcher: &mut IdDispatcher,
-	binary_operation: &BinaryOperation,
+	operation: &Operation,
 ) -> WriterResult {
 	let id = dispatcher.get().map_err(|err| err.map())?;
 	
-	let op = match binary_operation.operation() {
+	let op = match operation {
 		Operation::Add => "+",
 		Operation::Sub => "-",
 		Operation::Mul => "*",
@@ -142,13 +183,54 @@ fn write_binary_operation<W: Write>(
 	};
 	
 	writeln!(writer, r#"	{} [label="{}",shape = "hexagon"]"#, id, op).map_err(|err| err.map())?;
-	write_direction(writer, dispatcher)?;
+	write_direction(writer, dispatcher)
+}
+
+fn close_binary_operation(dispatcher: &mut IdDispatcher) -> WriterResult {
+	dispatcher.pop().map_err(|err| err.map())?;
+	Ok(())
+}
+
+fn write_arena_node<W: Write>(
+	writer: &mut W,
+	dispatcher: &mut IdDispatcher,
+	arena: &ExpressionArena,
+	node: NodeId,
+) -> WriterResult {
+	let mut tasks = vec![Task::Visit(node)];
 	
-	write_expression(writer, dispatcher, binary_operation.left())?;
-	write_expression(writer, dispatcher, binary_operation.right())?;
+	while let Some(task) = tasks.pop() {
+		match task {
+			Task::Visit(node) => match arena.node(node) {
+				ArenaNode::Number(num) => write_number_value(writer, dispatcher, num)?,
+				ArenaNode::Variable(name) => write_variable(writer, dispatcher, *name)?,
+				ArenaNode::Bracket(inner) => {
+					open_bracket(writer, dispatcher)?;
+					tasks.push(Task::CloseBracket);
+					tasks.push(Task::Visit(*inner));
+				}
+				ArenaNode::BinaryOperation(left, right, operation) => {
+					open_binary_operation(writer, dispatcher, operation)?;
+					tasks.push(Task::CloseBinaryOperation);
+					tasks.push(Task::Visit(*right));
+					tasks.push(Task::Visit(*left));
+				}
+			},
+			Task::CloseBracket => close_bracket(writer, dispatcher)?,
+			Task::CloseBinaryOperation => close_binary_operation(dispatcher)?,
+		}
+	}
 	
-	dispatcher.pop().map_err(|err| err.map())?;
+	Ok(())
+}
+
+pub fn write_arena_dot<W: Write>(writer: &mut W, arena: &ExpressionArena, id: NodeId) -> WriterResult {
+	write_header(writer)?;
+	let mut dispatcher = IdDispatcher::new();
 	
+	write_arena_node(writer, &mut dispatcher, arena, id)?;
+	
+	write_footer(writer)?;
 	Ok(())
 }
 
@@ -166,10 +248,87 @@ pub fn write_dot<W: Write, E: ArithmeticExpression>(
 	Ok(())
 }
 
+// Both sides hang from a root "=" node.
+pub fn write_equation_dot<W: Write>(writer: &mut W, equation: &Equation) -> WriterResult {
+	write_header(writer)?;
+	let mut dispatcher = IdDispatcher::new();
+	let id = dispatcher.get().map_err(|err| err.map())?;
+	
+	writeln!(writer, r#"	{} [label="=",shape = "diamond"]"#, id).map_err(|err| err.map())?;
+	write_expression(writer, &mut dispatcher, equation.left())?;
+	write_expression(writer, &mut dispatcher, equation.right())?;
+	dispatcher.pop().map_err(|err| err.map())?;
+	
+	write_footer(writer)?;
+	Ok(())
+}
+
+// Each composite number has the prime split off and the rest as its children, the
+// primes drawn as boxes like the numbers of a formula.
+pub fn write_factor_tree_dot<W: Write>(writer: &mut W, tree: &FactorTree) -> WriterResult {
+	write_header(writer)?;
+	let mut dispatcher = IdDispatcher::new();
+	let mut node = Some(tree);
+	let mut depth = 0;
+	
+	while let Some(current) = node {
+		match current.prime() {
+			Some(prime) => {
+				let id = dispatcher.get().map_err(|err| err.map())?;
+				writeln!(writer, r#"	{} [label="{}",shape = "circle"]"#, id, current.value()).map_err(|err| err.map())?;
+				write_direction(writer, &dispatcher)?;
+				write_number_value(writer, &mut dispatcher, &NumberValue::from(prime))?;
+				depth += 1;
+			}
+			None => write_number_value(writer, &mut dispatcher, &NumberValue::from(current.value()))?,
+		}
+		
+		node = current.rest();
+	}
+	
+	for _ in 0..depth {
+		dispatcher.pop().map_err(|err| err.map())?;
+	}
+	
+	write_footer(writer)?;
+	Ok(())
+}
+
+// One node per state, the terminal ones drawn double, and one edge per reduction
+// labelled with the path of the reduced node.
+pub fn write_state_space_dot<W: Write>(
+	writer: &mut W,
+	space: &StateSpace,
+	label: impl Fn(&Expression) -> String,
+) -> WriterResult {
+	let terminals = space.terminals();
+	
+	writeln!(writer, "digraph state_space{{").map_err(|x| x.map())?;
+	writeln!(writer, r#"   node [fontname = "Cascadia Code Regular"];"#).map_err(|x| x.map())?;
+	writeln!(writer).map_err(|x| x.map())?;
+	
+	for (id, state) in space.states().iter().enumerate() {
+		let label = label(state).replace('\\', "\\\\").replace('"', "\\\"");
+		let peripheries = if terminals.contains(&id) { 2 } else { 1 };
+		
+		writeln!(writer, r#"	{} [label="{}",shape="box",peripheries={}]"#, id, label, peripheries)
+			.map_err(|x| x.map())?;
+	}
+	
+	for edge in space.edges() {
+		writeln!(writer, r#"	{} -> {} [label="{}"]"#, edge.from(), edge.to(), edge.path()).map_err(|x| x.map())?;
+	}
+	
+	write_footer(writer)
+}
+
 #[cfg(test)]
 mod tests {
 	use std::io::Cursor;
 	
+	use crate::binary_operation::BinaryOperation;
+	use crate::bracket::Bracket;
+	
 	use super::*;
 	
 	#[test]
@@ -217,7 +376,7 @@ mod tests {
 		let num = NumberExpr::from(NumberValue::from(42));
 		let brackert = Bracket::from(num.to_expression());
 		
-		_ = write_bracket(&mut cursor, &mut dispatcher, &brackert).unwrap();
+		write_expression(&mut cursor, &mut dispatcher, &brackert.to_expression()).unwrap();
 		let actual = String::from_utf8(cursor.into_inner()).unwrap();
 		
 		
@@ -240,7 +399,7 @@ mod tests {
 		
 		let bin = BinaryOperation::new(left, right, Operation::Sub);
 		
-		write_binary_operation(&mut cursor, &mut dispatcher, &bin).unwrap();
+		write_expression(&mut cursor, &mut dispatcher, &bin.to_expression()).unwrap();
 		
 		let act = String::from_utf8(cursor.into_inner()).unwrap();
 		
@@ -301,4 +460,103 @@ mod tests {
 "#
 		);
 	}
+	
+	#[test]
+	fn state_space_dot() {
+		use crate::state_space::DEFAULT_STATE_LIMIT;
+		
+		let left = NumberExpr::from(NumberValue::from(1));
+		let right = NumberExpr::from(NumberValue::from(2));
+		let bin = BinaryOperation::new(left, right, Operation::Add).to_expression();
+		
+		let space = StateSpace::explore(&bin, DEFAULT_STATE_LIMIT);
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_state_space_dot(&mut cursor, &space, |e| format!("\"{}\"", e.depth())).unwrap();
+		
+		assert_eq!(
+			String::from_utf8(cursor.into_inner()).unwrap(),
+			r#"digraph state_space{
+   node [fontname = "Cascadia Code Regular"];
+
+	0 [label="\"2\"",shape="box",peripheries=1]
+	1 [label="\"1\"",shape="box",peripheries=2]
+	0 -> 1 [label="/"]
+}
+"#
+		);
+	}
+	
+	#[test]
+	fn arena_dot() {
+		let left = BinaryOperation::new(
+			NumberExpr::from(NumberValue::from(42)),
+			NumberExpr::from(NumberValue::from(100)),
+			Operation::Add,
+		);
+		let right = BinaryOperation::new(
+			NumberExpr::from(NumberValue::from(2)),
+			NumberExpr::from(NumberValue::from(3)),
+			Operation::Mul,
+		);
+		let bin = BinaryOperation::new(Bracket::from(left.to_expression()), right, Operation::Div)
+			.to_expression();
+		
+		let mut expected = Cursor::<Vec<u8>>::default();
+		write_dot(&mut expected, &bin).unwrap();
+		
+		let mut arena = ExpressionArena::new();
+		let id = arena.insert(&bin);
+		
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_arena_dot(&mut cursor, &arena, id).unwrap();
+		
+		assert_eq!(
+			String::from_utf8(cursor.into_inner()).unwrap(),
+			String::from_utf8(expected.into_inner()).unwrap()
+		);
+	}
+	
+	#[test]
+	fn deep_tree() {
+		const DEPTH: usize = 100_000;
+		let mut expr = NumberExpr::from(NumberValue::from(0)).to_expression();
+		
+		for _ in 0..DEPTH {
+			expr = Bracket::from(expr).to_expression();
+		}
+		
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_dot(&mut cursor, &expr).unwrap();
+		
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		assert_eq!(act.lines().count(), 3 + DEPTH * 2 + 1 + 1);
+		assert!(act.contains(&format!("\t{} [label=\"0\",shape=\"box\"]", DEPTH + 1)));
+	}
+	
+	#[test]
+	fn equation() {
+		let equation = Equation::new(crate::expr!(x), crate::expr!(3));
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_equation_dot(&mut cursor, &equation).unwrap();
+		
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		assert!(act.contains("\t1 [label=\"=\",shape = \"diamond\"]\n"));
+		assert!(act.contains("\t2 [label=\"x\",shape=\"ellipse\"]\n\t1 -> 2\n"));
+		assert!(act.contains("\t3 [label=\"3\",shape=\"box\"]\n\t1 -> 3\n"));
+		assert!(act.ends_with("}\n"));
+	}
+	
+	#[test]
+	fn factor_tree() {
+		let tree = crate::number_theory::factor_tree(12).unwrap();
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		write_factor_tree_dot(&mut cursor, &tree).unwrap();
+		
+		let act = String::from_utf8(cursor.into_inner()).unwrap();
+		assert!(act.contains("\t1 [label=\"12\",shape = \"circle\"]\n"));
+		assert!(act.contains("\t2 [label=\"2\",shape=\"box\"]\n\t1 -> 2\n"));
+		assert!(act.contains("\t3 [label=\"6\",shape = \"circle\"]\n\t1 -> 3\n"));
+		assert!(act.contains("\t5 [label=\"3\",shape=\"box\"]\n\t3 -> 5\n"));
+		assert!(act.ends_with("}\n"));
+	}
 }
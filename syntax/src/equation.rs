@@ -0,0 +1,502 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::{BinaryOperation, Operation};
+use crate::builder::num;
+use crate::expression::Expression;
+use crate::rational::Rational;
+use crate::variable::Variable;
+
+// Two formulas joined by =.
+pub struct Equation {
+	left: Expression,
+	right: Expression,
+}
+
+impl Equation {
+	pub fn new(left: Expression, right: Expression) -> Self {
+		Equation { left, right }
+	}
+
+	pub fn left(&self) -> &Expression {
+		&self.left
+	}
+
+	pub fn right(&self) -> &Expression {
+		&self.right
+	}
+}
+
+impl Clone for Equation {
+	fn clone(&self) -> Self {
+		Equation::new(self.left.clone(), self.right.clone())
+	}
+}
+
+impl PartialEq for Equation {
+	fn eq(&self, other: &Self) -> bool {
+		self.left == other.left && self.right == other.right
+	}
+}
+
+pub enum EquationError {
+	// x * x, or a division by a formula with x in it.
+	NotLinear,
+	// More than one unknown, such as x and y.
+	SeveralUnknowns(char, char),
+	DivisionByZero,
+	Overflow,
+}
+
+impl Clone for EquationError {
+	fn clone(&self) -> Self {
+		match self {
+			EquationError::NotLinear => EquationError::NotLinear,
+			EquationError::SeveralUnknowns(a, b) => EquationError::SeveralUnknowns(*a, *b),
+			EquationError::DivisionByZero => EquationError::DivisionByZero,
+			EquationError::Overflow => EquationError::Overflow,
+		}
+	}
+}
+
+impl Debug for EquationError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			EquationError::NotLinear => write!(f, "NotLinear"),
+			EquationError::SeveralUnknowns(a, b) => write!(f, "SeveralUnknowns:{},{}", a, b),
+			EquationError::DivisionByZero => write!(f, "DivisionByZero"),
+			EquationError::Overflow => write!(f, "Overflow"),
+		}
+	}
+}
+
+impl Display for EquationError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			EquationError::NotLinear => write!(f, "the equation is not linear"),
+			EquationError::SeveralUnknowns(a, b) => write!(f, "the equation has more than one unknown: {} and {}", a, b),
+			EquationError::DivisionByZero => write!(f, "the equation divides by zero"),
+			EquationError::Overflow => write!(f, "a number in the equation is too large"),
+		}
+	}
+}
+
+impl Error for EquationError {}
+
+// coefficient * x + constant
+#[derive(Clone, Copy)]
+struct Linear {
+	coefficient: Rational,
+	constant: Rational,
+}
+
+impl Linear {
+	fn constant(value: Rational) -> Self {
+		Linear {
+			coefficient: Rational::from(0),
+			constant: value,
+		}
+	}
+
+	fn is_constant(&self) -> bool {
+		self.coefficient.is_zero()
+	}
+}
+
+fn checked(value: Option<Rational>) -> Result<Rational, EquationError> {
+	value.ok_or(EquationError::Overflow)
+}
+
+fn combine(operation: &Operation, left: Linear, right: Linear) -> Result<Linear, EquationError> {
+	match operation {
+		Operation::Add => Ok(Linear {
+			coefficient: checked(left.coefficient.checked_add(&right.coefficient))?,
+			constant: checked(left.constant.checked_add(&right.constant))?,
+		}),
+		Operation::Sub => Ok(Linear {
+			coefficient: checked(left.coefficient.checked_sub(&right.coefficient))?,
+			constant: checked(left.constant.checked_sub(&right.constant))?,
+		}),
+		Operation::Mul => {
+			let (variable, factor) = match (left.is_constant(), right.is_constant()) {
+				(_, true) => (left, right.constant),
+				(true, false) => (right, left.constant),
+				(false, false) => return Err(EquationError::NotLinear),
+			};
+
+			Ok(Linear {
+				coefficient: checked(variable.coefficient.checked_mul(&factor))?,
+				constant: checked(variable.constant.checked_mul(&factor))?,
+			})
+		}
+		Operation::Div => {
+			if !right.is_constant() {
+				return Err(EquationError::NotLinear);
+			}
+
+			if right.constant.is_zero() {
+				return Err(EquationError::DivisionByZero);
+			}
+
+			Ok(Linear {
+				coefficient: checked(left.coefficient.checked_div(&right.constant))?,
+				constant: checked(left.constant.checked_div(&right.constant))?,
+			})
+		}
+	}
+}
+
+fn linear(expression: &Expression, unknown: &mut Option<char>) -> Result<Linear, EquationError> {
+	// An operation is combined once both its operands are, the left one first.
+	let mut stack = vec![(expression, false)];
+	let mut values = Vec::<Linear>::new();
+
+	while let Some((expr, operands_done)) = stack.pop() {
+		match expr {
+			Expression::Number(num) => values.push(Linear::constant(Rational::from(num.number()))),
+			Expression::Variable(var) => match unknown {
+				Some(name) if *name != var.name() => return Err(EquationError::SeveralUnknowns(*name, var.name())),
+				_ => {
+					*unknown = Some(var.name());
+					values.push(Linear {
+						coefficient: Rational::from(1),
+						constant: Rational::from(0),
+					});
+				}
+			},
+			Expression::Bracket(bracket) => stack.push((bracket.expression(), false)),
+			Expression::BinaryOperation(bin) if !operands_done => {
+				stack.push((expr, true));
+				stack.push((bin.right(), false));
+				stack.push((bin.left(), false));
+			}
+			Expression::BinaryOperation(bin) => {
+				let right = values.pop().unwrap();
+				let left = values.pop().unwrap();
+				values.push(combine(bin.operation(), left, right)?);
+			}
+		}
+	}
+
+	Ok(values.pop().unwrap())
+}
+
+fn integer(value: i64) -> Result<Expression, EquationError> {
+	i32::try_from(value).map(num).map_err(|_| EquationError::Overflow)
+}
+
+fn operation(left: Expression, right: Expression, operation: Operation) -> Expression {
+	BinaryOperation::new(left, right, operation).to_expression()
+}
+
+// A whole number, or p / q.
+fn value(value: &Rational) -> Result<Expression, EquationError> {
+	let numerator = integer(value.numerator())?;
+
+	if value.is_integer() {
+		Ok(numerator)
+	} else {
+		Ok(operation(numerator, integer(value.denominator())?, Operation::Div))
+	}
+}
+
+// x, c * x, or p * x / q.
+fn term(coefficient: &Rational, unknown: char) -> Result<Expression, EquationError> {
+	let x = Variable::from(unknown).to_expression();
+
+	if *coefficient == Rational::from(1) {
+		return Ok(x);
+	}
+
+	let product = operation(integer(coefficient.numerator())?, x, Operation::Mul);
+
+	if coefficient.is_integer() {
+		Ok(product)
+	} else {
+		Ok(operation(product, integer(coefficient.denominator())?, Operation::Div))
+	}
+}
+
+// The parts joined by + and −, each with the sign it is added with. 0 when there are none.
+fn sum(parts: Vec<(Rational, bool)>, unknown: char) -> Result<Expression, EquationError> {
+	let mut result: Option<Expression> = None;
+
+	for (part, is_term) in parts.into_iter().filter(|(p, _)| !p.is_zero()) {
+		let negative = part.numerator() < 0;
+		let magnitude = if negative && result.is_some() {
+			checked(Rational::from(0).checked_sub(&part))?
+		} else {
+			part
+		};
+
+		let expression = if is_term {
+			term(&magnitude, unknown)?
+		} else {
+			value(&magnitude)?
+		};
+
+		result = Some(match result {
+			None => expression,
+			Some(left) if negative => operation(left, expression, Operation::Sub),
+			Some(left) => operation(left, expression, Operation::Add),
+		});
+	}
+
+	Ok(result.unwrap_or_else(|| num(0)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquationStepKind {
+	// Each side written as a multiple of x and a number.
+	Expand,
+	// The terms with x moved to the left, the numbers to the right, with their signs changed.
+	Transpose,
+	// Like terms added together.
+	Combine,
+	// Both sides divided by the coefficient of x.
+	Divide,
+}
+
+pub struct EquationStep {
+	kind: EquationStepKind,
+	equation: Equation,
+	// The coefficient divided by, for Divide.
+	divisor: Option<Rational>,
+}
+
+impl EquationStep {
+	pub fn kind(&self) -> EquationStepKind {
+		self.kind
+	}
+
+	pub fn equation(&self) -> &Equation {
+		&self.equation
+	}
+}
+
+impl Display for EquationStep {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match (self.kind, self.divisor) {
+			(EquationStepKind::Expand, _) => write!(f, "expand both sides"),
+			(EquationStepKind::Transpose, _) => {
+				write!(f, "move the unknown to the left and the numbers to the right, changing their signs")
+			}
+			(EquationStepKind::Combine, _) => write!(f, "combine like terms"),
+			(EquationStepKind::Divide, Some(divisor)) => write!(f, "divide both sides by {}", divisor),
+			(EquationStepKind::Divide, None) => write!(f, "divide both sides"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Solution {
+	Unique(Rational),
+	// Both sides differ by a number whatever x is, as in x + 1 = x.
+	NoSolution,
+	// Both sides are the same whatever x is, as in 2 * x = x + x.
+	Infinite,
+}
+
+pub struct EquationTrace {
+	equation: Equation,
+	unknown: char,
+	steps: Vec<EquationStep>,
+	solution: Solution,
+}
+
+impl EquationTrace {
+	pub fn equation(&self) -> &Equation {
+		&self.equation
+	}
+
+	// x when the equation has no unknown at all.
+	pub fn unknown(&self) -> char {
+		self.unknown
+	}
+
+	pub fn steps(&self) -> &[EquationStep] {
+		&self.steps
+	}
+
+	pub fn solution(&self) -> Solution {
+		self.solution
+	}
+}
+
+impl Display for Solution {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Solution::Unique(value) => write!(f, "{}", value),
+			Solution::NoSolution => write!(f, "no solution"),
+			Solution::Infinite => write!(f, "every number is a solution"),
+		}
+	}
+}
+
+// Solves a linear equation in one unknown, recording the equation after each step.
+// Steps that would not change the equation are left out.
+pub fn solve_equation(equation: &Equation) -> Result<EquationTrace, EquationError> {
+	let mut unknown = None;
+	let left = linear(equation.left(), &mut unknown)?;
+	let right = linear(equation.right(), &mut unknown)?;
+	let name = unknown.unwrap_or('x');
+
+	let mut steps = Vec::<EquationStep>::new();
+	let mut push = |kind: EquationStepKind, next: Equation, divisor: Option<Rational>| {
+		let previous = steps.last().map_or(equation, |s| &s.equation);
+
+		if *previous != next {
+			steps.push(EquationStep {
+				kind,
+				equation: next,
+				divisor,
+			});
+		}
+	};
+
+	let expanded = Equation::new(
+		sum(vec![(left.coefficient, true), (left.constant, false)], name)?,
+		sum(vec![(right.coefficient, true), (right.constant, false)], name)?,
+	);
+	push(EquationStepKind::Expand, expanded, None);
+
+	let negate = |value: &Rational| checked(Rational::from(0).checked_sub(value));
+
+	if !right.coefficient.is_zero() || !left.constant.is_zero() {
+		let transposed = Equation::new(
+			sum(vec![(left.coefficient, true), (negate(&right.coefficient)?, true)], name)?,
+			sum(vec![(right.constant, false), (negate(&left.constant)?, false)], name)?,
+		);
+		push(EquationStepKind::Transpose, transposed, None);
+	}
+
+	let coefficient = checked(left.coefficient.checked_sub(&right.coefficient))?;
+	let constant = checked(right.constant.checked_sub(&left.constant))?;
+
+	let combined = Equation::new(
+		sum(vec![(coefficient, true)], name)?,
+		sum(vec![(constant, false)], name)?,
+	);
+	push(EquationStepKind::Combine, combined, None);
+
+	let solution = if coefficient.is_zero() {
+		if constant.is_zero() {
+			Solution::Infinite
+		} else {
+			Solution::NoSolution
+		}
+	} else {
+		let value = checked(constant.checked_div(&coefficient))?;
+		let divided = Equation::new(Variable::from(name).to_expression(), sum(vec![(value, false)], name)?);
+		push(EquationStepKind::Divide, divided, Some(coefficient));
+
+		Solution::Unique(value)
+	};
+
+	Ok(EquationTrace {
+		equation: equation.clone(),
+		unknown: name,
+		steps,
+		solution,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::builder::{bracket, var};
+	use crate::expr;
+
+	use super::*;
+
+	fn x() -> Expression {
+		var('x')
+	}
+
+	fn kinds(trace: &EquationTrace) -> Vec<EquationStepKind> {
+		trace.steps().iter().map(|s| s.kind()).collect()
+	}
+
+	#[test]
+	fn solve() {
+		// 2 * x + 3 = 3 * {x - 1}
+		let fixture = Equation::new(num(2) * x() + num(3), num(3) * bracket(x() - num(1)));
+		let act = solve_equation(&fixture).unwrap();
+
+		assert_eq!(act.solution(), Solution::Unique(Rational::from(6)));
+		assert_eq!(
+			kinds(&act),
+			vec![
+				EquationStepKind::Expand,
+				EquationStepKind::Transpose,
+				EquationStepKind::Combine,
+				EquationStepKind::Divide
+			]
+		);
+
+		let steps = act.steps();
+		assert!(*steps[0].equation() == Equation::new(num(2) * x() + num(3), num(3) * x() - num(3)));
+		assert!(*steps[1].equation() == Equation::new(num(2) * x() - num(3) * x(), num(-3) - num(3)));
+		assert!(*steps[2].equation() == Equation::new(num(-1) * x(), num(-6)));
+		assert!(*steps[3].equation() == Equation::new(x(), num(6)));
+		assert_eq!(steps[3].to_string(), "divide both sides by -1");
+	}
+
+	#[test]
+	fn fraction() {
+		// 4 * x = 6 gives 3 / 2, written as a division.
+		let act = solve_equation(&Equation::new(num(4) * x(), num(6))).unwrap();
+
+		assert_eq!(act.solution(), Solution::Unique(Rational::new(3, 2).unwrap()));
+		assert_eq!(kinds(&act), vec![EquationStepKind::Divide]);
+		assert!(*act.steps()[0].equation() == Equation::new(x(), expr!(3 / 2)));
+
+		// Already solved.
+		let act = solve_equation(&Equation::new(x(), num(5))).unwrap();
+		assert!(act.steps().is_empty());
+		assert_eq!(act.solution(), Solution::Unique(Rational::from(5)));
+	}
+
+	#[test]
+	fn special() {
+		let act = solve_equation(&Equation::new(x() + num(1), x())).unwrap();
+		assert_eq!(act.solution(), Solution::NoSolution);
+		assert!(*act.steps().last().unwrap().equation() == Equation::new(num(0), num(-1)));
+
+		let act = solve_equation(&Equation::new(num(2) * x(), x() + x())).unwrap();
+		assert_eq!(act.solution(), Solution::Infinite);
+		assert_eq!(act.solution().to_string(), "every number is a solution");
+	}
+
+	#[test]
+	fn errors() {
+		let act = solve_equation(&Equation::new(x() * x(), num(4))).err().unwrap();
+		assert!(matches!(act, EquationError::NotLinear));
+
+		let act = solve_equation(&Equation::new(num(1) / x(), num(4))).err().unwrap();
+		assert!(matches!(act, EquationError::NotLinear));
+
+		// x - x is no longer an unknown, but it is zero.
+		let act = solve_equation(&Equation::new(num(1) / bracket(x() - x()), num(4))).err().unwrap();
+		assert!(matches!(act, EquationError::DivisionByZero));
+
+		let y = var('y');
+		let act = solve_equation(&Equation::new(x() + y, num(4))).err().unwrap();
+		assert_eq!(act.to_string(), "the equation has more than one unknown: x and y");
+	}
+
+	#[test]
+	fn deep_tree() {
+		let mut fixture = x() - num(1);
+
+		for _ in 0..100_000 {
+			fixture = bracket(fixture);
+		}
+
+		let mut unknown = None;
+		let act = linear(&fixture, &mut unknown).ok().unwrap();
+
+		assert_eq!(unknown, Some('x'));
+		assert_eq!((act.coefficient, act.constant), (Rational::from(1), Rational::from(-1)));
+	}
+}
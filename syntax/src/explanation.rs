@@ -0,0 +1,352 @@
+use crate::binary_operation::Operation;
+use crate::expression::Expression;
+use crate::node_path::{Direction, NodePath};
+use crate::number_value::NumberValue;
+use crate::step::{Step, StepKind};
+use crate::step_choice::scope_of;
+
+// Why an operation is calculated at this point.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reason {
+	Plain,
+	InsideBracket,
+	// × or ÷ ahead of the given + or − in the same part of the formula.
+	Precedence(Operation),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Explanation {
+	Evaluate(Operation, NumberValue, NumberValue, Reason),
+	RemoveBracket(NumberValue),
+	// RPN: a number pushed onto the stack.
+	Push(NumberValue),
+	// RPN: two values popped, combined and the result pushed.
+	Apply(Operation, NumberValue, NumberValue, NumberValue),
+	// The numerator and the denominator of the fraction in the brackets.
+	RemoveFractionBracket(NumberValue, NumberValue),
+	// The two denominators and their least common multiple.
+	CommonDenominator(NumberValue, NumberValue, NumberValue),
+	CombineNumerators(Operation),
+	MultiplyFractions,
+	// The fraction divided by.
+	Reciprocal(NumberValue, NumberValue),
+	// The fraction and what both of its parts are divided by.
+	ReduceFraction(NumberValue, NumberValue, NumberValue),
+}
+
+// Turns an explanation into a sentence. Implement it for other languages or wordings.
+pub trait MessageCatalog {
+	fn message(&self, explanation: &Explanation) -> String;
+}
+
+pub struct Japanese;
+
+pub struct English;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+	Ja,
+	En,
+}
+
+impl Language {
+	pub fn from_tag(tag: &str) -> Option<Self> {
+		match tag {
+			"ja" => Some(Language::Ja),
+			"en" => Some(Language::En),
+			_ => None,
+		}
+	}
+
+	pub fn catalog(&self) -> &'static dyn MessageCatalog {
+		match self {
+			Language::Ja => &Japanese,
+			Language::En => &English,
+		}
+	}
+}
+
+fn number(value: &NumberValue) -> String {
+	let NumberValue::Integer(i) = value;
+	i.to_string()
+}
+
+fn ratio(numerator: &NumberValue, denominator: &NumberValue) -> String {
+	format!("{}/{}", number(numerator), number(denominator))
+}
+
+fn symbol(operation: &Operation) -> &'static str {
+	match operation {
+		Operation::Add => "+",
+		Operation::Sub => "−",
+		Operation::Mul => "×",
+		Operation::Div => "÷",
+	}
+}
+
+fn formula(operation: &Operation, left: &NumberValue, right: &NumberValue) -> String {
+	format!("{} {} {}", number(left), symbol(operation), number(right))
+}
+
+fn japanese_name(operation: &Operation) -> &'static str {
+	match operation {
+		Operation::Add => "たし算",
+		Operation::Sub => "ひき算",
+		Operation::Mul => "かけ算",
+		Operation::Div => "わり算",
+	}
+}
+
+fn english_name(operation: &Operation) -> &'static str {
+	match operation {
+		Operation::Add => "addition",
+		Operation::Sub => "subtraction",
+		Operation::Mul => "multiplication",
+		Operation::Div => "division",
+	}
+}
+
+fn english_verb(operation: &Operation) -> &'static str {
+	match operation {
+		Operation::Add => "Add",
+		Operation::Sub => "Subtract",
+		Operation::Mul => "Multiply",
+		Operation::Div => "Divide",
+	}
+}
+
+impl MessageCatalog for Japanese {
+	fn message(&self, explanation: &Explanation) -> String {
+		match explanation {
+			Explanation::Evaluate(op, left, right, Reason::Plain) => {
+				format!("{} を計算します", formula(op, left, right))
+			}
+			Explanation::Evaluate(op, left, right, Reason::InsideBracket) => {
+				format!("{} を先に計算します（かっこの中が先）", formula(op, left, right))
+			}
+			Explanation::Evaluate(op, left, right, Reason::Precedence(after)) => format!(
+				"{} を先に計算します（{}は{}より先）",
+				formula(op, left, right),
+				japanese_name(op),
+				japanese_name(after)
+			),
+			Explanation::RemoveBracket(value) => format!("({}) のかっこを外します", number(value)),
+			Explanation::Push(value) => format!("{} をスタックに積みます", number(value)),
+			Explanation::Apply(op, left, right, result) => format!(
+				"{} と {} を取り出して {} = {} を計算し、結果を積みます",
+				number(left),
+				number(right),
+				formula(op, left, right),
+				number(result)
+			),
+			Explanation::RemoveFractionBracket(n, d) => format!("({}) のかっこを外します", ratio(n, d)),
+			Explanation::CommonDenominator(left, right, lcm) => format!(
+				"{} と {} の最小公倍数 {} で通分します",
+				number(left),
+				number(right),
+				number(lcm)
+			),
+			Explanation::CombineNumerators(Operation::Sub) => String::from("分子どうしをひきます"),
+			Explanation::CombineNumerators(_) => String::from("分子どうしをたします"),
+			Explanation::MultiplyFractions => String::from("分子どうし、分母どうしをかけます"),
+			Explanation::Reciprocal(n, d) => format!("{} の逆数をかけます", ratio(n, d)),
+			Explanation::ReduceFraction(n, d, NumberValue::Integer(1)) => format!("{} を整理します", ratio(n, d)),
+			Explanation::ReduceFraction(n, d, divisor) => format!("{} を {} で約分します", ratio(n, d), number(divisor)),
+		}
+	}
+}
+
+impl MessageCatalog for English {
+	fn message(&self, explanation: &Explanation) -> String {
+		match explanation {
+			Explanation::Evaluate(op, left, right, Reason::Plain) => {
+				format!("{} {}", english_verb(op), formula(op, left, right))
+			}
+			Explanation::Evaluate(op, left, right, Reason::InsideBracket) => format!(
+				"{} {} first because it is inside brackets",
+				english_verb(op),
+				formula(op, left, right)
+			),
+			Explanation::Evaluate(op, left, right, Reason::Precedence(after)) => format!(
+				"{} {} first because {} precedes {}",
+				english_verb(op),
+				formula(op, left, right),
+				english_name(op),
+				english_name(after)
+			),
+			Explanation::RemoveBracket(value) => format!("Remove the brackets around {}", number(value)),
+			Explanation::Push(value) => format!("Push {} onto the stack", number(value)),
+			Explanation::Apply(op, left, right, result) => format!(
+				"Pop {} and {}, calculate {} = {} and push the result",
+				number(left),
+				number(right),
+				formula(op, left, right),
+				number(result)
+			),
+			Explanation::RemoveFractionBracket(n, d) => format!("Remove the brackets around {}", ratio(n, d)),
+			Explanation::CommonDenominator(left, right, lcm) => format!(
+				"Rewrite the fractions over {}, the least common multiple of {} and {}",
+				number(lcm),
+				number(left),
+				number(right)
+			),
+			Explanation::CombineNumerators(Operation::Sub) => String::from("Subtract the numerators"),
+			Explanation::CombineNumerators(_) => String::from("Add the numerators"),
+			Explanation::MultiplyFractions => String::from("Multiply the numerators and the denominators"),
+			Explanation::Reciprocal(n, d) => format!("Multiply by the reciprocal of {}", ratio(n, d)),
+			Explanation::ReduceFraction(n, d, NumberValue::Integer(1)) => format!("Simplify {}", ratio(n, d)),
+			Explanation::ReduceFraction(n, d, divisor) => format!("Reduce {} by {}", ratio(n, d), number(divisor)),
+		}
+	}
+}
+
+// The nearest + or − the operation at the path is an operand of, within its bracket.
+fn pending_additive(step: &Step) -> Option<Operation> {
+	let directions = step.path().directions();
+	let start = scope_of(step.path()).directions().len();
+	let mut found = None;
+
+	for len in start..directions.len() {
+		let path = NodePath::from(directions[..len].to_vec());
+
+		if let Some(Expression::BinaryOperation(bin)) = path.resolve(step.before()) {
+			if matches!(bin.operation(), Operation::Add | Operation::Sub) {
+				found = Some(bin.operation().clone());
+			}
+		}
+	}
+
+	found
+}
+
+// None when the step does not carry what its kind needs, such as a bracket without a
+// number in it.
+pub fn explain(step: &Step) -> Option<Explanation> {
+	match (step.kind(), step.operation(), step.operands()) {
+		(StepKind::BracketRemoved, _, [n, d]) => Some(Explanation::RemoveFractionBracket(n.clone(), d.clone())),
+		(StepKind::BracketRemoved, _, _) => Some(Explanation::RemoveBracket(step.result()?.clone())),
+		(StepKind::OperationEvaluated, Some(operation), [left, right]) => {
+			// Within a bracket, × and ÷ still go ahead of its + and −.
+			let reason = match (operation, pending_additive(step)) {
+				(Operation::Mul | Operation::Div, Some(after)) => Reason::Precedence(after),
+				_ if step.path().directions().contains(&Direction::Inner) => Reason::InsideBracket,
+				_ => Reason::Plain,
+			};
+
+			Some(Explanation::Evaluate(operation.clone(), left.clone(), right.clone(), reason))
+		}
+		(StepKind::CommonDenominator(lcm), _, [_, left, _, right]) => {
+			Some(Explanation::CommonDenominator(left.clone(), right.clone(), NumberValue::from(lcm)))
+		}
+		(StepKind::NumeratorsCombined, Some(operation), _) => Some(Explanation::CombineNumerators(operation.clone())),
+		(StepKind::FractionsMultiplied, _, _) => Some(Explanation::MultiplyFractions),
+		(StepKind::ReciprocalTaken, _, [_, _, n, d]) => Some(Explanation::Reciprocal(n.clone(), d.clone())),
+		(StepKind::FractionReduced(divisor), _, [n, d]) => {
+			Some(Explanation::ReduceFraction(n.clone(), d.clone(), NumberValue::from(divisor)))
+		}
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::expr;
+	use crate::step::{Division, StepTrace, Steps};
+	use crate::step_strategy::LeftmostInnermost;
+
+	use super::*;
+
+	fn messages(catalog: &dyn MessageCatalog) -> Vec<String> {
+		let trace = StepTrace::trace(&expr!(1 + 2 * 3 - { 8 - 2 }), &LeftmostInnermost);
+		trace.iter().map(|s| catalog.message(&explain(s).unwrap())).collect()
+	}
+
+	#[test]
+	fn explain_test() {
+		let trace = StepTrace::trace(&expr!(1 + 2 * 3), &LeftmostInnermost);
+		let mut steps = trace.iter();
+
+		let act = explain(steps.next().unwrap()).unwrap();
+		assert_eq!(
+			act,
+			Explanation::Evaluate(Operation::Mul, NumberValue::from(2), NumberValue::from(3), Reason::Precedence(Operation::Add))
+		);
+
+		let act = explain(steps.next().unwrap()).unwrap();
+		assert_eq!(act, Explanation::Evaluate(Operation::Add, NumberValue::from(1), NumberValue::from(6), Reason::Plain));
+	}
+
+	#[test]
+	fn japanese() {
+		assert_eq!(
+			messages(Language::Ja.catalog()),
+			vec![
+				"2 × 3 を先に計算します（かけ算はたし算より先）",
+				"1 + 6 を計算します",
+				"8 − 2 を先に計算します（かっこの中が先）",
+				"(6) のかっこを外します",
+				"7 − 6 を計算します",
+			]
+		);
+	}
+
+	#[test]
+	fn english() {
+		assert_eq!(
+			messages(Language::En.catalog()),
+			vec![
+				"Multiply 2 × 3 first because multiplication precedes addition",
+				"Add 1 + 6",
+				"Subtract 8 − 2 first because it is inside brackets",
+				"Remove the brackets around 6",
+				"Subtract 7 − 6",
+			]
+		);
+	}
+
+	#[test]
+	fn fraction() {
+		let steps: Vec<Step> = Steps::new(&expr!(1 / 6 + 1 / 3), &LeftmostInnermost)
+			.division(Division::Fraction)
+			.map(|s| s.unwrap())
+			.collect();
+		let act: Vec<String> = steps.iter().map(|s| English.message(&explain(s).unwrap())).collect();
+
+		assert_eq!(
+			act,
+			vec![
+				"Rewrite the fractions over 6, the least common multiple of 6 and 3",
+				"Add the numerators",
+				"Add 1 + 2 first because it is inside brackets",
+				"Remove the brackets around 3",
+				"Reduce 3/6 by 3",
+			]
+		);
+		assert_eq!(Japanese.message(&explain(&steps[0]).unwrap()), "6 と 3 の最小公倍数 6 で通分します");
+
+		let steps: Vec<Step> = Steps::new(&expr!(1 + { 1 / 2 } / { 3 / 4 }), &LeftmostInnermost)
+			.division(Division::Fraction)
+			.map(|s| s.unwrap())
+			.collect();
+		let act: Vec<String> = steps.iter().take(2).map(|s| Japanese.message(&explain(s).unwrap())).collect();
+		assert_eq!(act, vec!["3/4 の逆数をかけます", "(1/2) のかっこを外します"]);
+	}
+
+	#[test]
+	fn custom_catalog() {
+		struct Terse;
+
+		impl MessageCatalog for Terse {
+			fn message(&self, explanation: &Explanation) -> String {
+				match explanation {
+					Explanation::Evaluate(op, l, r, _) => formula(op, l, r),
+					_ => String::from("-"),
+				}
+			}
+		}
+
+		assert_eq!(messages(&Terse)[0], "2 × 3");
+		assert!(Language::from_tag("fr").is_none());
+		assert_eq!(Language::from_tag("en"), Some(Language::En));
+	}
+}
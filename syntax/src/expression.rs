@@ -1,13 +1,18 @@
 use crate::arithmetic_expression::ArithmeticExpression;
-use crate::binary_operation::BinaryOperation;
+use crate::binary_operation::{BinaryOperation, Operation};
 use crate::bracket::Bracket;
+use crate::node_path::{Direction, NodePath};
 use crate::number::Number;
-use crate::number_value::NumberResult;
+use crate::number_value::{NumberResult, NumberValue};
+use crate::step::{BracketRemoval, Steps};
+use crate::step_strategy::{LeftmostInnermost, StepStrategy};
+use crate::variable::Variable;
 
 pub enum Expression {
 	Number(Number),
 	Bracket(Bracket),
 	BinaryOperation(BinaryOperation),
+	Variable(Variable),
 }
 
 impl From<Bracket> for Expression {
@@ -22,6 +27,12 @@ impl From<Number> for Expression {
 	}
 }
 
+impl From<Variable> for Expression {
+	fn from(value: Variable) -> Self {
+		Expression::Variable(value)
+	}
+}
+
 impl From<BinaryOperation> for Expression {
 	fn from(value: BinaryOperation) -> Self {
 		Expression::BinaryOperation(value)
@@ -30,41 +41,353 @@ impl From<BinaryOperation> for Expression {
 
 impl Clone for Expression {
 	fn clone(&self) -> Self {
+		enum Task<'a> {
+			Visit(&'a Expression),
+			Bracket,
+			BinaryOperation(&'a Operation),
+		}
+		
+		let mut tasks = vec![Task::Visit(self)];
+		let mut exprs = Vec::<Expression>::new();
+		
+		while let Some(task) = tasks.pop() {
+			match task {
+				Task::Visit(Expression::Number(num)) => exprs.push(Expression::Number(num.clone())),
+				Task::Visit(Expression::Variable(var)) => exprs.push(Expression::Variable(var.clone())),
+				Task::Visit(Expression::Bracket(bracket)) => {
+					tasks.push(Task::Bracket);
+					tasks.push(Task::Visit(bracket.expression()));
+				}
+				Task::Visit(Expression::BinaryOperation(bin)) => {
+					tasks.push(Task::BinaryOperation(bin.operation()));
+					tasks.push(Task::Visit(bin.right()));
+					tasks.push(Task::Visit(bin.left()));
+				}
+				Task::Bracket => {
+					let inner = exprs.pop().unwrap();
+					exprs.push(Bracket::from(inner).to_expression());
+				}
+				Task::BinaryOperation(op) => {
+					let right = exprs.pop().unwrap();
+					let left = exprs.pop().unwrap();
+					exprs.push(BinaryOperation::new(left, right, op.clone()).to_expression());
+				}
+			}
+		}
+		
+		exprs.pop().unwrap()
+	}
+}
+
+impl PartialEq for Expression {
+	fn eq(&self, other: &Self) -> bool {
+		let mut stack = vec![(self, other)];
+		
+		while let Some(pair) = stack.pop() {
+			match pair {
+				(Expression::Number(l), Expression::Number(r)) => {
+					if l.number() != r.number() {
+						return false;
+					}
+				}
+				(Expression::Bracket(l), Expression::Bracket(r)) => {
+					stack.push((l.expression(), r.expression()))
+				}
+				(Expression::Variable(l), Expression::Variable(r)) => {
+					if l.name() != r.name() {
+						return false;
+					}
+				}
+				(Expression::BinaryOperation(l), Expression::BinaryOperation(r)) => {
+					if l.operation() != r.operation() {
+						return false;
+					}
+					
+					stack.push((l.right(), r.right()));
+					stack.push((l.left(), r.left()));
+				}
+				_ => return false,
+			}
+		}
+		
+		true
+	}
+}
+
+// Nested children are moved onto a heap allocated stack before they are released,
+// so dropping a very deep tree does not recurse.
+impl Drop for Expression {
+	fn drop(&mut self) {
+		let mut stack = Vec::<Expression>::new();
+		self.detach_children(&mut stack);
+		
+		while let Some(mut expr) = stack.pop() {
+			expr.detach_children(&mut stack);
+		}
+	}
+}
+
+impl Expression {
+	fn detach_children(&mut self, stack: &mut Vec<Expression>) {
 		match self {
-			Expression::Number(x) => Expression::Number(x.clone()),
-			Expression::Bracket(x) => Expression::Bracket(x.clone()),
-			Expression::BinaryOperation(x) => Expression::BinaryOperation(x.clone()),
+			Expression::Number(_) | Expression::Variable(_) => {}
+			Expression::Bracket(bracket) => bracket.detach(stack),
+			Expression::BinaryOperation(bin) => bin.detach(stack),
+		}
+	}
+	
+	pub(crate) fn placeholder() -> Expression {
+		Expression::Number(Number::from(NumberValue::from(0)))
+	}
+	
+	pub fn is_number(&self) -> bool {
+		matches!(self, Expression::Number(_))
+	}
+	
+	pub(crate) fn leftmost_innermost_redex(&self) -> Option<NodePath> {
+		let mut path = NodePath::root();
+		let mut current = self;
+		
+		loop {
+			match current {
+				Expression::Number(_) | Expression::Variable(_) => return None,
+				Expression::Bracket(bracket) => {
+					if bracket.expression().is_number() {
+						return Some(path);
+					}
+					
+					path.push(Direction::Inner);
+					current = bracket.expression();
+				}
+				Expression::BinaryOperation(bin) => {
+					if !bin.left().is_number() {
+						path.push(Direction::Left);
+						current = bin.left();
+					} else if !bin.right().is_number() {
+						path.push(Direction::Right);
+						current = bin.right();
+					} else {
+						return Some(path);
+					}
+				}
+			}
+		}
+	}
+	
+	// Reduces the redex at the path, a bracket around a number or an operation over two numbers.
+	pub fn reduce_at(&self, path: &NodePath) -> Option<Expression> {
+		let redex = match path.resolve(self)? {
+			Expression::Bracket(bracket) if bracket.expression().is_number() => bracket.expression().clone(),
+			Expression::BinaryOperation(bin) if bin.left().is_number() && bin.right().is_number() => {
+				match bin.step_calc() {
+					(reduced, true) => reduced,
+					_ => return None,
+				}
+			}
+			_ => return None,
+		};
+		
+		path.replace(self, redex)
+	}
+	
+	pub fn step_calc_with(&self, strategy: &dyn StepStrategy) -> (Expression, bool) {
+		match strategy.select(self).and_then(|path| self.reduce_at(&path)) {
+			Some(reduced) => (reduced, true),
+			None => (self.clone(), false),
 		}
 	}
+	
+	pub fn steps(&self) -> Steps<'static> {
+		Steps::new(self, &LeftmostInnermost)
+	}
+	
+	pub fn steps_with<'a>(&self, strategy: &'a dyn StepStrategy) -> Steps<'a> {
+		Steps::new(self, strategy)
+	}
+	
+	pub fn parallel_step(&self) -> (Expression, Vec<NodePath>) {
+		self.parallel_step_with(BracketRemoval::default())
+	}
+	
+	// Reduces every operation over two numbers and drops every bracket around a number at once.
+	// Returns the reduced nodes, inner ones first; empty when done.
+	pub fn parallel_step_with(&self, removal: BracketRemoval) -> (Expression, Vec<NodePath>) {
+		enum Task<'a> {
+			Visit(&'a Expression, NodePath),
+			Bracket(NodePath),
+			BinaryOperation(&'a Operation),
+		}
+		
+		let mut tasks = vec![Task::Visit(self, NodePath::root())];
+		let mut exprs = Vec::<Expression>::new();
+		let mut reduced = Vec::<NodePath>::new();
+		
+		while let Some(task) = tasks.pop() {
+			match task {
+				Task::Visit(Expression::Number(num), _) => exprs.push(num.clone().to_expression()),
+				Task::Visit(Expression::Variable(var), _) => exprs.push(var.clone().to_expression()),
+				Task::Visit(Expression::Bracket(bracket), path) => match bracket.expression() {
+					Expression::Number(num) => {
+						exprs.push(num.clone().to_expression());
+						reduced.push(path);
+					}
+					inner => {
+						let inner_path = path.child(Direction::Inner);
+						tasks.push(Task::Bracket(path));
+						tasks.push(Task::Visit(inner, inner_path));
+					}
+				},
+				Task::Visit(Expression::BinaryOperation(bin), path) => {
+					if bin.left().is_number() && bin.right().is_number() {
+						let (expr, proceed) = bin.step_calc();
+						exprs.push(expr);
+						
+						if proceed {
+							reduced.push(path);
+						}
+					} else {
+						tasks.push(Task::BinaryOperation(bin.operation()));
+						tasks.push(Task::Visit(bin.right(), path.child(Direction::Right)));
+						tasks.push(Task::Visit(bin.left(), path.child(Direction::Left)));
+					}
+				}
+				Task::Bracket(path) => {
+					let inner = exprs.pop().unwrap();
+					
+					// Content reduced by this very step.
+					if inner.is_number() && removal == BracketRemoval::WithReduction {
+						exprs.push(inner);
+						reduced.push(path);
+					} else {
+						exprs.push(Bracket::from(inner).to_expression());
+					}
+				}
+				Task::BinaryOperation(op) => {
+					let right = exprs.pop().unwrap();
+					let left = exprs.pop().unwrap();
+					exprs.push(BinaryOperation::new(left, right, op.clone()).to_expression());
+				}
+			}
+		}
+		
+		(exprs.pop().unwrap(), reduced)
+	}
+	
+	pub fn depth(&self) -> usize {
+		let mut stack = vec![(self, 1usize)];
+		let mut max = 0;
+		
+		while let Some((expr, depth)) = stack.pop() {
+			max = max.max(depth);
+			
+			match expr {
+				Expression::Number(_) | Expression::Variable(_) => {}
+				Expression::Bracket(bracket) => stack.push((bracket.expression(), depth + 1)),
+				Expression::BinaryOperation(bin) => {
+					stack.push((bin.left(), depth + 1));
+					stack.push((bin.right(), depth + 1));
+				}
+			}
+		}
+		
+		max
+	}
+	
+	pub fn bracket_count(&self) -> usize {
+		let mut stack = vec![self];
+		let mut count = 0;
+		
+		while let Some(expr) = stack.pop() {
+			match expr {
+				Expression::Number(_) | Expression::Variable(_) => {}
+				Expression::Bracket(bracket) => {
+					count += 1;
+					stack.push(bracket.expression());
+				}
+				Expression::BinaryOperation(bin) => {
+					stack.push(bin.left());
+					stack.push(bin.right());
+				}
+			}
+		}
+		
+		count
+	}
 }
 
 impl ArithmeticExpression for Expression {
 	fn calc(&self) -> NumberResult {
-		match self {
-			Expression::Number(x) => x.calc(),
-			Expression::Bracket(x) => x.calc(),
-			Expression::BinaryOperation(x) => x.calc(),
+		enum Task<'a> {
+			Visit(&'a Expression),
+			Apply(&'a Operation),
+		}
+		
+		let mut tasks = vec![Task::Visit(self)];
+		let mut values = Vec::<NumberValue>::new();
+		
+		while let Some(task) = tasks.pop() {
+			match task {
+				Task::Visit(Expression::Number(num)) => values.push(num.number().clone()),
+				Task::Visit(Expression::Variable(var)) => return var.calc(),
+				Task::Visit(Expression::Bracket(bracket)) => tasks.push(Task::Visit(bracket.expression())),
+				Task::Visit(Expression::BinaryOperation(bin)) => {
+					tasks.push(Task::Apply(bin.operation()));
+					tasks.push(Task::Visit(bin.right()));
+					tasks.push(Task::Visit(bin.left()));
+				}
+				Task::Apply(op) => {
+					let right = values.pop().unwrap();
+					let left = values.pop().unwrap();
+					
+					values.push(match op {
+						Operation::Add => left + right,
+						Operation::Sub => left - right,
+						Operation::Mul => left * right,
+						Operation::Div => left / right,
+					}?);
+				}
+			}
 		}
+		
+		Ok(values.pop().unwrap())
 	}
-
+	
 	fn to_expression(self) -> Expression {
 		self
 	}
-
+	
 	fn simplify(&self) -> Expression {
-		match self {
-			Expression::Number(num) => num.simplify(),
-			Expression::Bracket(bra) => bra.simplify(),
-			Expression::BinaryOperation(bin) => bin.simplify(),
+		enum Task<'a> {
+			Visit(&'a Expression),
+			BinaryOperation(&'a Operation),
+		}
+		
+		let mut tasks = vec![Task::Visit(self)];
+		let mut exprs = Vec::<Expression>::new();
+		
+		while let Some(task) = tasks.pop() {
+			match task {
+				Task::Visit(Expression::Number(num)) => exprs.push(num.simplify()),
+				Task::Visit(Expression::Variable(var)) => exprs.push(var.simplify()),
+				Task::Visit(Expression::Bracket(bracket)) => tasks.push(Task::Visit(bracket.expression())),
+				Task::Visit(Expression::BinaryOperation(bin)) => {
+					tasks.push(Task::BinaryOperation(bin.operation()));
+					tasks.push(Task::Visit(bin.right()));
+					tasks.push(Task::Visit(bin.left()));
+				}
+				Task::BinaryOperation(op) => {
+					let right = exprs.pop().unwrap();
+					let left = exprs.pop().unwrap();
+					exprs.push(BinaryOperation::new(left, right, op.clone()).to_expression());
+				}
+			}
 		}
+		
+		exprs.pop().unwrap()
 	}
-
+	
 	fn step_calc(&self) -> (Expression, bool) {
-		match self {
-			Expression::Number(num) => num.step_calc(),
-			Expression::Bracket(bracket) => bracket.step_calc(),
-			Expression::BinaryOperation(bin) => bin.step_calc(),
-		}
+		self.step_calc_with(&LeftmostInnermost)
 	}
 }
 
@@ -74,6 +397,7 @@ pub mod helper {
 	use crate::bracket::Bracket;
 	use crate::expression::Expression;
 	use crate::number::Number as NumberExpr;
+	#[cfg(test)]
 	use crate::number_value::NumberValue;
 
 	impl Expression {
@@ -99,10 +423,12 @@ pub mod helper {
 		}
 	}
 
+	#[cfg(test)]
 	fn create_number() -> NumberExpr {
 		NumberExpr::from(NumberValue::from(100))
 	}
 
+	#[cfg(test)]
 	fn create_binary_operation_fixture() -> BinaryOperation {
 		BinaryOperation::new(
 			NumberExpr::from(NumberValue::from(100)),
@@ -111,6 +437,7 @@ pub mod helper {
 		)
 	}
 
+	#[cfg(test)]
 	fn create_bracket_fixture() -> Bracket {
 		Bracket::from(Expression::Number(NumberExpr::from(NumberValue::from(100))))
 	}
@@ -334,4 +661,159 @@ mod tests {
 		fixture.right().extract_as_number().number().eq_i32(&300);
 		matches!(fixture.operation(), &Operation::Mul);
 	}
+
+	fn deep_fixture(depth: usize) -> Expression {
+		let mut expr = Number::from(NumberValue::from(0)).to_expression();
+
+		for _ in 0..depth {
+			let bin = BinaryOperation::new(expr, Number::from(NumberValue::from(1)), Operation::Add);
+			expr = Bracket::from(bin.to_expression()).to_expression();
+		}
+
+		expr
+	}
+
+	#[test]
+	fn deep_tree() {
+		const DEPTH: usize = 200_000;
+		let fixture = deep_fixture(DEPTH);
+
+		assert_eq!(fixture.depth(), DEPTH * 2 + 1);
+		assert_eq!(fixture.bracket_count(), DEPTH);
+		fixture.calc().unwrap().eq_i32(&(DEPTH as i32));
+
+		let cloned = fixture.clone();
+		cloned.calc().unwrap().eq_i32(&(DEPTH as i32));
+
+		let simplified = fixture.simplify();
+		assert_eq!(simplified.depth(), DEPTH + 1);
+		simplified.calc().unwrap().eq_i32(&(DEPTH as i32));
+
+		let (stepped, proceed) = fixture.step_calc();
+		assert!(proceed);
+		assert_eq!(stepped.depth(), DEPTH * 2);
+		stepped.calc().unwrap().eq_i32(&(DEPTH as i32));
+
+		let (stepped, proceed) = simplified.step_calc();
+		assert!(proceed);
+		assert_eq!(stepped.depth(), DEPTH);
+	}
+
+	#[test]
+	fn reduce_at() {
+		use crate::builder::{bracket, num};
+		
+		let fixture = bracket(num(1) + num(2)) * (num(3) + num(4));
+		
+		let act = fixture.reduce_at(&NodePath::from(vec![Direction::Right])).unwrap();
+		assert!(act == bracket(num(1) + num(2)) * num(7));
+		
+		let act = fixture.reduce_at(&NodePath::from(vec![Direction::Left, Direction::Inner])).unwrap();
+		assert!(act == bracket(num(3)) * (num(3) + num(4)));
+		
+		let act = act.reduce_at(&NodePath::from(vec![Direction::Left])).unwrap();
+		assert!(act == num(3) * (num(3) + num(4)));
+		
+		assert!(fixture.reduce_at(&NodePath::from(vec![Direction::Left])).is_none());
+		
+		assert!(fixture.reduce_at(&NodePath::root()).is_none());
+		assert!(fixture.reduce_at(&NodePath::from(vec![Direction::Right, Direction::Left])).is_none());
+		assert!(fixture.reduce_at(&NodePath::from(vec![Direction::Inner])).is_none());
+	}
+	
+	#[test]
+	fn steps() {
+		use crate::builder::{bracket, num};
+		
+		let fixture = bracket(num(1) + num(2)) * num(3);
+		let act: Vec<String> = fixture.steps().map(|s| s.unwrap().path().to_string()).collect();
+		assert_eq!(act, vec!["/L/I", "/L", "/"]);
+		
+		let act = fixture.steps_with(&crate::step_strategy::PrecedenceSweep).count();
+		assert_eq!(act, 3);
+	}
+	
+	#[test]
+	fn division_by_zero() {
+		use crate::builder::{bracket, num};
+		
+		let fixture = num(10) / bracket(num(5) - num(5));
+		let mut current = fixture.clone();
+		
+		loop {
+			let (next, proceed) = current.step_calc();
+			
+			if !proceed {
+				break;
+			}
+			
+			current = next;
+		}
+		
+		assert!(current == num(10) / num(0));
+		assert!(current.reduce_at(&NodePath::root()).is_none());
+		
+		let (act, reduced) = (num(1) / num(0) + num(2) * num(3)).parallel_step();
+		assert!(act == num(1) / num(0) + num(6));
+		assert_eq!(reduced.len(), 1);
+	}
+	
+	#[test]
+	fn parallel_step() {
+		use crate::builder::{bracket, num};
+		
+		let fixture = bracket(num(1) + num(2)) * bracket(num(3) + num(4));
+		let (act, reduced) = fixture.parallel_step();
+		assert!(act == bracket(num(3)) * bracket(num(7)));
+		
+		let paths: Vec<String> = reduced.iter().map(|p| p.to_string()).collect();
+		assert_eq!(paths, vec!["/L/I", "/R/I"]);
+		
+		let (act, reduced) = act.parallel_step();
+		assert!(act == num(3) * num(7));
+		assert_eq!(reduced.len(), 2);
+		
+		let (act, reduced) = fixture.parallel_step_with(BracketRemoval::WithReduction);
+		assert!(act == num(3) * num(7));
+		
+		let paths: Vec<String> = reduced.iter().map(|p| p.to_string()).collect();
+		assert_eq!(paths, vec!["/L/I", "/L", "/R/I", "/R"]);
+		
+		let fixture = bracket(bracket(num(1) + num(2)) * num(3) - num(4) / num(2)) + num(5);
+		let (act, reduced) = fixture.parallel_step_with(BracketRemoval::WithReduction);
+		assert!(act == bracket(num(3) * num(3) - num(2)) + num(5));
+		assert_eq!(reduced.len(), 3);
+		
+		let (act, reduced) = num(42).parallel_step();
+		assert!(act == num(42));
+		assert!(reduced.is_empty());
+	}
+	
+	#[test]
+	fn depth() {
+		assert_eq!(Number::from(NumberValue::from(1)).to_expression().depth(), 1);
+		assert_eq!(deep_fixture(3).depth(), 7);
+	}
+
+	#[test]
+	fn is_number() {
+		assert!(Number::from(NumberValue::from(1)).to_expression().is_number());
+		assert!(!deep_fixture(1).is_number());
+	}
+
+	#[test]
+	fn eq() {
+		let fixture = deep_fixture(3);
+		assert!(fixture == fixture.clone());
+		assert!(fixture != deep_fixture(2));
+		assert!(fixture != fixture.simplify());
+
+		let add = BinaryOperation::new(Number::from(NumberValue::from(1)), Number::from(NumberValue::from(2)), Operation::Add);
+		let sub = BinaryOperation::new(Number::from(NumberValue::from(1)), Number::from(NumberValue::from(2)), Operation::Sub);
+		assert!(add.clone().to_expression() != sub.to_expression());
+		assert!(add.clone().to_expression() == add.to_expression());
+
+		let deep = deep_fixture(200_000);
+		assert!(deep == deep.clone());
+	}
 }
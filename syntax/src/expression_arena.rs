@@ -0,0 +1,478 @@
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::{BinaryOperation, Operation};
+use crate::bracket::Bracket;
+use crate::expression::Expression;
+use crate::number::Number;
+use crate::number_value::{ArithmeticError, NumberResult, NumberValue};
+use crate::variable::Variable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+	pub fn index(&self) -> usize {
+		self.0
+	}
+}
+
+pub enum ArenaNode {
+	Number(NumberValue),
+	Bracket(NodeId),
+	BinaryOperation(NodeId, NodeId, Operation),
+	Variable(char),
+}
+
+impl Clone for ArenaNode {
+	fn clone(&self) -> Self {
+		match self {
+			ArenaNode::Number(num) => ArenaNode::Number(num.clone()),
+			ArenaNode::Variable(name) => ArenaNode::Variable(*name),
+			ArenaNode::Bracket(inner) => ArenaNode::Bracket(*inner),
+			ArenaNode::BinaryOperation(left, right, op) => {
+				ArenaNode::BinaryOperation(*left, *right, op.clone())
+			}
+		}
+	}
+}
+
+// Children are always allocated before their parents, so every id stored in a node
+// is smaller than the id of the node itself. Unchanged subtrees are shared between
+// the expressions produced by step_calc instead of being copied.
+#[derive(Default)]
+pub struct ExpressionArena {
+	nodes: Vec<ArenaNode>,
+}
+
+impl ExpressionArena {
+	pub fn new() -> Self {
+		ExpressionArena { nodes: Vec::new() }
+	}
+
+	pub fn with_capacity(capacity: usize) -> Self {
+		ExpressionArena {
+			nodes: Vec::with_capacity(capacity),
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+
+	pub fn clear(&mut self) {
+		self.nodes.clear()
+	}
+
+	pub fn node(&self, id: NodeId) -> &ArenaNode {
+		&self.nodes[id.0]
+	}
+
+	fn push(&mut self, node: ArenaNode) -> NodeId {
+		self.nodes.push(node);
+		NodeId(self.nodes.len() - 1)
+	}
+
+	pub fn number(&mut self, value: NumberValue) -> NodeId {
+		self.push(ArenaNode::Number(value))
+	}
+
+	pub fn variable(&mut self, name: char) -> NodeId {
+		self.push(ArenaNode::Variable(name))
+	}
+
+	pub fn bracket(&mut self, inner: NodeId) -> NodeId {
+		self.push(ArenaNode::Bracket(inner))
+	}
+
+	pub fn binary_operation(&mut self, left: NodeId, right: NodeId, operation: Operation) -> NodeId {
+		self.push(ArenaNode::BinaryOperation(left, right, operation))
+	}
+
+	pub fn insert(&mut self, expression: &Expression) -> NodeId {
+		enum Task<'a> {
+			Visit(&'a Expression),
+			Bracket,
+			BinaryOperation(&'a Operation),
+		}
+
+		let mut tasks = vec![Task::Visit(expression)];
+		let mut ids = Vec::<NodeId>::new();
+
+		while let Some(task) = tasks.pop() {
+			match task {
+				Task::Visit(Expression::Number(num)) => ids.push(self.number(num.number().clone())),
+				Task::Visit(Expression::Variable(var)) => ids.push(self.variable(var.name())),
+				Task::Visit(Expression::Bracket(bracket)) => {
+					tasks.push(Task::Bracket);
+					tasks.push(Task::Visit(bracket.expression()));
+				}
+				Task::Visit(Expression::BinaryOperation(bin)) => {
+					tasks.push(Task::BinaryOperation(bin.operation()));
+					tasks.push(Task::Visit(bin.right()));
+					tasks.push(Task::Visit(bin.left()));
+				}
+				Task::Bracket => {
+					let inner = ids.pop().unwrap();
+					ids.push(self.bracket(inner));
+				}
+				Task::BinaryOperation(op) => {
+					let right = ids.pop().unwrap();
+					let left = ids.pop().unwrap();
+					ids.push(self.binary_operation(left, right, op.clone()));
+				}
+			}
+		}
+
+		ids.pop().unwrap()
+	}
+
+	pub fn to_expression(&self, id: NodeId) -> Expression {
+		enum Task {
+			Visit(NodeId),
+			Bracket,
+			BinaryOperation(NodeId),
+		}
+
+		let mut tasks = vec![Task::Visit(id)];
+		let mut exprs = Vec::<Expression>::new();
+
+		while let Some(task) = tasks.pop() {
+			match task {
+				Task::Visit(id) => match self.node(id) {
+					ArenaNode::Number(num) => exprs.push(Number::from(num.clone()).to_expression()),
+					ArenaNode::Variable(name) => exprs.push(Variable::from(*name).to_expression()),
+					ArenaNode::Bracket(inner) => {
+						tasks.push(Task::Bracket);
+						tasks.push(Task::Visit(*inner));
+					}
+					ArenaNode::BinaryOperation(left, right, _) => {
+						tasks.push(Task::BinaryOperation(id));
+						tasks.push(Task::Visit(*right));
+						tasks.push(Task::Visit(*left));
+					}
+				},
+				Task::Bracket => {
+					let inner = exprs.pop().unwrap();
+					exprs.push(Bracket::from(inner).to_expression());
+				}
+				Task::BinaryOperation(id) => {
+					let ArenaNode::BinaryOperation(_, _, op) = self.node(id) else {
+						unreachable!()
+					};
+					let right = exprs.pop().unwrap();
+					let left = exprs.pop().unwrap();
+					exprs.push(BinaryOperation::new(left, right, op.clone()).to_expression());
+				}
+			}
+		}
+
+		exprs.pop().unwrap()
+	}
+
+	pub fn calc(&self, id: NodeId) -> NumberResult {
+		enum Task {
+			Visit(NodeId),
+			Apply(NodeId),
+		}
+
+		let mut tasks = vec![Task::Visit(id)];
+		let mut values = Vec::<NumberValue>::new();
+
+		while let Some(task) = tasks.pop() {
+			match task {
+				Task::Visit(id) => match self.node(id) {
+					ArenaNode::Number(num) => values.push(num.clone()),
+					ArenaNode::Variable(name) => return Variable::from(*name).calc(),
+					ArenaNode::Bracket(inner) => tasks.push(Task::Visit(*inner)),
+					ArenaNode::BinaryOperation(left, right, _) => {
+						tasks.push(Task::Apply(id));
+						tasks.push(Task::Visit(*right));
+						tasks.push(Task::Visit(*left));
+					}
+				},
+				Task::Apply(id) => {
+					let ArenaNode::BinaryOperation(_, _, op) = self.node(id) else {
+						unreachable!()
+					};
+					let right = values.pop().unwrap();
+					let left = values.pop().unwrap();
+
+					values.push(match op {
+						Operation::Add => left + right,
+						Operation::Sub => left - right,
+						Operation::Mul => left * right,
+						Operation::Div => left / right,
+					}?);
+				}
+			}
+		}
+
+		Ok(values.pop().unwrap())
+	}
+
+	// Same leftmost-innermost order as ArithmeticExpression::step_calc. An operation that
+	// cannot be calculated is an error rather than the end of the reduction.
+	pub fn step_calc(&mut self, id: NodeId) -> Result<(NodeId, bool), ArithmeticError> {
+		let mut path = Vec::<NodeId>::new();
+		let mut current = id;
+
+		let replacement = loop {
+			match self.node(current) {
+				ArenaNode::Number(_) => {
+					if path.is_empty() {
+						return Ok((id, false));
+					}
+					unreachable!()
+				}
+				ArenaNode::Variable(_) => return Ok((id, false)),
+				ArenaNode::Bracket(inner) => {
+					let inner = *inner;
+
+					if matches!(self.node(inner), ArenaNode::Number(_)) {
+						break inner;
+					}
+
+					path.push(current);
+					current = inner;
+				}
+				ArenaNode::BinaryOperation(left, right, _) => {
+					let (left, right) = (*left, *right);
+
+					if !matches!(self.node(left), ArenaNode::Number(_)) {
+						path.push(current);
+						current = left;
+					} else if !matches!(self.node(right), ArenaNode::Number(_)) {
+						path.push(current);
+						current = right;
+					} else {
+						let value = self.calc(current)?;
+						break self.number(value);
+					}
+				}
+			}
+		};
+
+		let mut child = current;
+		let mut rebuilt = replacement;
+
+		while let Some(parent) = path.pop() {
+			rebuilt = match self.node(parent).clone() {
+				ArenaNode::Bracket(_) => self.bracket(rebuilt),
+				ArenaNode::BinaryOperation(left, right, op) => {
+					if left == child {
+						self.binary_operation(rebuilt, right, op)
+					} else {
+						self.binary_operation(left, rebuilt, op)
+					}
+				}
+				_ => unreachable!(),
+			};
+			child = parent;
+		}
+
+		Ok((rebuilt, true))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::arithmetic_expression::ArithmeticExpression;
+	use crate::binary_operation::{BinaryOperation, Operation};
+	use crate::bracket::Bracket;
+	use crate::number::Number;
+	use crate::number_value::NumberValue;
+
+	use super::*;
+
+	fn num(value: i32) -> Number {
+		Number::from(NumberValue::from(value))
+	}
+
+	// {1 + 2 * 3} / {{4 - 5} * {{6 + 7} / 2}}
+	fn create_fixture() -> Expression {
+		let left = Bracket::from(
+			BinaryOperation::new(
+				num(1),
+				BinaryOperation::new(num(2), num(3), Operation::Mul),
+				Operation::Add,
+			)
+			.to_expression(),
+		);
+
+		let sub = Bracket::from(BinaryOperation::new(num(4), num(5), Operation::Sub).to_expression());
+		let add = Bracket::from(BinaryOperation::new(num(6), num(7), Operation::Add).to_expression());
+		let div = Bracket::from(BinaryOperation::new(add, num(2), Operation::Div).to_expression());
+		let right = Bracket::from(BinaryOperation::new(sub, div, Operation::Mul).to_expression());
+
+		BinaryOperation::new(left, right, Operation::Div).to_expression()
+	}
+
+	#[test]
+	fn insert() {
+		let mut fixture = ExpressionArena::new();
+		assert!(fixture.is_empty());
+
+		let id = fixture.insert(&num(42).to_expression());
+		assert_eq!(fixture.len(), 1);
+		assert!(matches!(fixture.node(id), ArenaNode::Number(NumberValue::Integer(42))));
+
+		let id = fixture.insert(&create_fixture());
+		assert_eq!(fixture.len(), 21);
+		assert_eq!(id.index(), 20);
+
+		let ArenaNode::BinaryOperation(left, right, Operation::Div) = fixture.node(id) else {
+			unreachable!()
+		};
+
+		assert!(left < &id);
+		assert!(right < &id);
+		assert!(matches!(fixture.node(*left), ArenaNode::Bracket(_)));
+		assert!(matches!(fixture.node(*right), ArenaNode::Bracket(_)));
+	}
+
+	#[test]
+	fn builder() {
+		let mut fixture = ExpressionArena::with_capacity(4);
+
+		let left = fixture.number(NumberValue::from(10));
+		let right = fixture.number(NumberValue::from(32));
+		let add = fixture.binary_operation(left, right, Operation::Add);
+		let bracket = fixture.bracket(add);
+
+		fixture.calc(bracket).unwrap().eq_i32(&42);
+
+		let expr = fixture.to_expression(bracket);
+		let expr = expr.extract_as_bracket().expression().extract_as_binary_operation();
+		expr.left().extract_as_number().number().eq_i32(&10);
+		expr.right().extract_as_number().number().eq_i32(&32);
+		assert!(matches!(expr.operation(), Operation::Add));
+
+		fixture.clear();
+		assert!(fixture.is_empty());
+	}
+
+	#[test]
+	fn to_expression() {
+		let mut fixture = ExpressionArena::new();
+		let id = fixture.insert(&create_fixture());
+
+		let act = fixture.to_expression(id);
+		let act = act.extract_as_binary_operation();
+		assert!(matches!(act.operation(), Operation::Div));
+
+		let left = act.left().extract_as_bracket().expression().extract_as_binary_operation();
+		left.left().extract_as_number().number().eq_i32(&1);
+
+		let mul = left.right().extract_as_binary_operation();
+		mul.left().extract_as_number().number().eq_i32(&2);
+		mul.right().extract_as_number().number().eq_i32(&3);
+		assert!(matches!(mul.operation(), Operation::Mul));
+	}
+
+	#[test]
+	fn calc() {
+		let mut fixture = ExpressionArena::new();
+		let id = fixture.insert(&create_fixture());
+
+		fixture.calc(id).unwrap().eq_number(&create_fixture().calc().unwrap());
+
+		let zero = BinaryOperation::new(
+			num(1),
+			Bracket::from(BinaryOperation::new(num(2), num(2), Operation::Sub).to_expression()),
+			Operation::Div,
+		);
+		let id = fixture.insert(&zero.to_expression());
+
+		let err = fixture.calc(id).err().unwrap();
+		err.lhs().eq_i32(&1);
+		err.rhs().eq_i32(&0);
+
+		let (id, proceed) = fixture.step_calc(id).unwrap();
+		assert!(proceed);
+		let (id, proceed) = fixture.step_calc(id).unwrap();
+		assert!(proceed);
+
+		// 1 / 0 cannot go on, which is not the same as being finished.
+		let err = fixture.step_calc(id).err().unwrap();
+		assert!(err.is_division_by_zero());
+		err.lhs().eq_i32(&1);
+
+		let id = fixture.number(NumberValue::from(7));
+		assert_eq!(fixture.step_calc(id).unwrap(), (id, false));
+	}
+
+	#[test]
+	fn step_calc() {
+		let mut fixture = ExpressionArena::new();
+		let mut id = fixture.insert(&create_fixture());
+		let mut expected = create_fixture();
+
+		loop {
+			let (next, proceed) = fixture.step_calc(id).unwrap();
+			let (expected_next, expected_proceed) = expected.step_calc();
+
+			assert_eq!(proceed, expected_proceed);
+
+			if !proceed {
+				assert_eq!(next, id);
+				break;
+			}
+
+			fixture.calc(next).unwrap().eq_number(&expected_next.calc().unwrap());
+			id = next;
+			expected = expected_next;
+		}
+
+		fixture.calc(id).unwrap().eq_i32(&-1);
+		assert!(matches!(fixture.node(id), ArenaNode::Number(_)));
+	}
+
+	#[test]
+	fn step_calc_shares_untouched_subtree() {
+		let mut fixture = ExpressionArena::new();
+		let left = fixture.insert(&BinaryOperation::new(num(1), num(2), Operation::Add).to_expression());
+		let right = fixture.insert(&BinaryOperation::new(num(3), num(4), Operation::Mul).to_expression());
+		let root = fixture.binary_operation(left, right, Operation::Sub);
+
+		let (next, proceed) = fixture.step_calc(root).unwrap();
+		assert!(proceed);
+
+		let ArenaNode::BinaryOperation(new_left, new_right, _) = fixture.node(next) else {
+			unreachable!()
+		};
+
+		assert_eq!(*new_right, right);
+		assert!(matches!(fixture.node(*new_left), ArenaNode::Number(NumberValue::Integer(3))));
+	}
+
+	#[test]
+	fn deep_tree() {
+		const DEPTH: usize = 200_000;
+
+		let mut fixture = ExpressionArena::with_capacity(DEPTH * 3 + 1);
+		let mut id = fixture.number(NumberValue::from(0));
+
+		for _ in 0..DEPTH {
+			let one = fixture.number(NumberValue::from(1));
+			let add = fixture.binary_operation(id, one, Operation::Add);
+			id = fixture.bracket(add);
+		}
+
+		fixture.calc(id).unwrap().eq_i32(&(DEPTH as i32));
+
+		let (stepped, proceed) = fixture.step_calc(id).unwrap();
+		assert!(proceed);
+		fixture.calc(stepped).unwrap().eq_i32(&(DEPTH as i32));
+
+		let expr = fixture.to_expression(id);
+		expr.calc().unwrap().eq_i32(&(DEPTH as i32));
+
+		let mut other = ExpressionArena::new();
+		let copied = other.insert(&expr);
+		assert_eq!(other.len(), DEPTH * 3 + 1);
+		other.calc(copied).unwrap().eq_i32(&(DEPTH as i32));
+	}
+}
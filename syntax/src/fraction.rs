@@ -0,0 +1,340 @@
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::{BinaryOperation, Operation};
+use crate::bracket::Bracket;
+use crate::builder::num;
+use crate::expression::Expression;
+use crate::node_path::NodePath;
+use crate::number_value::{ArithmeticError, NumberValue};
+use crate::rational::{gcd, Rational};
+use crate::step::{Step, StepFailure, StepKind};
+
+// What a reduction does to the node at the path: its kind, the operation, the operands
+// and the number it comes to, and the node that replaces it.
+type Reduction = (StepKind, Option<Operation>, Vec<NumberValue>, Option<NumberValue>, Expression);
+
+fn integer(expression: &Expression) -> Option<i32> {
+	match expression {
+		Expression::Number(number) => {
+			let NumberValue::Integer(i) = number.number();
+			Some(*i)
+		}
+		_ => None,
+	}
+}
+
+// An integer n is n / 1. The fraction is taken as written, not reduced, and seen through
+// the brackets a quotient keeps around it.
+fn value(expression: &Expression) -> Option<(i32, i32)> {
+	match expression {
+		Expression::Number(_) => integer(expression).map(|n| (n, 1)),
+		Expression::Bracket(bracket) => value(bracket.expression()),
+		Expression::BinaryOperation(bin) if bin.operation() == &Operation::Div => {
+			Some((integer(bin.left())?, integer(bin.right())?))
+		}
+		_ => None,
+	}
+}
+
+fn fraction(numerator: i32, denominator: i32) -> Expression {
+	BinaryOperation::new(num(numerator), num(denominator), Operation::Div).to_expression()
+}
+
+// A fraction that is not an integer, as opposed to a number.
+fn is_fraction(expression: &Expression) -> bool {
+	!expression.is_number() && value(expression).is_some()
+}
+
+// An addition or subtraction that is about to put its numerators together, whose
+// operands must not be reduced back first.
+fn combines(bin: &BinaryOperation) -> bool {
+	match (bin.operation(), value(bin.left()), value(bin.right())) {
+		(Operation::Add | Operation::Sub, Some((_, l)), Some((_, r))) => l == r,
+		_ => false,
+	}
+}
+
+// A fraction of two integers that is used as it is, being in lowest terms or about to
+// have its numerator combined.
+fn is_ready(bin: &BinaryOperation, in_combination: bool) -> bool {
+	match (bin.operation(), integer(bin.left()), integer(bin.right())) {
+		(Operation::Div, Some(n), Some(d)) => d != 0 && (in_combination || (d > 1 && gcd(n as i64, d as i64) == Some(1))),
+		_ => false,
+	}
+}
+
+fn view(expression: &Expression, in_combination: bool, needed: bool) -> Expression {
+	match expression {
+		Expression::Number(_) | Expression::Variable(_) => expression.clone(),
+		Expression::Bracket(bracket) => {
+			let inner = view(bracket.expression(), false, false);
+
+			if needed && inner.is_number() && is_fraction(bracket.expression()) {
+				num(0)
+			} else {
+				Bracket::from(inner).to_expression()
+			}
+		}
+		Expression::BinaryOperation(bin) if is_ready(bin, in_combination) => num(0),
+		Expression::BinaryOperation(bin) => {
+			let combination = combines(bin);
+			let operation = bin.operation();
+			let left = view(bin.left(), combination, operation == &Operation::Div);
+			let right = view(bin.right(), combination, matches!(operation, Operation::Mul | Operation::Div));
+
+			BinaryOperation::new(left, right, operation.clone()).to_expression()
+		}
+	}
+}
+
+// The expression as the strategies see it with the quotients kept as fractions. A fraction
+// ready to be used, and the bracket a quotient needs around one, stand for a number, so
+// that the strategies pick them only as operands.
+pub fn fraction_view(expression: &Expression) -> Expression {
+	view(expression, false, false)
+}
+
+fn overflow(left: i32, right: i32) -> ArithmeticError {
+	ArithmeticError::overflow(&NumberValue::from(left), &NumberValue::from(right))
+}
+
+fn checked(value: Option<i32>, left: i32, right: i32) -> Result<i32, ArithmeticError> {
+	value.ok_or_else(|| overflow(left, right))
+}
+
+fn reduce(numerator: i32, denominator: i32) -> Result<Option<Reduction>, ArithmeticError> {
+	let divisor = gcd(numerator as i64, denominator as i64).and_then(|g| i32::try_from(g).ok());
+	let divisor = checked(divisor, numerator, denominator)?;
+
+	if divisor == 1 && denominator > 1 {
+		return Ok(None);
+	}
+
+	let reduced = Rational::new(numerator as i64, denominator as i64).ok_or_else(|| overflow(numerator, denominator))?;
+	let n = checked(i32::try_from(reduced.numerator()).ok(), numerator, denominator)?;
+	let d = checked(i32::try_from(reduced.denominator()).ok(), numerator, denominator)?;
+	let (result, after) = if d == 1 { (Some(NumberValue::from(n)), num(n)) } else { (None, fraction(n, d)) };
+	let operands = vec![NumberValue::from(numerator), NumberValue::from(denominator)];
+
+	Ok(Some((StepKind::FractionReduced(divisor), None, operands, result, after)))
+}
+
+fn operate(bin: &BinaryOperation) -> Result<Option<Reduction>, ArithmeticError> {
+	let ((nl, dl), (nr, dr)) = match (value(bin.left()), value(bin.right())) {
+		(Some(left), Some(right)) => (left, right),
+		_ => return Ok(None),
+	};
+	let operation = bin.operation();
+	let operands = [nl, dl, nr, dr].into_iter().map(NumberValue::from).collect();
+
+	let (kind, after) = match operation {
+		Operation::Add | Operation::Sub if dl != dr => {
+			let divisor = checked(gcd(dl as i64, dr as i64).and_then(|g| i32::try_from(g).ok()), dl, dr)?;
+			let d = checked((dl / divisor).checked_mul(dr).and_then(i32::checked_abs), dl, dr)?;
+			let left = fraction(checked(nl.checked_mul(d / dl), nl, d)?, d);
+			let right = fraction(checked(nr.checked_mul(d / dr), nr, d)?, d);
+
+			(StepKind::CommonDenominator(d), BinaryOperation::new(left, right, operation.clone()).to_expression())
+		}
+		Operation::Add | Operation::Sub => {
+			let numerator = Bracket::from(BinaryOperation::new(num(nl), num(nr), operation.clone()).to_expression());
+			(StepKind::NumeratorsCombined, BinaryOperation::new(numerator, num(dl), Operation::Div).to_expression())
+		}
+		Operation::Mul => {
+			let numerator = Bracket::from(BinaryOperation::new(num(nl), num(nr), Operation::Mul).to_expression());
+			let denominator = match (dl, dr) {
+				(1, d) | (d, 1) => num(d),
+				(l, r) => Bracket::from(BinaryOperation::new(num(l), num(r), Operation::Mul).to_expression()).to_expression(),
+			};
+
+			(StepKind::FractionsMultiplied, BinaryOperation::new(numerator, denominator, Operation::Div).to_expression())
+		}
+		Operation::Div => {
+			if nr == 0 || dl == 0 {
+				return Err(ArithmeticError::division_by_zero(&NumberValue::from(nl)));
+			}
+
+			let reciprocal = if nr < 0 {
+				fraction(checked(dr.checked_neg(), dr, nr)?, checked(nr.checked_neg(), dr, nr)?)
+			} else {
+				fraction(dr, nr)
+			};
+			let after = BinaryOperation::new(bin.left().clone(), Bracket::from(reciprocal), Operation::Mul);
+
+			(StepKind::ReciprocalTaken, after.to_expression())
+		}
+	};
+
+	Ok(Some((kind, Some(operation.clone()), operands, None, after)))
+}
+
+impl Step {
+	// Reduces the node at the path with the quotients kept as fractions. Two integers
+	// divided are a fraction, put over a common denominator before it is added and
+	// reduced once it is worked out. Ok(None) when there is nothing to do at the path.
+	pub fn reduce_fraction(expression: &Expression, path: NodePath) -> Result<Option<Step>, StepFailure> {
+		let Some(target) = path.resolve(expression) else {
+			return Ok(None);
+		};
+
+		let reduction = match target {
+			Expression::Bracket(bracket) => match value(bracket.expression()) {
+				Some((n, d)) if is_fraction(bracket.expression()) => {
+					let operands = vec![NumberValue::from(n), NumberValue::from(d)];
+					Ok(Some((StepKind::BracketRemoved, None, operands, None, bracket.expression().clone())))
+				}
+				_ => return Step::reduce(expression, path),
+			},
+			Expression::BinaryOperation(bin) => match (bin.operation(), integer(bin.left()), integer(bin.right())) {
+				(Operation::Div, Some(n), Some(d)) if d != 0 => reduce(n, d),
+				(_, Some(_), Some(_)) => return Step::reduce(expression, path),
+				_ => operate(bin),
+			},
+			_ => return Step::reduce(expression, path),
+		};
+
+		let (kind, operation, operands, result, replacement) = match reduction {
+			Ok(Some(reduction)) => reduction,
+			Ok(None) => return Ok(None),
+			Err(error) => {
+				let operation = match target {
+					Expression::BinaryOperation(bin) => bin.operation().clone(),
+					_ => Operation::Div,
+				};
+				let operands = match value(target) {
+					Some((n, d)) => vec![NumberValue::from(n), NumberValue::from(d)],
+					None => vec![],
+				};
+
+				return Err(StepFailure::new(expression.clone(), path, operation, operands, error));
+			}
+		};
+
+		let Some(after) = path.replace(expression, replacement) else {
+			return Ok(None);
+		};
+
+		Ok(Some(Step::new(expression.clone(), after, path, kind, operation, operands, result)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::expr;
+	use crate::step::{Division, Steps};
+	use crate::step_strategy::{LeftmostInnermost, PrecedenceSweep};
+
+	use super::*;
+
+	fn steps(expression: Expression) -> Vec<(StepKind, Expression)> {
+		Steps::new(&expression, &LeftmostInnermost)
+			.division(Division::Fraction)
+			.map(|step| step.unwrap())
+			.map(|step| (step.kind(), step.into_after()))
+			.collect()
+	}
+
+	#[test]
+	fn common_denominator() {
+		let act = steps(expr!(1 / 3 + 1 / 4));
+
+		assert_eq!(act.len(), 4);
+		assert_eq!(act[0].0, StepKind::CommonDenominator(12));
+		assert!(act[0].1 == expr!(4 / 12 + 3 / 12));
+		assert_eq!(act[1].0, StepKind::NumeratorsCombined);
+		assert!(act[1].1 == expr!({ 4 + 3 } / 12));
+		assert_eq!(act[2].0, StepKind::OperationEvaluated);
+		assert!(act[2].1 == expr!({ 7 } / 12));
+		assert_eq!(act[3].0, StepKind::BracketRemoved);
+		assert!(act[3].1 == expr!(7 / 12));
+	}
+
+	#[test]
+	fn reduction() {
+		let act = steps(expr!(1 / 6 + 1 / 3));
+
+		assert!(act[0].1 == expr!(1 / 6 + 2 / 6));
+		assert_eq!(act.last().unwrap().0, StepKind::FractionReduced(3));
+		assert!(act.last().unwrap().1 == expr!(1 / 2));
+
+		let act = steps(expr!(3 / 4 - 1 / 4));
+		assert_eq!(act[0].0, StepKind::NumeratorsCombined);
+		assert!(act.last().unwrap().1 == expr!(1 / 2));
+
+		let act = steps(expr!(1 / 2 + 1 / 2));
+		assert!(act.last().unwrap().1 == expr!(1));
+
+		// A fraction already written unreduced is reduced before it is used.
+		let act = steps(expr!(2 / 4 + 1 / 3));
+		assert_eq!(act[0].0, StepKind::FractionReduced(2));
+		assert!(act[1].1 == expr!(3 / 6 + 2 / 6));
+	}
+
+	#[test]
+	fn product_and_quotient() {
+		let act = steps(expr!(2 / 3 * 3 / 4));
+		assert!(act.last().unwrap().1 == expr!(1 / 2));
+
+		let act = steps(expr!(1 + 2 * 3));
+		assert_eq!(act.len(), 2);
+		assert!(act.iter().all(|(kind, _)| kind == &StepKind::OperationEvaluated));
+
+		assert!(steps(expr!(7 / 12)).is_empty());
+		assert!(steps(expr!(6 / 3)).last().unwrap().1 == expr!(2));
+	}
+
+	#[test]
+	fn kept_bracket() {
+		// The bracket a quotient needs stays until the fraction in it is used.
+		let act = steps(expr!(2 / { 3 / 4 }));
+		assert_eq!(act[0].0, StepKind::ReciprocalTaken);
+		assert!(act[0].1 == expr!(2 * { 4 / 3 }));
+		assert_eq!(act[1].0, StepKind::FractionsMultiplied);
+		assert!(act[1].1 == expr!({ 2 * 4 } / 3));
+		assert!(act.last().unwrap().1 == expr!(8 / 3));
+
+		let act = steps(expr!({ 1 / 2 } / { 3 / 4 }));
+		assert_eq!(act[0].0, StepKind::ReciprocalTaken);
+		assert!(act[0].1 == expr!({ 1 / 2 } * { 4 / 3 }));
+		assert_eq!(act[1].0, StepKind::BracketRemoved);
+		assert!(act[1].1 == expr!(1 / 2 * { 4 / 3 }));
+		assert!(act.last().unwrap().1 == expr!(2 / 3));
+
+		let act = steps(expr!(1 + { 1 / 2 }));
+		assert_eq!(act[0].0, StepKind::BracketRemoved);
+		assert!(act[0].1 == expr!(1 + 1 / 2));
+	}
+
+	#[test]
+	fn strategy() {
+		let fixture = expr!({ 1 + 1 } / 3 + 1 / { 2 * 2 });
+
+		let act: Vec<Expression> = Steps::new(&fixture, &PrecedenceSweep)
+			.division(Division::Fraction)
+			.map(|step| step.unwrap().into_after())
+			.collect();
+		assert!(act[0] == expr!({ 2 } / 3 + 1 / { 2 * 2 }));
+		assert!(act[2] == expr!(2 / 3 + 1 / { 4 }));
+		assert!(act.last().unwrap() == &expr!(11 / 12));
+
+		let act: Vec<Step> = Steps::new(&fixture, &LeftmostInnermost)
+			.division(Division::Fraction)
+			.bracket_removal(crate::step::BracketRemoval::WithReduction)
+			.map(|step| step.unwrap())
+			.collect();
+		assert!(act[0].after() == &expr!(2 / 3 + 1 / { 2 * 2 }));
+		assert!(act[1].after() == &expr!(2 / 3 + 1 / 4));
+		assert!(act.iter().all(|step| step.kind() != StepKind::BracketRemoved));
+	}
+
+	#[test]
+	fn failure() {
+		let act: Vec<_> = Steps::new(&expr!(1 / 3 + 1 / 0), &LeftmostInnermost).division(Division::Fraction).collect();
+
+		assert_eq!(act.len(), 1);
+		let failure = act[0].as_ref().err().unwrap();
+		assert!(failure.error().is_division_by_zero());
+		assert_eq!(failure.path().to_string(), "/R");
+
+		assert!(steps(expr!(x + 1 / 3)).is_empty());
+	}
+}
@@ -0,0 +1,378 @@
+use std::ops::RangeInclusive;
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::{BinaryOperation, Operation};
+use crate::builder::{bracket, num};
+use crate::expression::Expression;
+use crate::number_value::NumberValue;
+
+// Attempts per exercise before the generator gives up on the constraints.
+pub const MAX_ATTEMPTS: usize = 10_000;
+
+// SplitMix64. Small and fixed, so a seed gives the same exercises on every platform and version.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+	state: u64,
+}
+
+impl SplitMix64 {
+	pub fn new(seed: u64) -> Self {
+		SplitMix64 { state: seed }
+	}
+
+	pub fn next_u64(&mut self) -> u64 {
+		self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+		let mut z = self.state;
+		z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+		z ^ (z >> 31)
+	}
+
+	// Uniform enough below the bound for worksheet purposes.
+	pub fn below(&mut self, bound: u64) -> u64 {
+		self.next_u64() % bound
+	}
+
+	// None for an empty range such as 5..=3.
+	pub fn range(&mut self, range: &RangeInclusive<i32>) -> Option<i32> {
+		if range.is_empty() {
+			return None;
+		}
+
+		let width = (i64::from(*range.end()) - i64::from(*range.start()) + 1) as u64;
+		Some((i64::from(*range.start()) + self.below(width) as i64) as i32)
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct ExerciseConstraints {
+	operands: RangeInclusive<i32>,
+	operators: Vec<Operation>,
+	depth: usize,
+	brackets: Option<usize>,
+	exact_division: bool,
+	non_negative: bool,
+	answer: Option<RangeInclusive<i32>>,
+}
+
+impl Default for ExerciseConstraints {
+	fn default() -> Self {
+		ExerciseConstraints {
+			operands: 1..=9,
+			operators: vec![Operation::Add, Operation::Sub, Operation::Mul, Operation::Div],
+			depth: 2,
+			brackets: None,
+			exact_division: true,
+			non_negative: true,
+			answer: None,
+		}
+	}
+}
+
+impl ExerciseConstraints {
+	pub fn operands(mut self, range: RangeInclusive<i32>) -> Self {
+		self.operands = range;
+		self
+	}
+
+	pub fn operators(mut self, operators: &[Operation]) -> Self {
+		self.operators = operators.to_vec();
+		self
+	}
+
+	// Operations on the longest path from the whole formula down to a number.
+	pub fn depth(mut self, depth: usize) -> Self {
+		self.depth = depth;
+		self
+	}
+
+	// Exactly this many brackets. Any number when not set.
+	pub fn brackets(mut self, brackets: usize) -> Self {
+		self.brackets = Some(brackets);
+		self
+	}
+
+	pub fn exact_division(mut self, exact: bool) -> Self {
+		self.exact_division = exact;
+		self
+	}
+
+	// No part of the formula has a value below zero.
+	pub fn non_negative(mut self, non_negative: bool) -> Self {
+		self.non_negative = non_negative;
+		self
+	}
+
+	pub fn answer(mut self, range: RangeInclusive<i32>) -> Self {
+		self.answer = Some(range);
+		self
+	}
+}
+
+// Every operation in the formula satisfies the constraints. The values of the parts are
+// worked out once, from the numbers up.
+fn acceptable(expression: &Expression, constraints: &ExerciseConstraints) -> bool {
+	enum Task<'a> {
+		Visit(&'a Expression),
+		Apply(&'a Operation),
+	}
+
+	let mut tasks = vec![Task::Visit(expression)];
+	let mut values = Vec::<i32>::new();
+
+	while let Some(task) = tasks.pop() {
+		let value = match task {
+			Task::Visit(expr) => match expr {
+				Expression::Number(number) => {
+					let NumberValue::Integer(i) = number.number();
+					*i
+				}
+				Expression::Variable(_) => return false,
+				Expression::Bracket(inner) => {
+					tasks.push(Task::Visit(inner.expression()));
+					continue;
+				}
+				Expression::BinaryOperation(bin) => {
+					tasks.push(Task::Apply(bin.operation()));
+					tasks.push(Task::Visit(bin.right()));
+					tasks.push(Task::Visit(bin.left()));
+					continue;
+				}
+			},
+			Task::Apply(operation) => {
+				let right = values.pop().unwrap();
+				let left = values.pop().unwrap();
+
+				if constraints.exact_division && *operation == Operation::Div && left.checked_rem(right) != Some(0) {
+					return false;
+				}
+
+				let value = match operation {
+					Operation::Add => left.checked_add(right),
+					Operation::Sub => left.checked_sub(right),
+					Operation::Mul => left.checked_mul(right),
+					Operation::Div => left.checked_div(right),
+				};
+
+				match value {
+					Some(value) => value,
+					None => return false,
+				}
+			}
+		};
+
+		if constraints.non_negative && value < 0 {
+			return false;
+		}
+
+		values.push(value);
+	}
+
+	true
+}
+
+// Yields random formulas within the constraints, the same ones for the same seed.
+// Ends when no formula is found in MAX_ATTEMPTS tries.
+pub struct ExerciseGenerator {
+	rng: SplitMix64,
+	constraints: ExerciseConstraints,
+}
+
+impl ExerciseGenerator {
+	pub fn new(seed: u64, constraints: ExerciseConstraints) -> Self {
+		ExerciseGenerator {
+			rng: SplitMix64::new(seed),
+			constraints,
+		}
+	}
+
+	// A tree with exactly the given depth, brackets added where the order needs them.
+	// None when there are no operands to pick from.
+	fn build(&mut self, depth: usize) -> Option<Expression> {
+		if depth == 0 || self.constraints.operators.is_empty() {
+			return self.rng.range(&self.constraints.operands).map(num);
+		}
+
+		let idx = self.rng.below(self.constraints.operators.len() as u64) as usize;
+		let operation = self.constraints.operators[idx].clone();
+
+		let other = self.rng.below(depth as u64) as usize;
+		let (left_depth, right_depth) = if self.rng.below(2) == 0 {
+			(depth - 1, other)
+		} else {
+			(other, depth - 1)
+		};
+
+		let mut left = self.build(left_depth)?;
+		let mut right = self.build(right_depth)?;
+
+		if let Expression::BinaryOperation(bin) = &left {
+			if operation.needs_bracket(bin.operation(), false) {
+				left = bracket(left);
+			}
+		}
+
+		if let Expression::BinaryOperation(bin) = &right {
+			if operation.needs_bracket(bin.operation(), true) {
+				right = bracket(right);
+			}
+		}
+
+		Some(BinaryOperation::new(left, right, operation).to_expression())
+	}
+
+	fn fits(&self, expression: &Expression) -> bool {
+		let constraints = &self.constraints;
+
+		if constraints.brackets.is_some_and(|n| n != expression.bracket_count()) {
+			return false;
+		}
+
+		if !acceptable(expression, constraints) {
+			return false;
+		}
+
+		match (&constraints.answer, expression.calc()) {
+			(Some(range), Ok(NumberValue::Integer(i))) => range.contains(&i),
+			_ => true,
+		}
+	}
+}
+
+impl Iterator for ExerciseGenerator {
+	type Item = Expression;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		for _ in 0..MAX_ATTEMPTS {
+			let candidate = self.build(self.constraints.depth)?;
+
+			if self.fits(&candidate) {
+				return Some(candidate);
+			}
+		}
+
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn values(expression: &Expression, found: &mut Vec<i32>) {
+		if let Ok(NumberValue::Integer(i)) = expression.calc() {
+			found.push(i);
+		}
+
+		match expression {
+			Expression::Number(_) | Expression::Variable(_) => {}
+			Expression::Bracket(inner) => values(inner.expression(), found),
+			Expression::BinaryOperation(bin) => {
+				values(bin.left(), found);
+				values(bin.right(), found);
+			}
+		}
+	}
+
+	#[test]
+	fn split_mix() {
+		// Reference values of SplitMix64 for the seed 1234567.
+		let mut rng = SplitMix64::new(1234567);
+		assert_eq!(rng.next_u64(), 6457827717110365317);
+		assert_eq!(rng.next_u64(), 3203168211198807973);
+
+		let mut rng = SplitMix64::new(0);
+		assert!((0..100).all(|_| (3..=5).contains(&rng.range(&(3..=5)).unwrap())));
+		assert!(rng.range(&RangeInclusive::new(5, 3)).is_none());
+	}
+
+	#[test]
+	fn reproducible() {
+		let constraints = ExerciseConstraints::default().depth(3);
+		let first: Vec<Expression> = ExerciseGenerator::new(42, constraints.clone()).take(5).collect();
+		let second: Vec<Expression> = ExerciseGenerator::new(42, constraints.clone()).take(5).collect();
+		let other: Vec<Expression> = ExerciseGenerator::new(43, constraints).take(5).collect();
+
+		assert_eq!(first.len(), 5);
+		assert!(first == second);
+		assert!(first != other);
+	}
+
+	#[test]
+	fn constraints() {
+		let constraints = ExerciseConstraints::default()
+			.operands(10..=99)
+			.operators(&[Operation::Sub, Operation::Div])
+			.depth(2)
+			.answer(0..=20);
+
+		for expr in ExerciseGenerator::new(7, constraints).take(20) {
+			let mut found = vec![];
+			values(&expr, &mut found);
+
+			assert!(found.iter().all(|v| *v >= 0));
+
+			let NumberValue::Integer(answer) = expr.calc().unwrap();
+			assert!((0..=20).contains(&answer));
+		}
+	}
+
+	#[test]
+	fn brackets() {
+		let constraints = ExerciseConstraints::default().depth(3).brackets(2);
+		let act: Vec<Expression> = ExerciseGenerator::new(1, constraints).take(5).collect();
+
+		assert_eq!(act.len(), 5);
+		assert!(act.iter().all(|e| e.bracket_count() == 2));
+	}
+
+	#[test]
+	fn impossible() {
+		// A single digit product is never above 81.
+		let constraints = ExerciseConstraints::default().operators(&[Operation::Mul]).depth(1).answer(100..=200);
+		assert!(ExerciseGenerator::new(1, constraints).next().is_none());
+
+		let constraints = ExerciseConstraints::default().operands(RangeInclusive::new(5, 3));
+		assert!(ExerciseGenerator::new(1, constraints).next().is_none());
+	}
+
+	#[test]
+	fn division_in_product() {
+		let constraints = ExerciseConstraints::default().operators(&[Operation::Mul, Operation::Div]).depth(3);
+		let divisions = |e: &Expression| matches!(e, Expression::BinaryOperation(bin) if *bin.operation() == Operation::Div);
+
+		// Written without brackets, the division would take the product as its dividend.
+		for expr in ExerciseGenerator::new(3, constraints).take(50) {
+			let mut stack = vec![&expr];
+
+			while let Some(e) = stack.pop() {
+				match e {
+					Expression::Number(_) | Expression::Variable(_) => {}
+					Expression::Bracket(inner) => stack.push(inner.expression()),
+					Expression::BinaryOperation(bin) => {
+						assert!(*bin.operation() != Operation::Mul || !divisions(bin.right()));
+						stack.push(bin.left());
+						stack.push(bin.right());
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn acceptable_test() {
+		let exact = ExerciseConstraints::default().exact_division(true);
+
+		assert!(acceptable(&(num(6) / num(3) + num(1)), &exact));
+		assert!(!acceptable(&(num(7) / num(2)), &exact));
+		assert!(!acceptable(&(num(7) / bracket(num(2) - num(2))), &exact));
+		// The remainder and the quotient overflow rather than panic.
+		assert!(!acceptable(&(num(i32::MIN) / num(-1)), &exact));
+		assert!(!acceptable(&(num(i32::MIN) / num(-1)), &ExerciseConstraints::default()));
+
+		let non_negative = ExerciseConstraints::default().non_negative(true);
+		assert!(!acceptable(&(num(10) - bracket(num(2) - num(3))), &non_negative));
+		assert!(acceptable(&(num(10) - bracket(num(3) - num(2))), &non_negative));
+	}
+}
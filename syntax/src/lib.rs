@@ -1,8 +1,31 @@
+pub mod answer_check;
 pub mod arithmetic_expression;
 pub mod binary_operation;
 pub mod bracket;
+pub mod bracketing;
+pub mod builder;
 pub mod dot_writer;
+pub mod equation;
+pub mod explanation;
 pub mod expression;
+pub mod expression_arena;
+pub mod fraction;
+pub mod generator;
 mod id_dispatcher;
+pub mod metrics;
+pub mod misconception;
+pub mod node_path;
 pub mod number;
+pub mod number_theory;
 pub mod number_value;
+pub mod pattern;
+pub mod puzzle;
+pub mod rational;
+pub mod rewrite;
+pub mod session;
+pub mod state_space;
+pub mod step;
+pub mod step_choice;
+pub mod step_strategy;
+pub mod variable;
+pub mod written;
@@ -0,0 +1,239 @@
+use std::fmt::{Display, Formatter};
+
+use crate::binary_operation::Operation;
+use crate::expression::Expression;
+use crate::number_value::NumberValue;
+use crate::step::{Step, StepTrace};
+use crate::step_strategy::{LeftmostInnermost, PrecedenceSweep, Rightmost, StepStrategy};
+use crate::written::WrittenCalculation;
+
+// Measures of how hard a formula is to calculate by hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metrics {
+	depth: usize,
+	nodes: usize,
+	brackets: usize,
+	steps: Vec<(&'static str, usize)>,
+	max_magnitude: u32,
+	precedence_traps: usize,
+	carries: usize,
+}
+
+fn is_multiplicative(operation: &Operation) -> bool {
+	matches!(operation, Operation::Mul | Operation::Div)
+}
+
+fn apply(operation: &Operation, left: NumberValue, right: NumberValue) -> Option<NumberValue> {
+	match operation {
+		Operation::Add => left + right,
+		Operation::Sub => left - right,
+		Operation::Mul => left * right,
+		Operation::Div => left / right,
+	}
+	.ok()
+}
+
+// The carries of the written calculation for a step, on the sizes of its numbers. Adding a
+// negative number is written as a subtraction, and a subtraction below zero the other way round.
+fn step_carries(step: &Step) -> usize {
+	let (Some(operation), [NumberValue::Integer(l), NumberValue::Integer(r)]) = (step.operation(), step.operands()) else {
+		return 0;
+	};
+	let (Some(mut left), Some(mut right)) = (l.checked_abs(), r.checked_abs()) else {
+		return 0;
+	};
+
+	let operation = match operation {
+		Operation::Add | Operation::Sub if ((*l < 0) == (*r < 0)) == (*operation == Operation::Add) => Operation::Add,
+		Operation::Add | Operation::Sub => Operation::Sub,
+		operation => operation.clone(),
+	};
+
+	if operation == Operation::Sub && left < right {
+		(left, right) = (right, left);
+	}
+
+	WrittenCalculation::new(&operation, &NumberValue::from(left), &NumberValue::from(right)).map_or(0, |w| w.carries())
+}
+
+impl Metrics {
+	pub fn of(expression: &Expression) -> Self {
+		let mut nodes = 0;
+		let mut brackets = 0;
+		let mut max_magnitude = 0;
+		let mut precedence_traps = 0;
+
+		// Each node is seen once on the way down and, once its operands have their values,
+		// once more on the way up. None is the value of a part that has none.
+		let mut stack = vec![(expression, false)];
+		let mut values = Vec::<Option<NumberValue>>::new();
+
+		while let Some((expr, operands_done)) = stack.pop() {
+			if operands_done {
+				if let Expression::BinaryOperation(bin) = expr {
+					let right = values.pop().flatten();
+					let left = values.pop().flatten();
+					values.push(left.zip(right).and_then(|(l, r)| apply(bin.operation(), l, r)));
+				}
+			} else {
+				nodes += 1;
+
+				match expr {
+					Expression::Number(num) => values.push(Some(num.number().clone())),
+					Expression::Variable(_) => values.push(None),
+					Expression::Bracket(bracket) => {
+						brackets += 1;
+						stack.push((expr, true));
+						stack.push((bracket.expression(), false));
+						continue;
+					}
+					Expression::BinaryOperation(bin) => {
+						// Read left to right, the + or − would wrongly go first.
+						if !is_multiplicative(bin.operation())
+							&& matches!(bin.right(), Expression::BinaryOperation(r) if is_multiplicative(r.operation()))
+						{
+							precedence_traps += 1;
+						}
+
+						stack.push((expr, true));
+						stack.push((bin.right(), false));
+						stack.push((bin.left(), false));
+						continue;
+					}
+				}
+			}
+
+			if let Some(Some(NumberValue::Integer(i))) = values.last() {
+				max_magnitude = max_magnitude.max(i.unsigned_abs());
+			}
+		}
+
+		let strategies: [(&'static str, &dyn StepStrategy); 3] = [
+			("leftmost", &LeftmostInnermost),
+			("precedence", &PrecedenceSweep),
+			("rightmost", &Rightmost),
+		];
+		let traces: Vec<StepTrace> = strategies.iter().map(|(_, s)| StepTrace::trace(expression, *s)).collect();
+		let steps = strategies.iter().zip(traces.iter()).map(|((name, _), trace)| (*name, trace.len())).collect();
+
+		// The carries of the leftmost innermost order, the first of the traces.
+		let carries = traces[0].iter().map(step_carries).sum();
+
+		Metrics {
+			depth: expression.depth(),
+			nodes,
+			brackets,
+			steps,
+			max_magnitude,
+			precedence_traps,
+			carries,
+		}
+	}
+
+	pub fn depth(&self) -> usize {
+		self.depth
+	}
+
+	pub fn nodes(&self) -> usize {
+		self.nodes
+	}
+
+	pub fn brackets(&self) -> usize {
+		self.brackets
+	}
+
+	// Steps taken by each strategy, by the name the command line uses for it.
+	pub fn steps(&self) -> &[(&'static str, usize)] {
+		&self.steps
+	}
+
+	// The largest absolute value of any part of the formula, the formula itself included.
+	pub fn max_magnitude(&self) -> u32 {
+		self.max_magnitude
+	}
+
+	// + or − followed by × or ÷ without brackets, as in a - b * c.
+	pub fn precedence_traps(&self) -> usize {
+		self.precedence_traps
+	}
+
+	// Carries and borrows in the written calculations.
+	pub fn carries(&self) -> usize {
+		self.carries
+	}
+
+	// A single number to sort exercises by. Each step counts, traps and brackets count
+	// extra, and so does every digit of the largest number met.
+	pub fn difficulty(&self) -> usize {
+		let steps = self.steps.iter().map(|(_, n)| *n).max().unwrap_or(0);
+		let digits = self.max_magnitude.to_string().len();
+
+		steps * 2 + self.depth + self.brackets * 2 + self.precedence_traps * 3 + self.carries + digits
+	}
+}
+
+impl Display for Metrics {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "depth: {}", self.depth)?;
+		writeln!(f, "nodes: {}", self.nodes)?;
+		writeln!(f, "brackets: {}", self.brackets)?;
+
+		for (name, count) in self.steps.iter() {
+			writeln!(f, "steps ({}): {}", name, count)?;
+		}
+
+		writeln!(f, "max magnitude: {}", self.max_magnitude)?;
+		writeln!(f, "precedence traps: {}", self.precedence_traps)?;
+		writeln!(f, "carries: {}", self.carries)?;
+		write!(f, "difficulty: {}", self.difficulty())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::expr;
+
+	use super::*;
+
+	#[test]
+	fn metrics() {
+		let act = Metrics::of(&expr!(47 - 3 * { 8 + 5 }));
+
+		assert_eq!(act.depth(), 5);
+		assert_eq!(act.nodes(), 8);
+		assert_eq!(act.brackets(), 1);
+		assert_eq!(act.steps(), &[("leftmost", 4), ("precedence", 4), ("rightmost", 4)]);
+		assert_eq!(act.max_magnitude(), 47);
+		assert_eq!(act.precedence_traps(), 1);
+		// 8 + 5 is done in the head, 47 - 39 borrows once.
+		assert_eq!(act.carries(), 1);
+		assert_eq!(act.difficulty(), 8 + 5 + 2 + 3 + 1 + 2);
+	}
+
+	#[test]
+	fn simple() {
+		let act = Metrics::of(&expr!(2 * 3 + 1));
+
+		assert_eq!(act.precedence_traps(), 0);
+		assert_eq!(act.carries(), 0);
+		assert!(act.difficulty() < Metrics::of(&expr!(1 + 2 * 3)).difficulty());
+	}
+
+	#[test]
+	fn carries() {
+		// Three in each of 246 × 5 and 246 × 9.
+		assert_eq!(Metrics::of(&expr!(246 * 950)).carries(), 6);
+		// Written as 233700 − 61.
+		assert_eq!(Metrics::of(&expr!(61 - 233700)).carries(), 2);
+		// Written as 47 + 36.
+		assert_eq!(Metrics::of(&expr!(47 - { 0 - 36 })).carries(), 1);
+	}
+
+	#[test]
+	fn display() {
+		let act = Metrics::of(&expr!(1 + 2)).to_string();
+
+		assert!(act.starts_with("depth: 2\nnodes: 3\n"));
+		assert!(act.ends_with("difficulty: 5"));
+	}
+}
@@ -0,0 +1,384 @@
+use std::fmt::{Display, Formatter};
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::Operation;
+use crate::expression::Expression;
+use crate::number_value::{ArithmeticError, NumberResult, NumberValue};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlatToken {
+	Number(NumberValue),
+	Variable(char),
+	Operator(Operation),
+	Open,
+	Close,
+}
+
+// The formula as it is written, a row of tokens with no structure, so that it can be
+// read again by rules other than the right ones.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatFormula {
+	tokens: Vec<FlatToken>,
+}
+
+impl From<&Expression> for FlatFormula {
+	fn from(expression: &Expression) -> Self {
+		// What is still to be written, last first.
+		enum Pending<'a> {
+			Expression(&'a Expression),
+			Token(FlatToken),
+		}
+
+		let mut tokens = vec![];
+		let mut stack = vec![Pending::Expression(expression)];
+
+		while let Some(pending) = stack.pop() {
+			match pending {
+				Pending::Token(token) => tokens.push(token),
+				Pending::Expression(Expression::Number(num)) => tokens.push(FlatToken::Number(num.number().clone())),
+				Pending::Expression(Expression::Variable(var)) => tokens.push(FlatToken::Variable(var.name())),
+				Pending::Expression(Expression::Bracket(bracket)) => {
+					tokens.push(FlatToken::Open);
+					stack.push(Pending::Token(FlatToken::Close));
+					stack.push(Pending::Expression(bracket.expression()));
+				}
+				Pending::Expression(Expression::BinaryOperation(bin)) => {
+					stack.push(Pending::Expression(bin.right()));
+					stack.push(Pending::Token(FlatToken::Operator(bin.operation().clone())));
+					stack.push(Pending::Expression(bin.left()));
+				}
+			}
+		}
+
+		FlatFormula { tokens }
+	}
+}
+
+// How the tokens are read back.
+#[derive(Clone, Copy)]
+struct Reading {
+	precedence: bool,
+	subtraction_from_right: bool,
+}
+
+impl Reading {
+	const RIGHT: Reading = Reading {
+		precedence: true,
+		subtraction_from_right: false,
+	};
+}
+
+fn apply(operation: &Operation, left: NumberValue, right: NumberValue) -> NumberResult {
+	match operation {
+		Operation::Add => left + right,
+		Operation::Sub => left - right,
+		Operation::Mul => left * right,
+		Operation::Div => left / right,
+	}
+}
+
+// A formula that ends early, has an operator where a number belongs or a bracket that
+// does not match. Both operands are reported as zero.
+fn malformed() -> ArithmeticError {
+	let zero = NumberValue::from(0);
+	ArithmeticError::new(&zero, &zero, "MALFORMED!")
+}
+
+// One bracket level read so far: its values, and the operators between them.
+#[derive(Default)]
+struct Level {
+	values: Vec<NumberValue>,
+	operators: Vec<Operation>,
+}
+
+impl Level {
+	fn value(self, reading: Reading) -> NumberResult {
+		let mut values = self.values.into_iter();
+		let mut value = values.next().ok_or_else(malformed)?;
+		let mut terms = vec![];
+		let mut operators = vec![];
+
+		// × and ÷ are taken into the term they belong to, unless they have no precedence.
+		for (op, right) in self.operators.into_iter().zip(values) {
+			if reading.precedence && matches!(op, Operation::Mul | Operation::Div) {
+				value = apply(&op, value, right)?;
+			} else {
+				terms.push(value);
+				operators.push(op);
+				value = right;
+			}
+		}
+
+		terms.push(value);
+
+		if reading.subtraction_from_right && operators.contains(&Operation::Sub) {
+			let mut value = terms.pop().unwrap();
+
+			while let (Some(op), Some(left)) = (operators.pop(), terms.pop()) {
+				value = apply(&op, left, value)?;
+			}
+
+			return Ok(value);
+		}
+
+		let mut terms = terms.into_iter();
+		let mut value = terms.next().unwrap();
+
+		for (op, right) in operators.iter().zip(terms) {
+			value = apply(op, value, right)?;
+		}
+
+		Ok(value)
+	}
+}
+
+impl FlatFormula {
+	pub fn tokens(&self) -> &[FlatToken] {
+		&self.tokens
+	}
+
+	fn read(&self, reading: Reading) -> NumberResult {
+		let mut levels = vec![Level::default()];
+		let mut operand = true;
+
+		for token in self.tokens.iter() {
+			let level = levels.last_mut().unwrap();
+
+			// Whether a number or an open bracket comes next.
+			operand = match (token, operand) {
+				(FlatToken::Number(value), true) => {
+					level.values.push(value.clone());
+					false
+				}
+				(FlatToken::Variable(name), true) => return Err(ArithmeticError::unknown(*name)),
+				(FlatToken::Open, true) => {
+					levels.push(Level::default());
+					true
+				}
+				(FlatToken::Operator(op), false) => {
+					level.operators.push(op.clone());
+					true
+				}
+				(FlatToken::Close, false) if levels.len() > 1 => {
+					let value = levels.pop().unwrap().value(reading)?;
+					levels.last_mut().unwrap().values.push(value);
+					false
+				}
+				_ => return Err(malformed()),
+			};
+		}
+
+		match (levels.pop(), operand) {
+			(Some(level), false) if levels.is_empty() => level.value(reading),
+			_ => Err(malformed()),
+		}
+	}
+
+	// The same formula without the brackets the predicate picks by the indices of their
+	// opening and closing tokens.
+	fn without_brackets(&self, pick: impl Fn(usize, usize) -> bool) -> FlatFormula {
+		let mut removed = vec![false; self.tokens.len()];
+		let mut open = vec![];
+
+		for (idx, token) in self.tokens.iter().enumerate() {
+			match token {
+				FlatToken::Open => open.push(idx),
+				FlatToken::Close => {
+					let start = open.pop().unwrap();
+
+					if pick(start, idx) {
+						removed[start] = true;
+						removed[idx] = true;
+					}
+				}
+				_ => {}
+			}
+		}
+
+		FlatFormula {
+			tokens: self
+				.tokens
+				.iter()
+				.zip(removed)
+				.filter(|(_, removed)| !removed)
+				.map(|(token, _)| token.clone())
+				.collect(),
+		}
+	}
+}
+
+// A common mistake in the order of calculation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misconception {
+	// Every operation from left to right, × and ÷ no earlier than + and −. Brackets are kept.
+	LeftToRight,
+	// A row of + and − with a subtraction in it calculated from the right: 9 - 4 - 3 as 9 - {4 - 3}.
+	SubtractionFromRight,
+	// Brackets ignored, the rest as it should be.
+	DropBrackets,
+	// A bracket after − opened without changing the signs inside: a - {b + c} as a - b + c.
+	MinusBracket,
+}
+
+impl Misconception {
+	pub const ALL: [Misconception; 4] = [
+		Misconception::LeftToRight,
+		Misconception::SubtractionFromRight,
+		Misconception::DropBrackets,
+		Misconception::MinusBracket,
+	];
+
+	// The answer a student with this misconception would get.
+	pub fn evaluate(&self, expression: &Expression) -> NumberResult {
+		let flat = FlatFormula::from(expression);
+
+		match self {
+			Misconception::LeftToRight => flat.read(Reading {
+				precedence: false,
+				subtraction_from_right: false,
+			}),
+			Misconception::SubtractionFromRight => flat.read(Reading {
+				precedence: true,
+				subtraction_from_right: true,
+			}),
+			Misconception::DropBrackets => flat.without_brackets(|_, _| true).read(Reading::RIGHT),
+			Misconception::MinusBracket => {
+				// Only a bracket that is the whole of what is subtracted.
+				let tokens = flat.tokens();
+				let subtracted = |open: usize, close: usize| {
+					open > 0
+						&& tokens[open - 1] == FlatToken::Operator(Operation::Sub)
+						&& !matches!(tokens.get(close + 1), Some(FlatToken::Operator(Operation::Mul | Operation::Div)))
+				};
+
+				flat.without_brackets(subtracted).read(Reading::RIGHT)
+			}
+		}
+	}
+}
+
+impl Display for Misconception {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Misconception::LeftToRight => write!(f, "calculating from left to right, ignoring that × and ÷ come first"),
+			Misconception::SubtractionFromRight => write!(f, "subtracting from the right"),
+			Misconception::DropBrackets => write!(f, "ignoring the brackets"),
+			Misconception::MinusBracket => write!(f, "opening a bracket after − without changing the signs"),
+		}
+	}
+}
+
+// The misconceptions that lead to the given wrong answer. Empty when the answer is right.
+pub fn diagnose(expression: &Expression, answer: &NumberValue) -> Vec<Misconception> {
+	if expression.calc().ok().as_ref() == Some(answer) {
+		return vec![];
+	}
+
+	Misconception::ALL
+		.into_iter()
+		.filter(|m| m.evaluate(expression).ok().as_ref() == Some(answer))
+		.collect()
+}
+
+// The distinct wrong answers the misconceptions give, for multiple choice.
+pub fn distractors(expression: &Expression) -> Vec<NumberValue> {
+	let right = expression.calc().ok();
+	let mut values = Vec::<NumberValue>::new();
+
+	for misconception in Misconception::ALL {
+		if let Ok(value) = misconception.evaluate(expression) {
+			if Some(&value) != right.as_ref() && !values.contains(&value) {
+				values.push(value);
+			}
+		}
+	}
+
+	values
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::builder::bracket;
+	use crate::expr;
+
+	use super::*;
+
+	fn evaluate(misconception: Misconception, expression: &Expression) -> i32 {
+		let NumberValue::Integer(i) = misconception.evaluate(expression).unwrap();
+		i
+	}
+
+	#[test]
+	fn flat() {
+		let act = FlatFormula::from(&expr!(1 - { 2 + 3 }));
+		assert_eq!(act.tokens().len(), 7);
+		assert_eq!(act.read(Reading::RIGHT).unwrap(), NumberValue::from(-4));
+	}
+
+	#[test]
+	fn evaluate_test() {
+		let fixture = expr!(2 + 3 * 4);
+		assert_eq!(evaluate(Misconception::LeftToRight, &fixture), 20);
+
+		let fixture = expr!(9 - 4 - 3);
+		assert_eq!(evaluate(Misconception::SubtractionFromRight, &fixture), 8);
+		assert_eq!(evaluate(Misconception::LeftToRight, &fixture), 2);
+
+		let fixture = expr!({ 2 + 3 } * 4);
+		assert_eq!(evaluate(Misconception::DropBrackets, &fixture), 14);
+		assert_eq!(evaluate(Misconception::MinusBracket, &fixture), 20);
+		assert_eq!(evaluate(Misconception::LeftToRight, &fixture), 20);
+
+		let fixture = expr!(10 - { 2 + 3 });
+		assert_eq!(evaluate(Misconception::MinusBracket, &fixture), 11);
+		assert_eq!(evaluate(Misconception::DropBrackets, &fixture), 11);
+	}
+
+	#[test]
+	fn errors() {
+		// The bracket is kept, and so is the division by zero.
+		let fixture = expr!(6 / { 2 - 2 } + 1);
+		assert!(Misconception::LeftToRight.evaluate(&fixture).is_err());
+		assert_eq!(Misconception::DropBrackets.evaluate(&fixture).unwrap(), NumberValue::from(2));
+
+		let unfinished = FlatFormula {
+			tokens: vec![FlatToken::Open, FlatToken::Number(NumberValue::from(1)), FlatToken::Operator(Operation::Add)],
+		};
+		assert_eq!(unfinished.read(Reading::RIGHT).err().unwrap().message(), "MALFORMED!");
+
+		let unopened = FlatFormula {
+			tokens: vec![FlatToken::Number(NumberValue::from(1)), FlatToken::Close],
+		};
+		assert!(unopened.read(Reading::RIGHT).is_err());
+	}
+
+	#[test]
+	fn deep_tree() {
+		let mut fixture = expr!(9 - 4 - 3);
+
+		for _ in 0..100_000 {
+			fixture = bracket(fixture);
+		}
+
+		assert_eq!(evaluate(Misconception::SubtractionFromRight, &fixture), 8);
+		assert_eq!(evaluate(Misconception::DropBrackets, &fixture), 2);
+	}
+
+	#[test]
+	fn diagnose_test() {
+		let fixture = expr!(20 - { 3 + 5 } * 2);
+
+		assert_eq!(diagnose(&fixture, &NumberValue::from(24)), vec![Misconception::LeftToRight]);
+		assert_eq!(diagnose(&fixture, &NumberValue::from(27)), vec![Misconception::DropBrackets]);
+		assert_eq!(diagnose(&fixture, &NumberValue::from(4)), vec![]);
+		assert_eq!(diagnose(&fixture, &NumberValue::from(100)), vec![]);
+	}
+
+	#[test]
+	fn distractors_test() {
+		let act = distractors(&expr!(20 - { 3 + 5 } * 2));
+		assert_eq!(act, vec![NumberValue::from(24), NumberValue::from(27)]);
+
+		assert!(distractors(&expr!(1 + 2)).is_empty());
+	}
+}
@@ -0,0 +1,215 @@
+use std::fmt::{Display, Formatter};
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::BinaryOperation;
+use crate::bracket::Bracket;
+use crate::expression::Expression;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+	Left,
+	Right,
+	Inner,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NodePath(Vec<Direction>);
+
+impl From<Vec<Direction>> for NodePath {
+	fn from(value: Vec<Direction>) -> Self {
+		NodePath(value)
+	}
+}
+
+impl Display for NodePath {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		if self.0.is_empty() {
+			return write!(f, "/");
+		}
+
+		for dir in self.0.iter() {
+			let c = match dir {
+				Direction::Left => 'L',
+				Direction::Right => 'R',
+				Direction::Inner => 'I',
+			};
+			write!(f, "/{}", c)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl NodePath {
+	pub fn root() -> Self {
+		NodePath(Vec::new())
+	}
+
+	pub fn directions(&self) -> &[Direction] {
+		&self.0
+	}
+
+	pub fn depth(&self) -> usize {
+		self.0.len()
+	}
+
+	pub fn is_root(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	pub fn push(&mut self, direction: Direction) {
+		self.0.push(direction)
+	}
+
+	pub fn pop(&mut self) -> Option<Direction> {
+		self.0.pop()
+	}
+
+	pub fn child(&self, direction: Direction) -> NodePath {
+		let mut vec = self.0.clone();
+		vec.push(direction);
+		NodePath(vec)
+	}
+
+	pub fn parent(&self) -> Option<NodePath> {
+		if self.0.is_empty() {
+			None
+		} else {
+			Some(NodePath(self.0[..self.0.len() - 1].to_vec()))
+		}
+	}
+
+	pub fn starts_with(&self, prefix: &NodePath) -> bool {
+		self.0.starts_with(&prefix.0)
+	}
+
+	pub fn resolve<'a>(&self, expression: &'a Expression) -> Option<&'a Expression> {
+		let mut current = expression;
+
+		for dir in self.0.iter() {
+			current = step_into(current, dir)?;
+		}
+
+		Some(current)
+	}
+
+	pub fn replace(&self, expression: &Expression, replacement: Expression) -> Option<Expression> {
+		let mut ancestors = Vec::<&Expression>::with_capacity(self.0.len());
+		let mut current = expression;
+
+		for dir in self.0.iter() {
+			ancestors.push(current);
+			current = step_into(current, dir)?;
+		}
+
+		let mut rebuilt = replacement;
+
+		for (parent, dir) in ancestors.into_iter().zip(self.0.iter()).rev() {
+			rebuilt = match (parent, dir) {
+				(Expression::Bracket(_), Direction::Inner) => Bracket::from(rebuilt).to_expression(),
+				(Expression::BinaryOperation(bin), Direction::Left) => {
+					BinaryOperation::new(rebuilt, bin.right().clone(), bin.operation().clone())
+						.to_expression()
+				}
+				(Expression::BinaryOperation(bin), Direction::Right) => {
+					BinaryOperation::new(bin.left().clone(), rebuilt, bin.operation().clone())
+						.to_expression()
+				}
+				_ => unreachable!(),
+			};
+		}
+
+		Some(rebuilt)
+	}
+}
+
+fn step_into<'a>(expression: &'a Expression, direction: &Direction) -> Option<&'a Expression> {
+	match (expression, direction) {
+		(Expression::Bracket(bracket), Direction::Inner) => Some(bracket.expression()),
+		(Expression::BinaryOperation(bin), Direction::Left) => Some(bin.left()),
+		(Expression::BinaryOperation(bin), Direction::Right) => Some(bin.right()),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::binary_operation::Operation;
+	use crate::number::Number;
+	use crate::number_value::NumberValue;
+
+	use super::*;
+
+	fn num(value: i32) -> Number {
+		Number::from(NumberValue::from(value))
+	}
+
+	// {1 + 2} * 3
+	fn create_fixture() -> Expression {
+		let add = BinaryOperation::new(num(1), num(2), Operation::Add);
+		BinaryOperation::new(Bracket::from(add.to_expression()), num(3), Operation::Mul).to_expression()
+	}
+
+	#[test]
+	fn root() {
+		let fixture = NodePath::root();
+		assert!(fixture.is_root());
+		assert_eq!(fixture.depth(), 0);
+		assert!(fixture.parent().is_none());
+		assert_eq!(fixture.to_string(), "/");
+	}
+
+	#[test]
+	fn child_and_parent() {
+		let fixture = NodePath::root().child(Direction::Left).child(Direction::Inner);
+		assert_eq!(fixture.depth(), 2);
+		assert_eq!(fixture.directions(), &[Direction::Left, Direction::Inner]);
+		assert_eq!(fixture.to_string(), "/L/I");
+
+		let parent = fixture.parent().unwrap();
+		assert_eq!(parent, NodePath::from(vec![Direction::Left]));
+		assert!(fixture.starts_with(&parent));
+		assert!(!parent.starts_with(&fixture));
+
+		let mut fixture = fixture;
+		assert_eq!(fixture.pop(), Some(Direction::Inner));
+		fixture.push(Direction::Right);
+		assert_eq!(fixture.to_string(), "/L/R");
+	}
+
+	#[test]
+	fn resolve() {
+		let expr = create_fixture();
+
+		let act = NodePath::from(vec![Direction::Left, Direction::Inner, Direction::Right]);
+		act.resolve(&expr).unwrap().extract_as_number().number().eq_i32(&2);
+
+		let act = NodePath::from(vec![Direction::Right]);
+		act.resolve(&expr).unwrap().extract_as_number().number().eq_i32(&3);
+
+		assert!(NodePath::from(vec![Direction::Inner]).resolve(&expr).is_none());
+		assert!(NodePath::from(vec![Direction::Right, Direction::Left])
+			.resolve(&expr)
+			.is_none());
+	}
+
+	#[test]
+	fn replace() {
+		let expr = create_fixture();
+		let path = NodePath::from(vec![Direction::Left, Direction::Inner]);
+
+		let act = path.replace(&expr, num(3).to_expression()).unwrap();
+		let act = act.extract_as_binary_operation();
+
+		act.left().extract_as_bracket().expression().extract_as_number().number().eq_i32(&3);
+		act.right().extract_as_number().number().eq_i32(&3);
+		act.calc().unwrap().eq_i32(&9);
+
+		let act = NodePath::root().replace(&expr, num(42).to_expression()).unwrap();
+		act.extract_as_number().number().eq_i32(&42);
+
+		assert!(NodePath::from(vec![Direction::Inner])
+			.replace(&expr, num(0).to_expression())
+			.is_none());
+	}
+}
@@ -0,0 +1,402 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::{BinaryOperation, Operation};
+use crate::builder::num;
+use crate::equation::Equation;
+use crate::expression::Expression;
+
+pub enum NumberTheoryError {
+	NotPositive(i32),
+	// 1 is neither prime nor a product of primes.
+	NoPrimeFactors,
+	TooFewNumbers,
+	Overflow,
+}
+
+impl Clone for NumberTheoryError {
+	fn clone(&self) -> Self {
+		match self {
+			NumberTheoryError::NotPositive(n) => NumberTheoryError::NotPositive(*n),
+			NumberTheoryError::NoPrimeFactors => NumberTheoryError::NoPrimeFactors,
+			NumberTheoryError::TooFewNumbers => NumberTheoryError::TooFewNumbers,
+			NumberTheoryError::Overflow => NumberTheoryError::Overflow,
+		}
+	}
+}
+
+impl Debug for NumberTheoryError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			NumberTheoryError::NotPositive(n) => write!(f, "NotPositive:{}", n),
+			NumberTheoryError::NoPrimeFactors => write!(f, "NoPrimeFactors"),
+			NumberTheoryError::TooFewNumbers => write!(f, "TooFewNumbers"),
+			NumberTheoryError::Overflow => write!(f, "Overflow"),
+		}
+	}
+}
+
+impl Display for NumberTheoryError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			NumberTheoryError::NotPositive(n) => write!(f, "{} is not a positive number", n),
+			NumberTheoryError::NoPrimeFactors => write!(f, "1 has no prime factors"),
+			NumberTheoryError::TooFewNumbers => write!(f, "at least two numbers are needed"),
+			NumberTheoryError::Overflow => write!(f, "the result is too large"),
+		}
+	}
+}
+
+impl Error for NumberTheoryError {}
+
+fn positive(numbers: &[i32]) -> Result<(), NumberTheoryError> {
+	match numbers.iter().find(|n| **n <= 0) {
+		Some(n) => Err(NumberTheoryError::NotPositive(*n)),
+		None => Ok(()),
+	}
+}
+
+fn smallest_prime_factor(n: i32) -> i32 {
+	let mut p = 2;
+
+	while (p as i64) * (p as i64) <= n as i64 {
+		if n % p == 0 {
+			return p;
+		}
+
+		p += 1;
+	}
+
+	n
+}
+
+// n = 2 * 6, 6 = 2 * 3. The prime split off is always the smallest, so the tree
+// leans to the right.
+pub struct FactorTree {
+	value: i32,
+	split: Option<(i32, Box<FactorTree>)>,
+}
+
+impl FactorTree {
+	pub fn value(&self) -> i32 {
+		self.value
+	}
+
+	// None for a prime, which is a leaf.
+	pub fn prime(&self) -> Option<i32> {
+		self.split.as_ref().map(|(p, _)| *p)
+	}
+
+	pub fn rest(&self) -> Option<&FactorTree> {
+		self.split.as_ref().map(|(_, rest)| rest.as_ref())
+	}
+}
+
+pub fn factor_tree(n: i32) -> Result<FactorTree, NumberTheoryError> {
+	positive(&[n])?;
+
+	if n == 1 {
+		return Err(NumberTheoryError::NoPrimeFactors);
+	}
+
+	let mut values = vec![];
+	let mut m = n;
+
+	loop {
+		let p = smallest_prime_factor(m);
+		values.push((m, p));
+
+		if p == m {
+			break;
+		}
+
+		m /= p;
+	}
+
+	let (last, _) = values.pop().unwrap();
+	let mut tree = FactorTree { value: last, split: None };
+
+	for (value, p) in values.into_iter().rev() {
+		tree = FactorTree {
+			value,
+			split: Some((p, Box::new(tree))),
+		};
+	}
+
+	Ok(tree)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrimePower {
+	prime: i32,
+	exponent: u32,
+}
+
+impl PrimePower {
+	pub fn prime(&self) -> i32 {
+		self.prime
+	}
+
+	pub fn exponent(&self) -> u32 {
+		self.exponent
+	}
+}
+
+impl Display for PrimePower {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self.exponent {
+			1 => write!(f, "{}", self.prime),
+			e => write!(f, "{}^{}", self.prime, e),
+		}
+	}
+}
+
+fn primes(n: i32) -> Result<Vec<i32>, NumberTheoryError> {
+	let mut primes = vec![];
+	let mut tree = Some(&factor_tree(n)?);
+
+	while let Some(node) = tree {
+		primes.push(node.prime().unwrap_or(node.value()));
+		tree = node.rest();
+	}
+
+	Ok(primes)
+}
+
+// Smallest prime first.
+pub fn factorize(n: i32) -> Result<Vec<PrimePower>, NumberTheoryError> {
+	let mut powers = Vec::<PrimePower>::new();
+
+	for p in primes(n)? {
+		match powers.last_mut() {
+			Some(power) if power.prime == p => power.exponent += 1,
+			_ => powers.push(PrimePower { prime: p, exponent: 1 }),
+		}
+	}
+
+	Ok(powers)
+}
+
+// 2^2 * 3
+pub fn power_notation(powers: &[PrimePower]) -> String {
+	powers.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(" * ")
+}
+
+fn product(factors: &[i32]) -> Expression {
+	let mut iter = factors.iter();
+	let mut expr = num(*iter.next().unwrap_or(&1));
+
+	for factor in iter {
+		expr = BinaryOperation::new(expr, num(*factor), Operation::Mul).to_expression();
+	}
+
+	expr
+}
+
+// One row of the work, such as 48 = 2 * 18 + 12, with what was done in the caption.
+pub struct TheoryStep {
+	caption: String,
+	equation: Equation,
+}
+
+impl TheoryStep {
+	pub fn caption(&self) -> &str {
+		&self.caption
+	}
+
+	pub fn equation(&self) -> &Equation {
+		&self.equation
+	}
+}
+
+pub struct TheoryTrace {
+	steps: Vec<TheoryStep>,
+	value: i32,
+	result: String,
+	tree: Option<FactorTree>,
+}
+
+impl TheoryTrace {
+	pub fn steps(&self) -> &[TheoryStep] {
+		&self.steps
+	}
+
+	pub fn value(&self) -> i32 {
+		self.value
+	}
+
+	// The answer as it is written, such as gcd(48, 18) = 6 or 12 = 2^2 * 3.
+	pub fn result(&self) -> &str {
+		&self.result
+	}
+
+	// Only for factor.
+	pub fn tree(&self) -> Option<&FactorTree> {
+		self.tree.as_ref()
+	}
+}
+
+// Euclid's algorithm, one division per row until the remainder is 0. The last
+// divisor is the gcd.
+pub fn gcd(a: i32, b: i32) -> Result<TheoryTrace, NumberTheoryError> {
+	positive(&[a, b])?;
+
+	let mut steps = vec![];
+	let (mut dividend, mut divisor) = (a, b);
+
+	while divisor != 0 {
+		let (quotient, remainder) = (dividend / divisor, dividend % divisor);
+		let right = BinaryOperation::new(
+			BinaryOperation::new(num(quotient), num(divisor), Operation::Mul),
+			num(remainder),
+			Operation::Add,
+		);
+
+		steps.push(TheoryStep {
+			caption: format!("divide {} by {}", dividend, divisor),
+			equation: Equation::new(num(dividend), right.to_expression()),
+		});
+
+		(dividend, divisor) = (divisor, remainder);
+	}
+
+	Ok(TheoryTrace {
+		steps,
+		value: dividend,
+		result: format!("gcd({}, {}) = {}", a, b, dividend),
+		tree: None,
+	})
+}
+
+// Splits off the smallest prime factor until only primes are left.
+pub fn factor(n: i32) -> Result<TheoryTrace, NumberTheoryError> {
+	let tree = factor_tree(n)?;
+	let mut steps = vec![];
+	let mut factors = vec![];
+	let mut node = &tree;
+
+	while let (Some(p), Some(rest)) = (node.prime(), node.rest()) {
+		factors.push(p);
+		steps.push(TheoryStep {
+			caption: format!("divide {} by its smallest prime factor {}", node.value(), p),
+			equation: Equation::new(num(n), product(&[factors.as_slice(), &[rest.value()]].concat())),
+		});
+		node = rest;
+	}
+
+	Ok(TheoryTrace {
+		steps,
+		value: n,
+		result: format!("{} = {}", n, power_notation(&factorize(n)?)),
+		tree: Some(tree),
+	})
+}
+
+// Factors every number and multiplies the highest power of each prime among them.
+pub fn lcm(numbers: &[i32]) -> Result<TheoryTrace, NumberTheoryError> {
+	if numbers.len() < 2 {
+		return Err(NumberTheoryError::TooFewNumbers);
+	}
+
+	positive(numbers)?;
+
+	let mut steps = vec![];
+	let mut highest = Vec::<PrimePower>::new();
+
+	for n in numbers.iter().filter(|n| **n != 1) {
+		steps.push(TheoryStep {
+			caption: format!("factor {}", n),
+			equation: Equation::new(num(*n), product(&primes(*n)?)),
+		});
+
+		for power in factorize(*n)? {
+			match highest.iter_mut().find(|p| p.prime == power.prime) {
+				Some(p) => p.exponent = p.exponent.max(power.exponent),
+				None => highest.push(power),
+			}
+		}
+	}
+
+	highest.sort_by_key(|p| p.prime);
+
+	let mut value = 1i32;
+	let mut factors = vec![];
+
+	for power in highest.iter() {
+		for _ in 0..power.exponent {
+			value = value.checked_mul(power.prime).ok_or(NumberTheoryError::Overflow)?;
+			factors.push(power.prime);
+		}
+	}
+
+	steps.push(TheoryStep {
+		caption: format!("multiply the highest power of each prime: {}", power_notation(&highest)),
+		equation: Equation::new(product(&factors), num(value)),
+	});
+
+	let list = numbers.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ");
+
+	Ok(TheoryTrace {
+		steps,
+		value,
+		result: format!("lcm({}) = {}", list, value),
+		tree: None,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::expr;
+
+	use super::*;
+
+	#[test]
+	fn euclid() {
+		let act = gcd(48, 18).unwrap();
+
+		assert_eq!(act.value(), 6);
+		assert_eq!(act.result(), "gcd(48, 18) = 6");
+		assert_eq!(act.steps().len(), 3);
+		assert_eq!(act.steps()[0].caption(), "divide 48 by 18");
+		assert!(act.steps()[0].equation() == &Equation::new(expr!(48), expr!(2 * 18 + 12)));
+		assert!(act.steps()[2].equation() == &Equation::new(expr!(12), expr!(2 * 6 + 0)));
+
+		assert_eq!(gcd(7, 5).unwrap().value(), 1);
+		assert!(matches!(gcd(0, 5), Err(NumberTheoryError::NotPositive(0))));
+	}
+
+	#[test]
+	fn factors() {
+		let act = factor(60).unwrap();
+
+		assert_eq!(act.result(), "60 = 2^2 * 3 * 5");
+		assert_eq!(act.steps().len(), 3);
+		assert_eq!(act.steps()[0].caption(), "divide 60 by its smallest prime factor 2");
+		assert!(act.steps()[0].equation() == &Equation::new(expr!(60), expr!(2 * 30)));
+		assert!(act.steps()[2].equation() == &Equation::new(expr!(60), expr!(2 * 2 * 3 * 5)));
+
+		let tree = act.tree().unwrap();
+		assert_eq!((tree.value(), tree.prime()), (60, Some(2)));
+		assert_eq!(tree.rest().unwrap().value(), 30);
+
+		assert!(factor(13).unwrap().steps().is_empty());
+		assert_eq!(factor(13).unwrap().result(), "13 = 13");
+		assert!(matches!(factor(1), Err(NumberTheoryError::NoPrimeFactors)));
+		assert_eq!(power_notation(&factorize(2147483647).unwrap()), "2147483647");
+	}
+
+	#[test]
+	fn prime_powers() {
+		let act = lcm(&[12, 18]).unwrap();
+
+		assert_eq!(act.value(), 36);
+		assert_eq!(act.result(), "lcm(12, 18) = 36");
+		assert_eq!(act.steps()[2].caption(), "multiply the highest power of each prime: 2^2 * 3^2");
+		assert!(act.steps()[2].equation() == &Equation::new(expr!(2 * 2 * 3 * 3), expr!(36)));
+
+		assert_eq!(lcm(&[4, 6, 1]).unwrap().value(), 12);
+		assert!(matches!(lcm(&[4]), Err(NumberTheoryError::TooFewNumbers)));
+		assert!(matches!(lcm(&[65536, 65537]), Err(NumberTheoryError::Overflow)));
+	}
+}
@@ -4,6 +4,10 @@ use std::ops::{Add, Div, Mul, Rem, Sub};
 
 pub type NumberResult = Result<NumberValue, ArithmeticError>;
 
+const DIVISION_BY_ZERO: &str = "DIV/0!";
+const OVERFLOW: &str = "OVERFLOW!";
+const UNKNOWN: &str = "UNKNOWN:";
+
 pub struct ArithmeticError {
 	rhs: NumberValue,
 	lhs: NumberValue,
@@ -32,6 +36,40 @@ impl ArithmeticError {
 	}
 }
 
+impl Clone for ArithmeticError {
+	fn clone(&self) -> Self {
+		ArithmeticError {
+			rhs: self.rhs.clone(),
+			lhs: self.lhs.clone(),
+			message: self.message.clone(),
+		}
+	}
+}
+
+impl ArithmeticError {
+	pub fn is_division_by_zero(&self) -> bool {
+		self.message == DIVISION_BY_ZERO
+	}
+	
+	pub fn division_by_zero(lhs: &NumberValue) -> Self {
+		ArithmeticError::new(lhs, &NumberValue::from(0), DIVISION_BY_ZERO)
+	}
+	
+	pub fn overflow(lhs: &NumberValue, rhs: &NumberValue) -> Self {
+		ArithmeticError::new(lhs, rhs, OVERFLOW)
+	}
+	
+	// A formula with an unknown in it has no value. Both operands are reported as zero.
+	pub fn unknown(name: char) -> Self {
+		let zero = NumberValue::from(0);
+		ArithmeticError::new(&zero, &zero, &format!("{}{}", UNKNOWN, name))
+	}
+	
+	pub fn is_unknown(&self) -> bool {
+		self.message.starts_with(UNKNOWN)
+	}
+}
+
 impl Debug for ArithmeticError {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
 		write!(f, "lhs:{} rhs:{} msg:{}", self.lhs, self.rhs, self.message)?;
@@ -78,11 +116,12 @@ impl Add<Self> for NumberValue {
 	type Output = NumberResult;
 	
 	fn add(self, rhs: NumberValue) -> Self::Output {
-		Ok(match self {
-			NumberValue::Integer(l) => match rhs {
-				NumberValue::Integer(r) => NumberValue::Integer(l + r),
+		match (&self, &rhs) {
+			(NumberValue::Integer(l), NumberValue::Integer(r)) => match l.checked_add(*r) {
+				Some(v) => Ok(NumberValue::Integer(v)),
+				None => Err(ArithmeticError::new(&self, &rhs, OVERFLOW)),
 			},
-		})
+		}
 	}
 }
 
@@ -90,11 +129,12 @@ impl Sub<Self> for NumberValue {
 	type Output = NumberResult;
 	
 	fn sub(self, rhs: NumberValue) -> Self::Output {
-		Ok(match self {
-			NumberValue::Integer(l) => match rhs {
-				NumberValue::Integer(r) => NumberValue::Integer(l - r),
+		match (&self, &rhs) {
+			(NumberValue::Integer(l), NumberValue::Integer(r)) => match l.checked_sub(*r) {
+				Some(v) => Ok(NumberValue::Integer(v)),
+				None => Err(ArithmeticError::new(&self, &rhs, OVERFLOW)),
 			},
-		})
+		}
 	}
 }
 
@@ -102,11 +142,12 @@ impl Mul<Self> for NumberValue {
 	type Output = NumberResult;
 	
 	fn mul(self, rhs: NumberValue) -> Self::Output {
-		Ok(match self {
-			NumberValue::Integer(l) => match rhs {
-				NumberValue::Integer(r) => NumberValue::Integer(l * r),
+		match (&self, &rhs) {
+			(NumberValue::Integer(l), NumberValue::Integer(r)) => match l.checked_mul(*r) {
+				Some(v) => Ok(NumberValue::Integer(v)),
+				None => Err(ArithmeticError::new(&self, &rhs, OVERFLOW)),
 			},
-		})
+		}
 	}
 }
 
@@ -114,13 +155,12 @@ impl Div<Self> for NumberValue {
 	type Output = NumberResult;
 	
 	fn div(self, rhs: NumberValue) -> Self::Output {
-		match rhs {
-			NumberValue::Integer(0) => Err(ArithmeticError::new(&self, &rhs, "DIV/0!")),
-			_ => Ok(match self {
-				NumberValue::Integer(l) => match rhs {
-					NumberValue::Integer(r) => NumberValue::Integer(l / r),
-				},
-			}),
+		match (&self, &rhs) {
+			(_, NumberValue::Integer(0)) => Err(ArithmeticError::new(&self, &rhs, DIVISION_BY_ZERO)),
+			(NumberValue::Integer(l), NumberValue::Integer(r)) => match l.checked_div(*r) {
+				Some(v) => Ok(NumberValue::Integer(v)),
+				None => Err(ArithmeticError::new(&self, &rhs, OVERFLOW)),
+			},
 		}
 	}
 }
@@ -129,11 +169,13 @@ impl Rem<Self> for NumberValue {
 	type Output = NumberResult;
 	
 	fn rem(self, rhs: NumberValue) -> Self::Output {
-		Ok(match self {
-			NumberValue::Integer(l) => match rhs {
-				NumberValue::Integer(r) => NumberValue::Integer(l % r),
+		match (&self, &rhs) {
+			(_, NumberValue::Integer(0)) => Err(ArithmeticError::new(&self, &rhs, DIVISION_BY_ZERO)),
+			(NumberValue::Integer(l), NumberValue::Integer(r)) => match l.checked_rem(*r) {
+				Some(v) => Ok(NumberValue::Integer(v)),
+				None => Err(ArithmeticError::new(&self, &rhs, OVERFLOW)),
 			},
-		})
+		}
 	}
 }
 
@@ -284,6 +326,28 @@ mod tests {
 		
 		let txt = format!("{}", act);
 		assert_eq!("lhs:100i32 rhs:0i32 msg:DIV/0!", txt);
+		assert!(act.is_division_by_zero());
+		
+		assert!((NumberValue::Integer(5) % NumberValue::Integer(0)).unwrap_err().is_division_by_zero());
+	}
+	
+	#[test]
+	fn overflow_test() {
+		let max = NumberValue::Integer(i32::MAX);
+		let min = NumberValue::Integer(i32::MIN);
+		let one = NumberValue::Integer(1);
+		
+		let act = (max.clone() + one.clone()).unwrap_err();
+		assert_eq!(act.message(), "OVERFLOW!");
+		assert!(!act.is_division_by_zero());
+		act.lhs().eq_i32(&i32::MAX);
+		
+		assert!((min.clone() - one.clone()).is_err());
+		assert!((max * NumberValue::Integer(2)).is_err());
+		assert!((min.clone() / NumberValue::Integer(-1)).is_err());
+		assert!((min % NumberValue::Integer(-1)).is_err());
+		
+		(one.clone() / one).unwrap().eq_i32(&1);
 	}
 	
 	#[test]
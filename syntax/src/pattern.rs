@@ -0,0 +1,349 @@
+use std::fmt::{Display, Formatter};
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::{BinaryOperation, Operation};
+use crate::bracket::Bracket;
+use crate::expression::Expression;
+use crate::node_path::{Direction, NodePath};
+use crate::number::Number;
+use crate::number_value::NumberValue;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+	Wildcard,
+	Variable(String),
+	Number(NumberValue),
+	Bracket(Box<Pattern>),
+	BinaryOperation(Box<Pattern>, Box<Pattern>, Operation),
+}
+
+impl Display for Pattern {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Pattern::Wildcard => write!(f, "_"),
+			Pattern::Variable(name) => write!(f, "{}", name),
+			Pattern::Number(NumberValue::Integer(i)) => write!(f, "{}", i),
+			Pattern::Bracket(inner) => write!(f, "{{{}}}", inner),
+			Pattern::BinaryOperation(left, right, op) => {
+				let c = match op {
+					Operation::Add => '+',
+					Operation::Sub => '-',
+					Operation::Mul => '*',
+					Operation::Div => '/',
+				};
+				write!(f, "{} {} {}", left, c, right)
+			}
+		}
+	}
+}
+
+#[derive(Clone, Default)]
+pub struct Bindings(Vec<(String, Expression)>);
+
+impl Bindings {
+	pub fn get(&self, name: &str) -> Option<&Expression> {
+		self.0.iter().find(|(n, _)| n == name).map(|(_, e)| e)
+	}
+
+	pub fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &Expression)> {
+		self.0.iter().map(|(n, e)| (n.as_str(), e))
+	}
+
+	// A variable that is already bound only accepts a structurally equal expression.
+	fn bind(&mut self, name: &str, expression: &Expression) -> bool {
+		match self.get(name) {
+			Some(bound) => bound == expression,
+			None => {
+				self.0.push((name.to_string(), expression.clone()));
+				true
+			}
+		}
+	}
+}
+
+pub struct PatternMatch {
+	path: NodePath,
+	bindings: Bindings,
+}
+
+impl PatternMatch {
+	pub fn path(&self) -> &NodePath {
+		&self.path
+	}
+
+	pub fn bindings(&self) -> &Bindings {
+		&self.bindings
+	}
+}
+
+impl Pattern {
+	pub fn variable(name: &str) -> Self {
+		Pattern::Variable(name.to_string())
+	}
+
+	pub fn number(value: i32) -> Self {
+		Pattern::Number(NumberValue::from(value))
+	}
+
+	pub fn bracket(inner: Pattern) -> Self {
+		Pattern::Bracket(Box::new(inner))
+	}
+
+	pub fn binary_operation(left: Pattern, right: Pattern, operation: Operation) -> Self {
+		Pattern::BinaryOperation(Box::new(left), Box::new(right), operation)
+	}
+
+	pub fn variables(&self) -> Vec<&str> {
+		let mut vec = Vec::<&str>::new();
+		let mut stack = vec![self];
+
+		while let Some(pattern) = stack.pop() {
+			match pattern {
+				Pattern::Variable(name) => {
+					if !vec.contains(&name.as_str()) {
+						vec.push(name)
+					}
+				}
+				Pattern::Bracket(inner) => stack.push(inner),
+				Pattern::BinaryOperation(left, right, _) => {
+					stack.push(right);
+					stack.push(left);
+				}
+				Pattern::Wildcard | Pattern::Number(_) => {}
+			}
+		}
+
+		vec
+	}
+
+	pub fn has_wildcard(&self) -> bool {
+		let mut stack = vec![self];
+
+		while let Some(pattern) = stack.pop() {
+			match pattern {
+				Pattern::Wildcard => return true,
+				Pattern::Bracket(inner) => stack.push(inner),
+				Pattern::BinaryOperation(left, right, _) => {
+					stack.push(right);
+					stack.push(left);
+				}
+				Pattern::Variable(_) | Pattern::Number(_) => {}
+			}
+		}
+
+		false
+	}
+
+	pub fn match_root(&self, expression: &Expression) -> Option<Bindings> {
+		let mut bindings = Bindings::default();
+		let mut stack = vec![(self, expression)];
+
+		while let Some(pair) = stack.pop() {
+			match pair {
+				(Pattern::Wildcard, _) => {}
+				(Pattern::Variable(name), expr) => {
+					if !bindings.bind(name, expr) {
+						return None;
+					}
+				}
+				(Pattern::Number(value), Expression::Number(num)) => {
+					if value != num.number() {
+						return None;
+					}
+				}
+				(Pattern::Bracket(inner), Expression::Bracket(bracket)) => {
+					stack.push((inner, bracket.expression()))
+				}
+				(Pattern::BinaryOperation(left, right, op), Expression::BinaryOperation(bin)) => {
+					if op != bin.operation() {
+						return None;
+					}
+
+					stack.push((right, bin.right()));
+					stack.push((left, bin.left()));
+				}
+				_ => return None,
+			}
+		}
+
+		Some(bindings)
+	}
+
+	// Builds the expression the pattern describes. Fails on a wildcard or an unbound variable.
+	pub fn substitute(&self, bindings: &Bindings) -> Option<Expression> {
+		let expr = match self {
+			Pattern::Wildcard => return None,
+			Pattern::Variable(name) => bindings.get(name)?.clone(),
+			Pattern::Number(value) => Number::from(value.clone()).to_expression(),
+			Pattern::Bracket(inner) => Bracket::from(inner.substitute(bindings)?).to_expression(),
+			Pattern::BinaryOperation(left, right, op) => BinaryOperation::new(
+				left.substitute(bindings)?,
+				right.substitute(bindings)?,
+				op.clone(),
+			)
+				.to_expression(),
+		};
+
+		Some(expr)
+	}
+
+	// Every matching subexpression in pre-order, outermost and leftmost first.
+	pub fn matches(&self, expression: &Expression) -> Vec<PatternMatch> {
+		let mut vec = Vec::<PatternMatch>::new();
+		let mut stack = vec![(expression, NodePath::root())];
+
+		while let Some((expr, path)) = stack.pop() {
+			match expr {
+				Expression::Number(_) | Expression::Variable(_) => {}
+				Expression::Bracket(bracket) => {
+					stack.push((bracket.expression(), path.child(Direction::Inner)))
+				}
+				Expression::BinaryOperation(bin) => {
+					stack.push((bin.right(), path.child(Direction::Right)));
+					stack.push((bin.left(), path.child(Direction::Left)));
+				}
+			}
+
+			if let Some(bindings) = self.match_root(expr) {
+				vec.push(PatternMatch { path, bindings });
+			}
+		}
+
+		vec
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::builder::{bracket, num};
+
+	use super::*;
+
+	// a - a
+	fn same_operands() -> Pattern {
+		Pattern::binary_operation(Pattern::variable("a"), Pattern::variable("a"), Operation::Sub)
+	}
+
+	#[test]
+	fn variables() {
+		let fixture = Pattern::binary_operation(
+			Pattern::binary_operation(Pattern::variable("a"), Pattern::variable("c"), Operation::Mul),
+			Pattern::binary_operation(Pattern::variable("b"), Pattern::variable("c"), Operation::Mul),
+			Operation::Add,
+		);
+
+		assert_eq!(fixture.variables(), vec!["a", "c", "b"]);
+		assert!(Pattern::Wildcard.variables().is_empty());
+		assert!(!fixture.has_wildcard());
+		assert!(Pattern::bracket(Pattern::Wildcard).has_wildcard());
+	}
+
+	#[test]
+	fn match_root() {
+		let fixture = Pattern::binary_operation(Pattern::Wildcard, Pattern::number(0), Operation::Mul);
+
+		let act = fixture.match_root(&(num(42) * num(0))).unwrap();
+		assert!(act.is_empty());
+
+		assert!(fixture.match_root(&(num(42) * num(1))).is_none());
+		assert!(fixture.match_root(&(num(42) + num(0))).is_none());
+		assert!(fixture.match_root(&num(0)).is_none());
+	}
+
+	#[test]
+	fn consistent_bindings() {
+		let fixture = same_operands();
+
+		let act = fixture.match_root(&(bracket(num(1) + num(2)) - bracket(num(1) + num(2)))).unwrap();
+		assert_eq!(act.len(), 1);
+		assert!(act.get("a").unwrap() == &bracket(num(1) + num(2)));
+		assert!(act.get("b").is_none());
+
+		assert!(fixture.match_root(&(bracket(num(1) + num(2)) - bracket(num(2) + num(1)))).is_none());
+		assert!(fixture.match_root(&(num(3) - num(3))).is_some());
+	}
+
+	#[test]
+	fn bracket_pattern() {
+		let fixture = Pattern::binary_operation(
+			Pattern::bracket(Pattern::variable("a")),
+			Pattern::bracket(Pattern::variable("a")),
+			Operation::Sub,
+		);
+
+		let act = fixture.match_root(&(bracket(num(4) * num(5)) - bracket(num(4) * num(5)))).unwrap();
+		assert!(act.get("a").unwrap() == &(num(4) * num(5)));
+
+		assert!(fixture.match_root(&(num(3) - num(3))).is_none());
+	}
+
+	#[test]
+	fn matches() {
+		let fixture = Pattern::binary_operation(Pattern::variable("n"), Pattern::number(1), Operation::Div);
+		let expr = bracket(num(6) / num(1)) * bracket(num(3) - num(4) / num(1));
+
+		let act = fixture.matches(&expr);
+		assert_eq!(act.len(), 2);
+
+		assert_eq!(act[0].path().to_string(), "/L/I");
+		assert!(act[0].bindings().get("n").unwrap() == &num(6));
+
+		assert_eq!(act[1].path().to_string(), "/R/I/R");
+		assert!(act[1].bindings().get("n").unwrap() == &num(4));
+
+		for m in act.iter() {
+			assert!(m.path().resolve(&expr).is_some());
+		}
+
+		let act = Pattern::Wildcard.matches(&expr);
+		assert_eq!(act.len(), 11);
+		assert!(act[0].path().is_root());
+
+		let act = Pattern::variable("x").matches(&num(1));
+		let names: Vec<&str> = act[0].bindings().iter().map(|(n, _)| n).collect();
+		assert_eq!(names, vec!["x"]);
+	}
+
+	#[test]
+	fn substitute() {
+		let fixture = Pattern::binary_operation(
+			Pattern::bracket(Pattern::binary_operation(Pattern::variable("a"), Pattern::variable("b"), Operation::Add)),
+			Pattern::variable("c"),
+			Operation::Mul,
+		);
+
+		let bindings = Pattern::binary_operation(
+			Pattern::binary_operation(Pattern::variable("a"), Pattern::variable("c"), Operation::Mul),
+			Pattern::binary_operation(Pattern::variable("b"), Pattern::variable("c"), Operation::Mul),
+			Operation::Add,
+		)
+			.match_root(&(num(2) * num(7) + num(3) * num(7)))
+			.unwrap();
+
+		let act = fixture.substitute(&bindings).unwrap();
+		assert!(act == bracket(num(2) + num(3)) * num(7));
+
+		assert!(Pattern::Wildcard.substitute(&bindings).is_none());
+		assert!(Pattern::variable("x").substitute(&bindings).is_none());
+		assert!(Pattern::number(4).substitute(&Bindings::default()).unwrap() == num(4));
+	}
+
+	#[test]
+	fn display() {
+		let fixture = Pattern::binary_operation(
+			Pattern::bracket(Pattern::binary_operation(Pattern::variable("a"), Pattern::number(-1), Operation::Sub)),
+			Pattern::Wildcard,
+			Operation::Div,
+		);
+
+		assert_eq!(fixture.to_string(), "{a - -1} / _");
+	}
+}
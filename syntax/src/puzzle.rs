@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::{BinaryOperation, Operation};
+use crate::builder::{bracket, num};
+use crate::expression::Expression;
+use crate::rational::Rational;
+
+// More numbers than this are not searched; each one is a level of the search.
+pub const MAX_NUMBERS: usize = 8;
+// Values and formulas the search keeps before it gives up; six numbers keep about 120,000.
+pub const DEFAULT_SEARCH_LIMIT: usize = 1_000_000;
+
+// A number or a formula built from some of the numbers, as the search combines them.
+#[derive(Clone)]
+struct Part {
+	value: Rational,
+	expression: Expression,
+	brackets: usize,
+	// The signed operands of a row of + and −, or of × and ÷, sorted. Empty for a number.
+	items: Vec<String>,
+	// Equal for formulas that differ only in the order of such operands.
+	key: String,
+}
+
+fn inverse(item: &str) -> String {
+	let (sign, rest) = item.split_at(1);
+
+	let sign = match sign {
+		"+" => '-',
+		"-" => '+',
+		"*" => '/',
+		_ => '*',
+	};
+
+	format!("{}{}", sign, rest)
+}
+
+impl Part {
+	fn number(value: i32) -> Self {
+		Part {
+			value: Rational::from(value),
+			expression: num(value),
+			brackets: 0,
+			items: vec![],
+			key: value.to_string(),
+		}
+	}
+
+	// The operands of this part as seen from a parent of the same kind, so that
+	// a - {b - c} and a - b + c become the same row.
+	fn items_under(&self, parent: &Operation) -> Vec<String> {
+		match &self.expression {
+			Expression::BinaryOperation(bin) if bin.operation().is_additive() == parent.is_additive() => {
+				self.items.clone()
+			}
+			_ if parent.is_additive() => vec![format!("+{}", self.key)],
+			_ => vec![format!("*{}", self.key)],
+		}
+	}
+
+	// Brackets where minimal_infix_notation writes them, so that the count matches what is shown.
+	fn operand(&self, parent: &Operation, right: bool) -> (Expression, usize) {
+		match &self.expression {
+			Expression::BinaryOperation(bin) if parent.needs_bracket(bin.operation(), right) => {
+				(bracket(self.expression.clone()), self.brackets + 1)
+			}
+			_ => (self.expression.clone(), self.brackets),
+		}
+	}
+
+	fn combine(operation: Operation, left: &Part, right: &Part) -> Option<Part> {
+		let value = Rational::apply(&operation, &left.value, &right.value)?;
+
+		let mut items = left.items_under(&operation);
+
+		match operation {
+			Operation::Sub | Operation::Div => items.extend(right.items_under(&operation).iter().map(|i| inverse(i))),
+			Operation::Add | Operation::Mul => items.extend(right.items_under(&operation)),
+		}
+
+		items.sort();
+		let key = format!("({})", items.concat());
+
+		let (left, left_brackets) = left.operand(&operation, false);
+		let (right, right_brackets) = right.operand(&operation, true);
+
+		Some(Part {
+			value,
+			expression: BinaryOperation::new(left, right, operation).to_expression(),
+			brackets: left_brackets + right_brackets,
+			items,
+			key,
+		})
+	}
+}
+
+// The value a right operand needs so that the operation on left gives value, among the values
+// the other numbers can make. Every such value when it is not unique, as in 0 × y = 0.
+fn right_operands(operation: &Operation, left: &Rational, value: &Rational, candidates: &HashSet<Rational>) -> Vec<Rational> {
+	let needed = match operation {
+		Operation::Add => value.checked_sub(left),
+		Operation::Sub => left.checked_sub(value),
+		Operation::Mul => value.checked_div(left),
+		Operation::Div => left.checked_div(value),
+	};
+
+	match needed {
+		Some(right) => candidates.get(&right).copied().into_iter().collect(),
+		None => candidates.iter().filter(|r| Rational::apply(operation, left, r).as_ref() == Some(value)).copied().collect(),
+	}
+}
+
+// The ways to split the numbers of a mask in two, each pair once.
+fn splits(mask: usize) -> Vec<(usize, usize)> {
+	let lowest = mask & mask.wrapping_neg();
+	let mut splits = vec![];
+	let mut sub = (mask - 1) & mask;
+
+	while sub != 0 {
+		if sub & lowest != 0 {
+			splits.push((sub, mask ^ sub));
+		}
+		sub = (sub - 1) & mask;
+	}
+
+	splits
+}
+
+// Sets of numbers are bit masks of their indices.
+struct Search {
+	numbers: Vec<i32>,
+	// The values each set of the numbers can make, worked out once from the single numbers up.
+	values: Vec<HashSet<Rational>>,
+	// The formulas for a value from a set of the numbers, once worked out.
+	formulas: HashMap<(usize, Rational), Vec<Part>>,
+	// Values and formulas kept so far, against the limit.
+	kept: usize,
+	limit: usize,
+}
+
+impl Search {
+	fn new(numbers: &[i32], limit: usize) -> Option<Self> {
+		let full = (1 << numbers.len()) - 1;
+		let mut values = vec![HashSet::new(); full];
+		let mut kept = 0;
+
+		// A set splits into smaller masks, so theirs are ready. The full set is only solved for the target.
+		for mask in 1..full {
+			let mut reached = HashSet::new();
+
+			if mask.count_ones() == 1 {
+				reached.insert(Rational::from(numbers[mask.trailing_zeros() as usize]));
+			}
+
+			for (a, b) in splits(mask) {
+				for left in values[a].iter() {
+					for right in values[b].iter() {
+						for operation in [Operation::Add, Operation::Mul, Operation::Sub, Operation::Div] {
+							reached.extend(Rational::apply(&operation, left, right));
+							reached.extend(Rational::apply(&operation, right, left));
+						}
+					}
+				}
+
+				if kept + reached.len() > limit {
+					return None;
+				}
+			}
+
+			kept += reached.len();
+			values[mask] = reached;
+		}
+
+		Some(Search { numbers: numbers.to_vec(), values, formulas: HashMap::new(), kept, limit })
+	}
+
+	// Formulas of the same operands in another order are kept once, with the fewest brackets.
+	// The operation on top is part of the key, as it decides the brackets of a parent.
+	fn solve(&mut self, mask: usize, value: Rational) -> Option<Vec<Part>> {
+		if let Some(parts) = self.formulas.get(&(mask, value)) {
+			return Some(parts.clone());
+		}
+
+		let mut found: BTreeMap<String, Part> = BTreeMap::new();
+
+		if mask.count_ones() == 1 {
+			let number = self.numbers[mask.trailing_zeros() as usize];
+
+			if Rational::from(number) == value {
+				let part = Part::number(number);
+				found.insert(part.key.clone(), part);
+			}
+		}
+
+		for (a, b) in splits(mask) {
+			for operation in [Operation::Add, Operation::Mul, Operation::Sub, Operation::Div] {
+				// + and × once, − and ÷ with either set on the left.
+				let orders = match operation {
+					Operation::Add | Operation::Mul => vec![(a, b)],
+					Operation::Sub | Operation::Div => vec![(a, b), (b, a)],
+				};
+
+				for (l, r) in orders {
+					let lefts: Vec<Rational> = self.values[l].iter().copied().collect();
+
+					for left in lefts {
+						for right in right_operands(&operation, &left, &value, &self.values[r]) {
+							let left_parts = self.solve(l, left)?;
+							let right_parts = self.solve(r, right)?;
+
+							for lp in left_parts.iter() {
+								for rp in right_parts.iter() {
+									let Some(part) = Part::combine(operation.clone(), lp, rp) else {
+										continue;
+									};
+									let key = format!("{:?}{}", operation, part.key);
+
+									if found.get(&key).is_none_or(|p| p.brackets > part.brackets) {
+										found.insert(key, part);
+									}
+								}
+							}
+						}
+					}
+				}
+			}
+		}
+
+		let parts: Vec<Part> = found.into_values().collect();
+
+		self.kept += parts.len();
+		if self.kept > self.limit {
+			return None;
+		}
+
+		self.formulas.insert((mask, value), parts.clone());
+		Some(parts)
+	}
+}
+
+// Every formula that uses each of the numbers once with + − × ÷ and brackets and equals the
+// target, calculated exactly so that 8 / {1 - 1 / 5} counts as 10. Formulas that differ only
+// in the order of what is added and subtracted, or multiplied and divided, are given once. Fewest brackets first.
+// None when there are more than MAX_NUMBERS numbers or the search reaches the limit.
+pub fn solve(numbers: &[i32], target: i32, limit: usize) -> Option<Vec<Expression>> {
+	if numbers.len() > MAX_NUMBERS {
+		return None;
+	}
+
+	if numbers.is_empty() {
+		return Some(vec![]);
+	}
+
+	let mut search = Search::new(numbers, limit)?;
+	let parts = search.solve((1 << numbers.len()) - 1, Rational::from(target))?;
+
+	// The same formula may top out in either of two operations; the one with the fewest brackets.
+	let mut found: BTreeMap<String, Part> = BTreeMap::new();
+	for part in parts {
+		if found.get(&part.key).is_none_or(|p| p.brackets > part.brackets) {
+			found.insert(part.key.clone(), part);
+		}
+	}
+
+	let mut solutions: Vec<Part> = found.into_values().collect();
+	solutions.sort_by_key(|p| p.brackets);
+
+	Some(solutions.into_iter().map(|p| p.expression).collect())
+}
+
+// The solutions written with the fewest brackets.
+pub fn solve_minimal(numbers: &[i32], target: i32, limit: usize) -> Option<Vec<Expression>> {
+	let solutions = solve(numbers, target, limit)?;
+
+	Some(match solutions.first().map(Expression::bracket_count) {
+		Some(fewest) => solutions.into_iter().filter(|s| s.bracket_count() == fewest).collect(),
+		None => solutions,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::expr;
+
+	use super::*;
+
+	#[test]
+	fn make_ten() {
+		let act = solve(&[1, 1, 5, 8], 10, DEFAULT_SEARCH_LIMIT).unwrap();
+
+		assert_eq!(act.len(), 1);
+		assert!(act[0] == expr!(8 / { 1 - 1 / 5 }));
+	}
+
+	#[test]
+	fn commutativity() {
+		assert_eq!(solve(&[1, 2], 3, DEFAULT_SEARCH_LIMIT).unwrap().len(), 1);
+		assert_eq!(solve(&[2, 2], 4, DEFAULT_SEARCH_LIMIT).unwrap().len(), 2);
+		assert!(solve(&[1, 2], 100, DEFAULT_SEARCH_LIMIT).unwrap().is_empty());
+		assert!(solve(&[], 0, DEFAULT_SEARCH_LIMIT).unwrap().is_empty());
+		assert!(solve(&[7], 7, DEFAULT_SEARCH_LIMIT).unwrap() == vec![num(7)]);
+	}
+
+	#[test]
+	fn exact() {
+		for numbers in [[1, 3, 4, 6], [3, 3, 8, 8]] {
+			let act = solve(&numbers, 24, DEFAULT_SEARCH_LIMIT).unwrap();
+
+			assert!(!act.is_empty());
+			assert!(act.iter().all(|e| Rational::of(e) == Some(Rational::from(24))));
+		}
+	}
+
+	#[test]
+	fn minimal() {
+		assert_eq!(solve(&[2, 3, 4], 14, DEFAULT_SEARCH_LIMIT).unwrap().len(), 2);
+
+		let act = solve_minimal(&[2, 3, 4], 14, DEFAULT_SEARCH_LIMIT).unwrap();
+		assert!(act == vec![expr!(2 + 3 * 4)]);
+
+		// 1 + 2 + 3 + 4 is given once, however the additions are grouped.
+		let act = solve_minimal(&[1, 2, 3, 4], 10, DEFAULT_SEARCH_LIMIT).unwrap();
+		assert_eq!(act.len(), 10);
+		assert!(act.iter().all(|e| e.bracket_count() == 0));
+	}
+
+	#[test]
+	fn six_numbers() {
+		let act = solve_minimal(&[25, 50, 75, 100, 3, 6], 952, DEFAULT_SEARCH_LIMIT).unwrap();
+		assert!(!act.is_empty());
+		assert!(act.iter().all(|e| Rational::of(e) == Some(Rational::from(952))));
+
+		let act = solve_minimal(&[1, 2, 3, 4, 5, 6], 21, DEFAULT_SEARCH_LIMIT).unwrap();
+		assert!(!act.is_empty());
+		assert!(act.iter().all(|e| Rational::of(e) == Some(Rational::from(21))));
+		assert!(act.iter().all(|e| e.bracket_count() == 0));
+	}
+
+	#[test]
+	fn limits() {
+		assert!(solve(&[1, 1, 5, 8], 10, 10).is_none());
+		assert!(solve_minimal(&[1; MAX_NUMBERS + 1], 9, DEFAULT_SEARCH_LIMIT).is_none());
+	}
+}
@@ -0,0 +1,202 @@
+use std::fmt::{Display, Formatter};
+
+use crate::binary_operation::Operation;
+use crate::expression::Expression;
+use crate::number_value::NumberValue;
+
+// An exact fraction, always in lowest terms with a positive denominator.
+// Every operation is checked, None on overflow or division by zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+	numerator: i64,
+	denominator: i64,
+}
+
+// None when the divisor does not fit, as for gcd(i64::MIN, 0).
+pub(crate) fn gcd(a: i64, b: i64) -> Option<i64> {
+	let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+
+	while b != 0 {
+		(a, b) = (b, a % b);
+	}
+
+	i64::try_from(a).ok()
+}
+
+impl Rational {
+	pub fn new(numerator: i64, denominator: i64) -> Option<Self> {
+		if denominator == 0 {
+			return None;
+		}
+
+		let divisor = gcd(numerator, denominator)?;
+		let sign = if denominator < 0 { -1 } else { 1 };
+
+		Some(Rational {
+			numerator: (numerator / divisor).checked_mul(sign)?,
+			denominator: (denominator / divisor).checked_mul(sign)?,
+		})
+	}
+
+	pub fn numerator(&self) -> i64 {
+		self.numerator
+	}
+
+	pub fn denominator(&self) -> i64 {
+		self.denominator
+	}
+
+	pub fn is_integer(&self) -> bool {
+		self.denominator == 1
+	}
+
+	pub fn is_zero(&self) -> bool {
+		self.numerator == 0
+	}
+
+	pub fn checked_add(&self, rhs: &Rational) -> Option<Rational> {
+		let numerator = self
+			.numerator
+			.checked_mul(rhs.denominator)?
+			.checked_add(rhs.numerator.checked_mul(self.denominator)?)?;
+		Rational::new(numerator, self.denominator.checked_mul(rhs.denominator)?)
+	}
+
+	pub fn checked_sub(&self, rhs: &Rational) -> Option<Rational> {
+		self.checked_add(&Rational {
+			numerator: rhs.numerator.checked_neg()?,
+			denominator: rhs.denominator,
+		})
+	}
+
+	pub fn checked_mul(&self, rhs: &Rational) -> Option<Rational> {
+		Rational::new(
+			self.numerator.checked_mul(rhs.numerator)?,
+			self.denominator.checked_mul(rhs.denominator)?,
+		)
+	}
+
+	pub fn checked_div(&self, rhs: &Rational) -> Option<Rational> {
+		Rational::new(
+			self.numerator.checked_mul(rhs.denominator)?,
+			self.denominator.checked_mul(rhs.numerator)?,
+		)
+	}
+
+	pub fn apply(operation: &Operation, left: &Rational, right: &Rational) -> Option<Rational> {
+		match operation {
+			Operation::Add => left.checked_add(right),
+			Operation::Sub => left.checked_sub(right),
+			Operation::Mul => left.checked_mul(right),
+			Operation::Div => left.checked_div(right),
+		}
+	}
+
+	// The value of the formula without truncating any division. None with an unknown in it.
+	pub fn of(expression: &Expression) -> Option<Rational> {
+		// An operation is applied once both its operands are worked out, the left one first.
+		let mut stack = vec![(expression, false)];
+		let mut values = Vec::<Rational>::new();
+
+		while let Some((expr, operands_done)) = stack.pop() {
+			match expr {
+				Expression::Number(num) => values.push(Rational::from(num.number())),
+				Expression::Variable(_) => return None,
+				Expression::Bracket(bracket) => stack.push((bracket.expression(), false)),
+				Expression::BinaryOperation(bin) if !operands_done => {
+					stack.push((expr, true));
+					stack.push((bin.right(), false));
+					stack.push((bin.left(), false));
+				}
+				Expression::BinaryOperation(bin) => {
+					let right = values.pop()?;
+					let left = values.pop()?;
+					values.push(Rational::apply(bin.operation(), &left, &right)?);
+				}
+			}
+		}
+
+		values.pop()
+	}
+}
+
+impl From<i32> for Rational {
+	fn from(value: i32) -> Self {
+		Rational {
+			numerator: i64::from(value),
+			denominator: 1,
+		}
+	}
+}
+
+impl From<&NumberValue> for Rational {
+	fn from(value: &NumberValue) -> Self {
+		let NumberValue::Integer(i) = value;
+		Rational::from(*i)
+	}
+}
+
+impl Display for Rational {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		if self.is_integer() {
+			write!(f, "{}", self.numerator)
+		} else {
+			write!(f, "{}/{}", self.numerator, self.denominator)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::builder::{bracket, num, var};
+	use crate::expr;
+
+	use super::*;
+
+	#[test]
+	fn normalize() {
+		assert_eq!(Rational::new(4, -6).unwrap(), Rational::new(-2, 3).unwrap());
+		assert_eq!(Rational::new(0, -5).unwrap(), Rational::from(0));
+		assert!(Rational::new(1, 0).is_none());
+		assert_eq!(Rational::new(6, 3).unwrap().to_string(), "2");
+		assert_eq!(Rational::new(-3, 12).unwrap().to_string(), "-1/4");
+
+		// −i64::MIN does not fit, neither as the divisor nor with the sign moved up.
+		assert_eq!(Rational::new(i64::MIN, 2).unwrap().numerator(), i64::MIN / 2);
+		assert!(Rational::new(i64::MIN, -1).is_none());
+		assert!(Rational::new(i64::MIN, i64::MIN).is_none());
+		assert!(Rational::new(0, i64::MIN).is_none());
+	}
+
+	#[test]
+	fn arithmetic() {
+		let third = Rational::new(1, 3).unwrap();
+		let quarter = Rational::new(1, 4).unwrap();
+
+		assert_eq!(third.checked_add(&quarter).unwrap(), Rational::new(7, 12).unwrap());
+		assert_eq!(third.checked_sub(&quarter).unwrap(), Rational::new(1, 12).unwrap());
+		assert_eq!(third.checked_mul(&quarter).unwrap(), Rational::new(1, 12).unwrap());
+		assert_eq!(third.checked_div(&quarter).unwrap(), Rational::new(4, 3).unwrap());
+		assert!(third.checked_div(&Rational::from(0)).is_none());
+		assert!(Rational::from(i32::MAX).checked_mul(&Rational::new(i64::MAX, 1).unwrap()).is_none());
+	}
+
+	#[test]
+	fn of() {
+		assert_eq!(Rational::of(&expr!(8 / { 1 - 1 / 5 })).unwrap(), Rational::from(10));
+		assert_eq!(Rational::of(&expr!(7 / 2)).unwrap(), Rational::new(7, 2).unwrap());
+		assert!(Rational::of(&expr!(1 / { 2 - 2 })).is_none());
+	}
+
+	#[test]
+	fn deep_tree() {
+		let mut fixture = num(0);
+
+		for _ in 0..100_000 {
+			fixture = bracket(fixture + num(1));
+		}
+
+		assert_eq!(Rational::of(&fixture).unwrap(), Rational::from(100_000));
+		assert!(Rational::of(&bracket(fixture - var('x'))).is_none());
+	}
+}
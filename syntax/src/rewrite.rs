@@ -0,0 +1,374 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::expression::Expression;
+use crate::node_path::{Direction, NodePath};
+use crate::pattern::Pattern;
+
+pub const DEFAULT_STEP_LIMIT: usize = 1000;
+
+pub enum RuleError {
+	UnboundVariable(String),
+	WildcardInReplacement,
+}
+
+impl Clone for RuleError {
+	fn clone(&self) -> Self {
+		match self {
+			RuleError::UnboundVariable(name) => RuleError::UnboundVariable(name.clone()),
+			RuleError::WildcardInReplacement => RuleError::WildcardInReplacement,
+		}
+	}
+}
+
+impl Debug for RuleError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RuleError::UnboundVariable(name) => write!(f, "UnboundVariable:{}", name),
+			RuleError::WildcardInReplacement => write!(f, "WildcardInReplacement"),
+		}
+	}
+}
+
+impl Display for RuleError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RuleError::UnboundVariable(name) => {
+				write!(f, "{} is not bound by the left hand side.", name)
+			}
+			RuleError::WildcardInReplacement => {
+				write!(f, "_ is not allowed in the right hand side.")
+			}
+		}
+	}
+}
+
+impl Error for RuleError {}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+	lhs: Pattern,
+	rhs: Pattern,
+}
+
+impl Display for Rule {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} => {}", self.lhs, self.rhs)
+	}
+}
+
+impl Rule {
+	pub fn new(lhs: Pattern, rhs: Pattern) -> Result<Self, RuleError> {
+		if rhs.has_wildcard() {
+			return Err(RuleError::WildcardInReplacement);
+		}
+
+		let bound = lhs.variables();
+
+		for name in rhs.variables() {
+			if !bound.contains(&name) {
+				return Err(RuleError::UnboundVariable(name.to_string()));
+			}
+		}
+
+		Ok(Rule { lhs, rhs })
+	}
+
+	pub fn lhs(&self) -> &Pattern {
+		&self.lhs
+	}
+
+	pub fn rhs(&self) -> &Pattern {
+		&self.rhs
+	}
+
+	pub fn apply_root(&self, expression: &Expression) -> Option<Expression> {
+		let bindings = self.lhs.match_root(expression)?;
+		self.rhs.substitute(&bindings)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewriteStrategy {
+	// A single rewrite at the outermost-leftmost position where any rule applies.
+	Once,
+	// One bottom-up sweep, every node rewritten at most once.
+	InnermostFirst,
+	// Sweeps repeated until no rule applies, at most the given number of rewrites.
+	Fixpoint(usize),
+}
+
+pub struct RewriteStep {
+	rule: usize,
+	path: NodePath,
+	before: Expression,
+	after: Expression,
+}
+
+impl RewriteStep {
+	pub fn rule(&self) -> usize {
+		self.rule
+	}
+
+	pub fn path(&self) -> &NodePath {
+		&self.path
+	}
+
+	pub fn before(&self) -> &Expression {
+		&self.before
+	}
+
+	pub fn after(&self) -> &Expression {
+		&self.after
+	}
+}
+
+// Right before left, so that the left child is popped first.
+fn children(expression: &Expression, path: &NodePath) -> Vec<NodePath> {
+	match path.resolve(expression) {
+		Some(Expression::Bracket(_)) => vec![path.child(Direction::Inner)],
+		Some(Expression::BinaryOperation(_)) => {
+			vec![path.child(Direction::Right), path.child(Direction::Left)]
+		}
+		_ => vec![],
+	}
+}
+
+fn post_order(expression: &Expression) -> Vec<NodePath> {
+	let mut vec = Vec::<NodePath>::new();
+	let mut stack = vec![(NodePath::root(), false)];
+
+	while let Some((path, visited)) = stack.pop() {
+		if visited {
+			vec.push(path);
+		} else {
+			let c = children(expression, &path);
+			stack.push((path, true));
+			stack.extend(c.into_iter().map(|p| (p, false)));
+		}
+	}
+
+	vec
+}
+
+fn pre_order(expression: &Expression) -> Vec<NodePath> {
+	let mut vec = Vec::<NodePath>::new();
+	let mut stack = vec![NodePath::root()];
+
+	while let Some(path) = stack.pop() {
+		stack.extend(children(expression, &path));
+		vec.push(path);
+	}
+
+	vec
+}
+
+fn try_rules(rules: &[Rule], expression: &Expression, path: NodePath) -> Option<RewriteStep> {
+	let target = path.resolve(expression)?;
+
+	for (idx, rule) in rules.iter().enumerate() {
+		if let Some(replacement) = rule.apply_root(target) {
+			let after = path.replace(expression, replacement)?;
+
+			return Some(RewriteStep {
+				rule: idx,
+				path,
+				before: expression.clone(),
+				after,
+			});
+		}
+	}
+
+	None
+}
+
+// Rewriting a node never moves the nodes that follow it in post-order, so the
+// paths collected up front stay valid for the whole sweep.
+fn sweep(rules: &[Rule], expression: &Expression, limit: usize, steps: &mut Vec<RewriteStep>) -> Expression {
+	let mut current = expression.clone();
+
+	for path in post_order(expression) {
+		if steps.len() >= limit {
+			break;
+		}
+
+		if let Some(step) = try_rules(rules, &current, path) {
+			current = step.after.clone();
+			steps.push(step);
+		}
+	}
+
+	current
+}
+
+// Returns the rewritten expression and every rule firing in order.
+// A fixpoint run that stops at its limit yields exactly that many steps.
+pub fn rewrite(
+	expression: &Expression,
+	rules: &[Rule],
+	strategy: RewriteStrategy,
+) -> (Expression, Vec<RewriteStep>) {
+	let mut steps = Vec::<RewriteStep>::new();
+
+	let result = match strategy {
+		RewriteStrategy::Once => {
+			let step = pre_order(expression)
+				.into_iter()
+				.find_map(|path| try_rules(rules, expression, path));
+
+			match step {
+				Some(step) => {
+					let after = step.after.clone();
+					steps.push(step);
+					after
+				}
+				None => expression.clone(),
+			}
+		}
+		RewriteStrategy::InnermostFirst => sweep(rules, expression, usize::MAX, &mut steps),
+		RewriteStrategy::Fixpoint(limit) => {
+			let mut current = expression.clone();
+
+			loop {
+				let before = steps.len();
+				current = sweep(rules, &current, limit, &mut steps);
+
+				if steps.len() == before || steps.len() >= limit {
+					break;
+				}
+			}
+
+			current
+		}
+	};
+
+	(result, steps)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::binary_operation::Operation;
+	use crate::builder::{bracket, num};
+
+	use super::*;
+
+	fn var(name: &str) -> Pattern {
+		Pattern::variable(name)
+	}
+
+	// x * 1 => x
+	fn unit() -> Rule {
+		Rule::new(
+			Pattern::binary_operation(var("x"), Pattern::number(1), Operation::Mul),
+			var("x"),
+		)
+		.unwrap()
+	}
+
+	// a*c + b*c => {a+b}*c
+	fn factor_out() -> Rule {
+		Rule::new(
+			Pattern::binary_operation(
+				Pattern::binary_operation(var("a"), var("c"), Operation::Mul),
+				Pattern::binary_operation(var("b"), var("c"), Operation::Mul),
+				Operation::Add,
+			),
+			Pattern::binary_operation(
+				Pattern::bracket(Pattern::binary_operation(var("a"), var("b"), Operation::Add)),
+				var("c"),
+				Operation::Mul,
+			),
+		)
+		.unwrap()
+	}
+
+	// a + b => b + a
+	fn commute() -> Rule {
+		Rule::new(
+			Pattern::binary_operation(var("a"), var("b"), Operation::Add),
+			Pattern::binary_operation(var("b"), var("a"), Operation::Add),
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn validation() {
+		let act = Rule::new(var("x"), var("y"));
+		assert!(matches!(act, Err(RuleError::UnboundVariable(name)) if name == "y"));
+
+		let act = Rule::new(Pattern::Wildcard, Pattern::Wildcard);
+		assert!(matches!(act, Err(RuleError::WildcardInReplacement)));
+
+		let act = Rule::new(
+			Pattern::binary_operation(Pattern::Wildcard, Pattern::number(0), Operation::Mul),
+			Pattern::number(0),
+		);
+		assert!(act.is_ok());
+
+		assert_eq!(factor_out().to_string(), "a * c + b * c => {a + b} * c");
+	}
+
+	#[test]
+	fn apply_root() {
+		let act = factor_out().apply_root(&(num(3) * num(7) + num(4) * num(7))).unwrap();
+		assert!(act == bracket(num(3) + num(4)) * num(7));
+
+		assert!(factor_out().apply_root(&(num(3) * num(7) + num(4) * num(8))).is_none());
+	}
+
+	#[test]
+	fn once() {
+		let expr = (num(2) * num(1)) * num(1);
+		let (act, steps) = rewrite(&expr, &[unit()], RewriteStrategy::Once);
+
+		assert!(act == num(2) * num(1));
+		assert_eq!(steps.len(), 1);
+		assert!(steps[0].path().is_root());
+		assert_eq!(steps[0].rule(), 0);
+		assert!(steps[0].before() == &expr);
+		assert!(steps[0].after() == &act);
+
+		let (act, steps) = rewrite(&num(3), &[unit()], RewriteStrategy::Once);
+		assert!(act == num(3));
+		assert!(steps.is_empty());
+	}
+
+	#[test]
+	fn innermost_first() {
+		let expr = bracket(num(2) * num(1)) * num(1) + num(5) * num(1);
+		let (act, steps) = rewrite(&expr, &[unit()], RewriteStrategy::InnermostFirst);
+
+		assert!(act == bracket(num(2)) + num(5));
+		let paths: Vec<String> = steps.iter().map(|s| s.path().to_string()).collect();
+		assert_eq!(paths, vec!["/L/L/I", "/L", "/R"]);
+
+		for pair in steps.windows(2) {
+			assert!(pair[0].after() == pair[1].before());
+		}
+	}
+
+	#[test]
+	fn fixpoint() {
+		let expr = (num(2) * num(7) + num(3) * num(7)) * num(1);
+		let rules = [unit(), factor_out()];
+
+		let (act, steps) = rewrite(&expr, &rules, RewriteStrategy::InnermostFirst);
+		assert!(act == bracket(num(2) + num(3)) * num(7));
+		let rules: Vec<usize> = steps.iter().map(|s| s.rule()).collect();
+		assert_eq!(rules, vec![1, 0]);
+
+		let expr = bracket(num(4) * num(1)) * num(1);
+		let (act, steps) = rewrite(&expr, &[unit()], RewriteStrategy::Fixpoint(DEFAULT_STEP_LIMIT));
+		assert!(act == bracket(num(4)));
+		assert_eq!(steps.len(), 2);
+	}
+
+	#[test]
+	fn step_limit() {
+		let expr = num(1) + num(2);
+		let (act, steps) = rewrite(&expr, &[commute()], RewriteStrategy::Fixpoint(5));
+
+		assert_eq!(steps.len(), 5);
+		assert!(act == num(2) + num(1));
+	}
+}
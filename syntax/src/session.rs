@@ -0,0 +1,270 @@
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::expression::Expression;
+use crate::node_path::NodePath;
+
+// A state that can be stepped forward, either by its own default rule or by a choice.
+pub trait SessionState: Clone {
+	type Choice: Clone + PartialEq;
+
+	fn advance(&self) -> Option<Self>;
+
+	fn advance_with(&self, choice: &Self::Choice) -> Option<Self>;
+}
+
+impl SessionState for Expression {
+	// The redex to reduce.
+	type Choice = NodePath;
+
+	fn advance(&self) -> Option<Self> {
+		match self.step_calc() {
+			(next, true) => Some(next),
+			_ => None,
+		}
+	}
+
+	fn advance_with(&self, choice: &Self::Choice) -> Option<Self> {
+		self.reduce_at(choice)
+	}
+}
+
+struct SessionNode<S: SessionState> {
+	state: S,
+	parent: Option<usize>,
+	// None when reached by the default rule.
+	choice: Option<S::Choice>,
+	children: Vec<usize>,
+}
+
+// The history of an evaluation as a tree. The line is the branch being looked at, from the
+// initial state to the newest step on it, and the position is the step shown in that line.
+// Every state is computed once; moving back and forth or returning to a branch reuses it.
+pub struct StepSession<S: SessionState> {
+	nodes: Vec<SessionNode<S>>,
+	line: Vec<usize>,
+	position: usize,
+}
+
+impl<S: SessionState> StepSession<S> {
+	pub fn new(initial: S) -> Self {
+		StepSession {
+			nodes: vec![SessionNode {
+				state: initial,
+				parent: None,
+				choice: None,
+				children: vec![],
+			}],
+			line: vec![0],
+			position: 0,
+		}
+	}
+
+	pub fn current(&self) -> &S {
+		&self.nodes[self.line[self.position]].state
+	}
+
+	pub fn position(&self) -> usize {
+		self.position
+	}
+
+	// Number of states in the line, the initial one included.
+	pub fn len(&self) -> usize {
+		self.line.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.line.is_empty()
+	}
+
+	// Number of states ever computed, over every branch.
+	pub fn node_count(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn history(&self) -> impl Iterator<Item = &S> {
+		self.line.iter().map(|id| &self.nodes[*id].state)
+	}
+
+	// Choices already taken from the current state, the default one as None.
+	pub fn branches(&self) -> impl Iterator<Item = Option<&S::Choice>> {
+		let node = &self.nodes[self.line[self.position]];
+		node.children.iter().map(|id| self.nodes[*id].choice.as_ref())
+	}
+
+	fn child(&self, choice: Option<&S::Choice>) -> Option<usize> {
+		self.nodes[self.line[self.position]]
+			.children
+			.iter()
+			.copied()
+			.find(|id| self.nodes[*id].choice.as_ref() == choice)
+	}
+
+	fn attach(&mut self, state: S, choice: Option<S::Choice>) -> usize {
+		let parent = self.line[self.position];
+		let id = self.nodes.len();
+
+		self.nodes.push(SessionNode {
+			state,
+			parent: Some(parent),
+			choice,
+			children: vec![],
+		});
+		self.nodes[parent].children.push(id);
+
+		id
+	}
+
+	// Replaces the rest of the line with the given child.
+	fn enter(&mut self, id: usize) {
+		self.line.truncate(self.position + 1);
+		self.line.push(id);
+		self.position += 1;
+	}
+
+	// Moves one step along the line, computing the step by the default rule at its end.
+	// None when the evaluation is finished.
+	pub fn forward(&mut self) -> Option<&S> {
+		if self.position + 1 < self.line.len() {
+			self.position += 1;
+			return Some(self.current());
+		}
+
+		let id = match self.child(None) {
+			Some(id) => id,
+			None => {
+				let next = self.current().advance()?;
+				self.attach(next, None)
+			}
+		};
+
+		self.enter(id);
+		Some(self.current())
+	}
+
+	pub fn back(&mut self) -> Option<&S> {
+		if self.position == 0 {
+			return None;
+		}
+
+		self.position -= 1;
+		Some(self.current())
+	}
+
+	// Moves to the n-th state of the line, stepping forward past its end if needed.
+	// None when the evaluation finishes before it, leaving the position at the last state.
+	pub fn goto(&mut self, n: usize) -> Option<&S> {
+		if n < self.line.len() {
+			self.position = n;
+			return Some(self.current());
+		}
+
+		self.position = self.line.len() - 1;
+
+		while self.position < n {
+			self.forward()?;
+		}
+
+		Some(self.current())
+	}
+
+	// Back to the initial state. The history is kept, so forward replays it.
+	pub fn reset(&mut self) -> &S {
+		self.position = 0;
+		self.current()
+	}
+
+	// Steps by the given choice. Unless the choice was taken before, this starts a new branch
+	// and the rest of the line is replaced; the old branch stays in the tree.
+	pub fn choose(&mut self, choice: S::Choice) -> Option<&S> {
+		let id = match self.child(Some(&choice)) {
+			Some(id) => id,
+			None => {
+				let next = self.current().advance_with(&choice)?;
+				self.attach(next, Some(choice))
+			}
+		};
+
+		self.enter(id);
+		Some(self.current())
+	}
+
+	// The state the current one was stepped from, whichever branch it is on.
+	pub fn parent_of_current(&self) -> Option<&S> {
+		let parent = self.nodes[self.line[self.position]].parent?;
+		Some(&self.nodes[parent].state)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::builder::num;
+	use crate::expr;
+	use crate::node_path::Direction;
+
+	use super::*;
+
+	fn fixture() -> StepSession<Expression> {
+		StepSession::new(expr!(1 + 2 * 3 - 4 / 2))
+	}
+
+	#[test]
+	fn forward_back() {
+		let mut session = fixture();
+
+		assert!(session.forward().unwrap() == &(num(1) + num(6) - num(4) / num(2)));
+		assert!(session.forward().unwrap() == &(num(7) - num(4) / num(2)));
+		assert_eq!(session.position(), 2);
+
+		assert!(session.back().unwrap() == &(num(1) + num(6) - num(4) / num(2)));
+		assert!(session.forward().unwrap() == &(num(7) - num(4) / num(2)));
+		assert_eq!(session.node_count(), 3);
+
+		while session.forward().is_some() {}
+		assert!(session.current() == &num(5));
+		assert_eq!(session.len(), 5);
+
+		assert!(session.reset() == &expr!(1 + 2 * 3 - 4 / 2));
+		assert!(session.back().is_none());
+		assert_eq!(session.len(), 5);
+	}
+
+	#[test]
+	fn goto() {
+		let mut session = fixture();
+
+		assert!(session.goto(3).unwrap() == &(num(7) - num(2)));
+		assert!(session.goto(1).unwrap() == &(num(1) + num(6) - num(4) / num(2)));
+		assert_eq!(session.node_count(), 4);
+
+		assert!(session.goto(10).is_none());
+		assert_eq!(session.position(), 4);
+		assert!(session.current() == &num(5));
+	}
+
+	#[test]
+	fn branch() {
+		let mut session = fixture();
+		session.goto(2);
+		session.back();
+
+		// 4 / 2 before 1 + 6.
+		let path = NodePath::from(vec![Direction::Right]);
+		assert!(session.choose(path.clone()).unwrap() == &(num(1) + num(6) - num(2)));
+		assert_eq!(session.len(), 3);
+		assert!(session.parent_of_current().unwrap() == &(num(1) + num(6) - num(4) / num(2)));
+
+		session.back();
+		assert_eq!(session.branches().count(), 2);
+		assert!(session.branches().any(|c| c == Some(&path)));
+
+		// Forward follows the line, taking the same choice again reuses its state.
+		let count = session.node_count();
+		assert!(session.forward().unwrap() == &(num(1) + num(6) - num(2)));
+		session.back();
+		assert!(session.choose(path).unwrap() == &(num(1) + num(6) - num(2)));
+		assert_eq!(session.node_count(), count);
+
+		session.back();
+		assert!(session.choose(NodePath::root()).is_none());
+		assert_eq!(session.position(), 1);
+	}
+}
@@ -0,0 +1,344 @@
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::io::prelude::*;
+
+use crate::expression::Expression;
+use crate::node_path::{Direction, NodePath};
+use crate::number_value::NumberValue;
+use crate::step::Step;
+
+pub const DEFAULT_STATE_LIMIT: usize = 10_000;
+
+pub struct StateEdge {
+	from: usize,
+	to: usize,
+	path: NodePath,
+}
+
+impl StateEdge {
+	pub fn from(&self) -> usize {
+		self.from
+	}
+
+	pub fn to(&self) -> usize {
+		self.to
+	}
+
+	pub fn path(&self) -> &NodePath {
+		&self.path
+	}
+}
+
+// Every state reachable by single-redex reductions, identical states merged.
+// State 0 is the initial expression.
+pub struct StateSpace {
+	states: Vec<Expression>,
+	edges: Vec<StateEdge>,
+	// The indices of the edges out of each state.
+	successors: Vec<Vec<usize>>,
+	complete: bool,
+}
+
+// Identical trees give identical keys.
+fn state_key(expression: &Expression) -> String {
+	let mut key = String::default();
+	let mut stack = vec![expression];
+
+	while let Some(expr) = stack.pop() {
+		match expr {
+			Expression::Number(num) => {
+				let NumberValue::Integer(i) = num.number();
+				key.push_str(&format!("{};", i));
+			}
+			Expression::Variable(var) => key.push_str(&format!("{};", var.name())),
+			Expression::Bracket(bracket) => {
+				key.push('{');
+				stack.push(bracket.expression());
+			}
+			Expression::BinaryOperation(bin) => {
+				key.push_str(&format!("{:?};", bin.operation()));
+				stack.push(bin.right());
+				stack.push(bin.left());
+			}
+		}
+	}
+
+	key
+}
+
+fn redexes(expression: &Expression) -> Vec<NodePath> {
+	let mut vec = Vec::<NodePath>::new();
+	let mut stack = vec![(expression, NodePath::root())];
+
+	while let Some((expr, path)) = stack.pop() {
+		match expr {
+			Expression::Number(_) | Expression::Variable(_) => {}
+			Expression::Bracket(bracket) => {
+				if bracket.expression().is_number() {
+					vec.push(path);
+				} else {
+					stack.push((bracket.expression(), path.child(Direction::Inner)));
+				}
+			}
+			Expression::BinaryOperation(bin) => {
+				if bin.left().is_number() && bin.right().is_number() {
+					vec.push(path);
+				} else {
+					stack.push((bin.right(), path.child(Direction::Right)));
+					stack.push((bin.left(), path.child(Direction::Left)));
+				}
+			}
+		}
+	}
+
+	vec
+}
+
+fn size(expression: &Expression) -> usize {
+	let mut count = 0;
+	let mut stack = vec![expression];
+
+	while let Some(expr) = stack.pop() {
+		count += 1;
+
+		match expr {
+			Expression::Number(_) | Expression::Variable(_) => {}
+			Expression::Bracket(bracket) => stack.push(bracket.expression()),
+			Expression::BinaryOperation(bin) => {
+				stack.push(bin.left());
+				stack.push(bin.right());
+			}
+		}
+	}
+
+	count
+}
+
+fn escape_json(scr: &str) -> String {
+	let mut buff = String::default();
+
+	for c in scr.chars() {
+		match c {
+			'"' => buff.push_str("\\\""),
+			'\\' => buff.push_str("\\\\"),
+			'\n' => buff.push_str("\\n"),
+			'\r' => buff.push_str("\\r"),
+			'\t' => buff.push_str("\\t"),
+			c if (c as u32) < 0x20 => buff.push_str(&format!("\\u{:04x}", c as u32)),
+			c => buff.push(c),
+		}
+	}
+
+	buff
+}
+
+impl StateSpace {
+	// Breadth first from the given expression. Stops adding states at the limit,
+	// which is_complete then reports.
+	pub fn explore(expression: &Expression, limit: usize) -> Self {
+		let mut space = StateSpace {
+			states: vec![expression.clone()],
+			edges: vec![],
+			successors: vec![vec![]],
+			complete: true,
+		};
+
+		let mut ids = HashMap::<String, usize>::new();
+		ids.insert(state_key(expression), 0);
+
+		let mut queue = VecDeque::from([0usize]);
+
+		while let Some(from) = queue.pop_front() {
+			for path in redexes(&space.states[from]) {
+				let next = match Step::reduce(&space.states[from], path.clone()) {
+					Ok(Some(step)) => step.into_after(),
+					_ => continue,
+				};
+
+				let key = state_key(&next);
+				let to = match ids.get(&key) {
+					Some(id) => *id,
+					None => {
+						if space.states.len() >= limit {
+							space.complete = false;
+							continue;
+						}
+
+						let id = space.states.len();
+						ids.insert(key, id);
+						space.states.push(next);
+						space.successors.push(vec![]);
+						queue.push_back(id);
+						id
+					}
+				};
+
+				space.successors[from].push(space.edges.len());
+				space.edges.push(StateEdge { from, to, path });
+			}
+		}
+
+		space
+	}
+
+	pub fn states(&self) -> &[Expression] {
+		&self.states
+	}
+
+	pub fn edges(&self) -> &[StateEdge] {
+		&self.edges
+	}
+
+	pub fn is_complete(&self) -> bool {
+		self.complete
+	}
+
+	// States nothing can be reduced from: the value, or an expression stuck on an error.
+	pub fn terminals(&self) -> Vec<usize> {
+		(0..self.states.len()).filter(|id| self.successors[*id].is_empty()).collect()
+	}
+
+	// Whether every order ends in the same state.
+	pub fn is_confluent(&self) -> bool {
+		self.terminals().len() == 1
+	}
+
+	// Number of distinct reduction orders from the start to a terminal state.
+	pub fn order_count(&self) -> usize {
+		// Every reduction shrinks the tree, so smaller states are counted first.
+		let mut order: Vec<usize> = (0..self.states.len()).collect();
+		order.sort_by_key(|id| size(&self.states[*id]));
+
+		let mut counts = vec![0usize; self.states.len()];
+
+		for id in order {
+			let successors = &self.successors[id];
+
+			counts[id] = match successors.is_empty() {
+				true => 1,
+				false => successors.iter().fold(0usize, |count, e| count.saturating_add(counts[self.edges[*e].to])),
+			};
+		}
+
+		counts[0]
+	}
+
+	pub fn write_json<W: Write>(&self, writer: &mut W, label: impl Fn(&Expression) -> String) -> io::Result<()> {
+		writeln!(writer, "{{")?;
+		writeln!(writer, "  \"states\": [")?;
+
+		for (id, state) in self.states.iter().enumerate() {
+			let separator = if id + 1 < self.states.len() { "," } else { "" };
+			writeln!(
+				writer,
+				"    {{\"id\": {}, \"label\": \"{}\", \"terminal\": {}}}{}",
+				id,
+				escape_json(&label(state)),
+				self.successors[id].is_empty(),
+				separator
+			)?;
+		}
+
+		writeln!(writer, "  ],")?;
+		writeln!(writer, "  \"edges\": [")?;
+
+		for (idx, edge) in self.edges.iter().enumerate() {
+			let separator = if idx + 1 < self.edges.len() { "," } else { "" };
+			writeln!(
+				writer,
+				"    {{\"from\": {}, \"to\": {}, \"path\": \"{}\"}}{}",
+				edge.from, edge.to, edge.path, separator
+			)?;
+		}
+
+		writeln!(writer, "  ],")?;
+		writeln!(writer, "  \"orders\": {},", self.order_count())?;
+		writeln!(writer, "  \"complete\": {}", self.complete)?;
+		writeln!(writer, "}}")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use crate::builder::num;
+	use crate::expr;
+	use crate::step_choice::step_at;
+
+	use super::*;
+
+	#[test]
+	fn explore() {
+		let act = StateSpace::explore(&expr!(1 * 2 + 3 * 4), DEFAULT_STATE_LIMIT);
+
+		// 1*2+3*4, 2+3*4, 1*2+12, 2+12, 14
+		assert_eq!(act.states().len(), 5);
+		assert_eq!(act.edges().len(), 5);
+		assert_eq!(act.order_count(), 2);
+		assert!(act.is_confluent());
+		assert!(act.is_complete());
+		assert!(act.states()[act.terminals()[0]] == num(14));
+	}
+
+	#[test]
+	fn school_order() {
+		let fixture = expr!(1 + 2 - 3 * 4);
+
+		let act = StateSpace::explore(&fixture, DEFAULT_STATE_LIMIT);
+		assert_eq!(act.order_count(), 2);
+		assert!(act.states()[act.terminals()[0]] == num(-9));
+
+		// Every reduction of the space is one step_at accepts.
+		for edge in act.edges() {
+			let next = step_at(&act.states()[edge.from()], edge.path()).unwrap();
+			assert!(next == act.states()[edge.to()]);
+		}
+	}
+
+	#[test]
+	fn merged() {
+		let act = StateSpace::explore(&expr!({ 1 + 2 } * { 3 + 4 }), DEFAULT_STATE_LIMIT);
+
+		// Each side takes two reductions, and the four can interleave in six ways.
+		assert_eq!(act.states().len(), 10);
+		assert_eq!(act.order_count(), 6);
+		assert!(act.is_confluent());
+
+		let act = StateSpace::explore(&expr!({ 1 + 2 } * { 3 + 4 }), 4);
+		assert_eq!(act.states().len(), 4);
+		assert!(!act.is_complete());
+	}
+
+	#[test]
+	fn stuck() {
+		let act = StateSpace::explore(&expr!(1 / 0 + 2 * 3), DEFAULT_STATE_LIMIT);
+
+		assert_eq!(act.states().len(), 2);
+		assert_eq!(act.terminals(), vec![1]);
+		assert!(!act.states()[1].is_number());
+	}
+
+	#[test]
+	fn json() {
+		let act = StateSpace::explore(&expr!(1 + 2), DEFAULT_STATE_LIMIT);
+		let mut cursor = Cursor::<Vec<u8>>::default();
+		act.write_json(&mut cursor, |e| format!("\"{}\"", e.depth())).unwrap();
+
+		assert_eq!(
+			String::from_utf8(cursor.into_inner()).unwrap(),
+			r#"{
+  "states": [
+    {"id": 0, "label": "\"2\"", "terminal": false},
+    {"id": 1, "label": "\"1\"", "terminal": true}
+  ],
+  "edges": [
+    {"from": 0, "to": 1, "path": "/"}
+  ],
+  "orders": 1,
+  "complete": true
+}
+"#
+		);
+	}
+}
@@ -0,0 +1,665 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::Index;
+use std::slice::Iter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::binary_operation::Operation;
+use crate::expression::Expression;
+use crate::fraction::fraction_view;
+use crate::node_path::NodePath;
+use crate::number_value::{ArithmeticError, NumberValue};
+use crate::step_strategy::StepStrategy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+	BracketRemoved,
+	OperationEvaluated,
+	// With fraction division. The fractions are rewritten over their least common multiple.
+	CommonDenominator(i32),
+	NumeratorsCombined,
+	FractionsMultiplied,
+	ReciprocalTaken,
+	// Numerator and denominator divided by their gcd, 1 for a negative or 1 denominator.
+	FractionReduced(i32),
+}
+
+// A reduction that could not be carried out. It always ends the stepping.
+#[derive(Clone)]
+pub struct StepFailure {
+	expression: Expression,
+	path: NodePath,
+	operation: Operation,
+	operands: Vec<NumberValue>,
+	error: ArithmeticError,
+}
+
+impl StepFailure {
+	pub fn new(
+		expression: Expression,
+		path: NodePath,
+		operation: Operation,
+		operands: Vec<NumberValue>,
+		error: ArithmeticError,
+	) -> Self {
+		StepFailure {
+			expression,
+			path,
+			operation,
+			operands,
+			error,
+		}
+	}
+
+	pub fn expression(&self) -> &Expression {
+		&self.expression
+	}
+
+	pub fn path(&self) -> &NodePath {
+		&self.path
+	}
+
+	pub fn operation(&self) -> &Operation {
+		&self.operation
+	}
+
+	pub fn operands(&self) -> &[NumberValue] {
+		&self.operands
+	}
+
+	pub fn error(&self) -> &ArithmeticError {
+		&self.error
+	}
+}
+
+impl Debug for StepFailure {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "path:{} operation:{:?} error:{:?}", self.path, self.operation, self.error)
+	}
+}
+
+impl Display for StepFailure {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		if self.error.is_division_by_zero() {
+			write!(f, "cannot divide by zero here")
+		} else {
+			write!(f, "cannot calculate here: {}", self.error.message())
+		}
+	}
+}
+
+impl std::error::Error for StepFailure {}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BracketRemoval {
+	// Dropping a bracket around a number is a step of its own.
+	#[default]
+	Separate,
+	// The step that reduces the content to a number drops the bracket as well.
+	WithReduction,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Division {
+	#[default]
+	Integer,
+	// Quotients are kept as fractions, put over a common denominator (通分) before they
+	// are added and reduced (約分) once they are worked out.
+	Fraction,
+}
+
+#[derive(Clone)]
+pub struct Step {
+	before: Expression,
+	after: Expression,
+	path: NodePath,
+	kind: StepKind,
+	operation: Option<Operation>,
+	operands: Vec<NumberValue>,
+	result: Option<NumberValue>,
+}
+
+impl Step {
+	pub(crate) fn new(
+		before: Expression,
+		after: Expression,
+		path: NodePath,
+		kind: StepKind,
+		operation: Option<Operation>,
+		operands: Vec<NumberValue>,
+		result: Option<NumberValue>,
+	) -> Self {
+		Step {
+			before,
+			after,
+			path,
+			kind,
+			operation,
+			operands,
+			result,
+		}
+	}
+
+	// Reduces the redex at the path, a bracket around a number or an operation over two numbers.
+	// Ok(None) when there is no redex at the path.
+	pub fn reduce(expression: &Expression, path: NodePath) -> Result<Option<Step>, StepFailure> {
+		let Some(target) = path.resolve(expression) else {
+			return Ok(None);
+		};
+		
+		let (kind, operation, operands, result) = match target {
+			Expression::Bracket(bracket) => {
+				let result = match bracket.expression() {
+					Expression::Number(num) => Some(num.number().clone()),
+					_ => None,
+				};
+
+				(StepKind::BracketRemoved, None, vec![], result)
+			}
+			Expression::BinaryOperation(bin) => match (bin.left(), bin.right()) {
+				(Expression::Number(left), Expression::Number(right)) => {
+					let operands = vec![left.number().clone(), right.number().clone()];
+
+					let result = match bin.calc() {
+						Ok(value) => value,
+						Err(error) => {
+							return Err(StepFailure::new(
+								expression.clone(),
+								path,
+								bin.operation().clone(),
+								operands,
+								error,
+							))
+						}
+					};
+
+					(StepKind::OperationEvaluated, Some(bin.operation().clone()), operands, Some(result))
+				}
+				_ => return Ok(None),
+			},
+			Expression::Number(_) | Expression::Variable(_) => return Ok(None),
+		};
+
+		let Some(after) = expression.reduce_at(&path) else {
+			return Ok(None);
+		};
+
+		Ok(Some(Step {
+			before: expression.clone(),
+			after,
+			path,
+			kind,
+			operation,
+			operands,
+			result,
+		}))
+	}
+
+	// Folds the removal of the brackets around a freshly computed number into this step,
+	// every level of {{...}} at once.
+	fn drop_enclosing_bracket(&mut self) {
+		if self.kind == StepKind::BracketRemoved || self.result.is_none() {
+			return;
+		}
+
+		let mut path = self.path.clone();
+
+		while let Some(parent) = path.parent() {
+			if !matches!(parent.resolve(&self.after), Some(Expression::Bracket(_))) {
+				break;
+			}
+
+			match self.after.reduce_at(&parent) {
+				Some(after) => self.after = after,
+				None => break,
+			}
+
+			path = parent;
+		}
+	}
+
+	pub fn before(&self) -> &Expression {
+		&self.before
+	}
+
+	pub fn after(&self) -> &Expression {
+		&self.after
+	}
+
+	pub fn into_after(self) -> Expression {
+		self.after
+	}
+
+	pub fn path(&self) -> &NodePath {
+		&self.path
+	}
+
+	pub fn kind(&self) -> StepKind {
+		self.kind
+	}
+
+	pub fn operation(&self) -> Option<&Operation> {
+		self.operation.as_ref()
+	}
+
+	pub fn operands(&self) -> &[NumberValue] {
+		&self.operands
+	}
+
+	pub fn result(&self) -> Option<&NumberValue> {
+		self.result.as_ref()
+	}
+}
+
+// Step budget and cancellation shared by the stepping iterators.
+#[derive(Clone, Default)]
+pub struct StepControl {
+	budget: Option<usize>,
+	cancel: Option<Arc<AtomicBool>>,
+	taken: usize,
+}
+
+impl StepControl {
+	pub fn set_budget(&mut self, budget: usize) {
+		self.budget = Some(budget)
+	}
+
+	pub fn set_cancel(&mut self, flag: Arc<AtomicBool>) {
+		self.cancel = Some(flag)
+	}
+
+	pub fn taken(&self) -> usize {
+		self.taken
+	}
+
+	// Counts one step, or refuses when the budget is spent or the flag is raised.
+	pub fn proceed(&mut self) -> bool {
+		if self.budget.is_some_and(|b| self.taken >= b) {
+			return false;
+		}
+
+		if self.cancel.as_ref().is_some_and(|c| c.load(Ordering::Relaxed)) {
+			return false;
+		}
+
+		self.taken += 1;
+		true
+	}
+}
+
+// Yields one step per reduction. A failure is yielded once and ends the iteration.
+pub struct Steps<'a> {
+	current: Option<Expression>,
+	strategy: &'a dyn StepStrategy,
+	control: StepControl,
+	removal: BracketRemoval,
+	division: Division,
+}
+
+impl<'a> Steps<'a> {
+	pub fn new(expression: &Expression, strategy: &'a dyn StepStrategy) -> Self {
+		Steps {
+			current: Some(expression.clone()),
+			strategy,
+			control: StepControl::default(),
+			removal: BracketRemoval::default(),
+			division: Division::default(),
+		}
+	}
+
+	pub fn bracket_removal(mut self, removal: BracketRemoval) -> Self {
+		self.removal = removal;
+		self
+	}
+
+	pub fn division(mut self, division: Division) -> Self {
+		self.division = division;
+		self
+	}
+
+	pub fn budget(mut self, budget: usize) -> Self {
+		self.control.set_budget(budget);
+		self
+	}
+
+	pub fn cancel_on(mut self, flag: Arc<AtomicBool>) -> Self {
+		self.control.set_cancel(flag);
+		self
+	}
+
+	// The expression the next step starts from, None once an error ended the iteration.
+	pub fn current(&self) -> Option<&Expression> {
+		self.current.as_ref()
+	}
+}
+
+impl Iterator for Steps<'_> {
+	type Item = Result<Step, StepFailure>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let current = self.current.as_ref()?;
+		let path = match self.division {
+			Division::Integer => self.strategy.select(current)?,
+			Division::Fraction => self.strategy.select(&fraction_view(current))?,
+		};
+
+		if !self.control.proceed() {
+			return None;
+		}
+
+		let reduced = match self.division {
+			Division::Integer => Step::reduce(current, path),
+			Division::Fraction => Step::reduce_fraction(current, path),
+		};
+
+		match reduced {
+			Ok(Some(mut step)) => {
+				if self.removal == BracketRemoval::WithReduction {
+					step.drop_enclosing_bracket();
+				}
+
+				self.current = Some(step.after.clone());
+				Some(Ok(step))
+			}
+			Ok(None) => None,
+			Err(e) => {
+				self.current = None;
+				Some(Err(e))
+			}
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StepSummary {
+	pub total: usize,
+	pub brackets_removed: usize,
+	pub operations_evaluated: usize,
+	pub additions: usize,
+	pub subtractions: usize,
+	pub multiplications: usize,
+	pub divisions: usize,
+	pub max_depth: usize,
+}
+
+#[derive(Clone, Default)]
+pub struct StepTrace {
+	steps: Vec<Step>,
+	failure: Option<StepFailure>,
+}
+
+impl StepTrace {
+	pub fn trace(expression: &Expression, strategy: &dyn StepStrategy) -> Self {
+		let mut trace = StepTrace::default();
+
+		for step in Steps::new(expression, strategy) {
+			match step {
+				Ok(step) => trace.steps.push(step),
+				Err(failure) => trace.failure = Some(failure),
+			}
+		}
+
+		trace
+	}
+
+	// Why the stepping stopped before reaching a number.
+	pub fn failure(&self) -> Option<&StepFailure> {
+		self.failure.as_ref()
+	}
+
+	pub fn len(&self) -> usize {
+		self.steps.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.steps.is_empty()
+	}
+
+	pub fn iter(&self) -> Iter<'_, Step> {
+		self.steps.iter()
+	}
+
+	pub fn push(&mut self, step: Step) {
+		self.steps.push(step)
+	}
+
+	pub fn last(&self) -> Option<&Step> {
+		self.steps.last()
+	}
+
+	pub fn count(&self, kind: StepKind) -> usize {
+		self.steps.iter().filter(|s| s.kind == kind).count()
+	}
+
+	pub fn summary(&self) -> StepSummary {
+		let mut summary = StepSummary {
+			total: self.steps.len(),
+			..StepSummary::default()
+		};
+
+		for step in self.steps.iter() {
+			summary.max_depth = summary.max_depth.max(step.before.depth());
+
+			match step.kind {
+				StepKind::BracketRemoved => summary.brackets_removed += 1,
+				StepKind::OperationEvaluated => summary.operations_evaluated += 1,
+				_ => continue,
+			}
+
+			match step.operation {
+				Some(Operation::Add) => summary.additions += 1,
+				Some(Operation::Sub) => summary.subtractions += 1,
+				Some(Operation::Mul) => summary.multiplications += 1,
+				Some(Operation::Div) => summary.divisions += 1,
+				None => {}
+			}
+		}
+
+		summary
+	}
+}
+
+impl Index<usize> for StepTrace {
+	type Output = Step;
+
+	fn index(&self, index: usize) -> &Self::Output {
+		&self.steps[index]
+	}
+}
+
+impl<'a> IntoIterator for &'a StepTrace {
+	type Item = &'a Step;
+	type IntoIter = Iter<'a, Step>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.steps.iter()
+	}
+}
+
+impl IntoIterator for StepTrace {
+	type Item = Step;
+	type IntoIter = std::vec::IntoIter<Step>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.steps.into_iter()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::builder::{bracket, num};
+	use crate::node_path::Direction;
+	use crate::step_strategy::LeftmostInnermost;
+
+	use super::*;
+
+	#[test]
+	fn reduce() {
+		let fixture = bracket(num(6) / num(2)) * num(4);
+
+		let act = Step::reduce(&fixture, NodePath::from(vec![Direction::Left, Direction::Inner])).unwrap().unwrap();
+		assert_eq!(act.kind(), StepKind::OperationEvaluated);
+		assert_eq!(act.operation(), Some(&Operation::Div));
+		act.operands()[0].eq_i32(&6);
+		act.operands()[1].eq_i32(&2);
+		act.result().unwrap().eq_i32(&3);
+		assert!(act.before() == &fixture);
+		assert!(act.after() == &(bracket(num(3)) * num(4)));
+
+		let act = Step::reduce(act.after(), NodePath::from(vec![Direction::Left])).unwrap().unwrap();
+		assert_eq!(act.kind(), StepKind::BracketRemoved);
+		assert!(act.operation().is_none());
+		assert!(act.operands().is_empty());
+		act.result().unwrap().eq_i32(&3);
+		assert!(act.into_after() == num(3) * num(4));
+
+		assert!(Step::reduce(&fixture, NodePath::root()).unwrap().is_none());
+		assert!(Step::reduce(&fixture, NodePath::from(vec![Direction::Right])).unwrap().is_none());
+		assert!(Step::reduce(&fixture, NodePath::from(vec![Direction::Inner])).unwrap().is_none());
+
+		assert!(Step::reduce(&(num(1) / num(0)), NodePath::root()).is_err());
+	}
+
+	#[test]
+	fn trace() {
+		let fixture = bracket(num(1) + num(2)) * num(3) - num(4) / num(2);
+		let act = StepTrace::trace(&fixture, &LeftmostInnermost);
+
+		assert_eq!(act.len(), 5);
+		assert!(!act.is_empty());
+		assert_eq!(act[0].path().to_string(), "/L/L/I");
+		assert_eq!(act[0].kind(), StepKind::OperationEvaluated);
+		assert_eq!(act[1].path().to_string(), "/L/L");
+		assert_eq!(act[1].kind(), StepKind::BracketRemoved);
+		assert!(act.last().unwrap().after() == &num(7));
+
+		for (prev, next) in act.iter().zip(act.iter().skip(1)) {
+			assert!(prev.after() == next.before());
+		}
+
+		let kinds: Vec<StepKind> = (&act).into_iter().map(|s| s.kind()).collect();
+		assert_eq!(kinds.len(), 5);
+		assert_eq!(act.count(StepKind::OperationEvaluated), 4);
+
+		assert!(StepTrace::trace(&num(1), &LeftmostInnermost).is_empty());
+	}
+
+	#[test]
+	fn bracket_removal() {
+		let fixture = bracket(num(4) - num(5)) * bracket(bracket(num(6) + num(7)) / num(2));
+
+		let act: Vec<Expression> = fixture.steps().map(|s| s.unwrap().into_after()).collect();
+		assert_eq!(act.len(), 7);
+		assert!(act[0] == bracket(num(-1)) * bracket(bracket(num(6) + num(7)) / num(2)));
+		assert!(act[1] == num(-1) * bracket(bracket(num(6) + num(7)) / num(2)));
+		assert!(act[2] == num(-1) * bracket(bracket(num(13)) / num(2)));
+
+		let act: Vec<Step> = Steps::new(&fixture, &LeftmostInnermost)
+			.bracket_removal(BracketRemoval::WithReduction)
+			.map(|s| s.unwrap())
+			.collect();
+		assert_eq!(act.len(), 4);
+		assert!(act.iter().all(|s| s.kind() == StepKind::OperationEvaluated));
+		assert_eq!(act[0].path().to_string(), "/L/I");
+		assert!(act[0].after() == &(num(-1) * bracket(bracket(num(6) + num(7)) / num(2))));
+		assert!(act[1].after() == &(num(-1) * bracket(num(13) / num(2))));
+		assert!(act[3].after() == &num(-6));
+
+		let fixture = bracket(bracket(num(1) + num(2))) * num(3);
+		let act: Vec<Step> = Steps::new(&fixture, &LeftmostInnermost)
+			.bracket_removal(BracketRemoval::WithReduction)
+			.map(|s| s.unwrap())
+			.collect();
+		assert_eq!(act.len(), 2);
+		assert!(act[0].after() == &(num(3) * num(3)));
+		assert!(fixture.parallel_step_with(BracketRemoval::WithReduction).0 == act[0].after().clone());
+	}
+
+	#[test]
+	fn summary() {
+		let fixture = bracket(num(1) + num(2)) * num(3) - num(4) / num(2);
+		let act = StepTrace::trace(&fixture, &LeftmostInnermost).summary();
+
+		let expected = StepSummary {
+			total: 5,
+			brackets_removed: 1,
+			operations_evaluated: 4,
+			additions: 1,
+			subtractions: 1,
+			multiplications: 1,
+			divisions: 1,
+			max_depth: 5,
+		};
+
+		assert_eq!(act, expected);
+	}
+
+	#[test]
+	fn steps() {
+		let fixture = bracket(num(1) + num(2)) * num(3) - num(4) / num(2);
+
+		let act: Vec<Step> = Steps::new(&fixture, &LeftmostInnermost).map(|s| s.unwrap()).collect();
+		assert_eq!(act.len(), 5);
+		assert!(act[4].after() == &num(7));
+
+		let act = Steps::new(&fixture, &LeftmostInnermost)
+			.take_while(|s| s.as_ref().is_ok_and(|s| s.kind() != StepKind::OperationEvaluated || s.path().depth() > 1))
+			.count();
+		assert_eq!(act, 2);
+
+		assert_eq!(Steps::new(&num(1), &LeftmostInnermost).count(), 0);
+	}
+
+	#[test]
+	fn budget() {
+		let fixture = bracket(num(1) + num(2)) * num(3) - num(4) / num(2);
+		let mut steps = Steps::new(&fixture, &LeftmostInnermost).budget(2);
+
+		assert!(steps.next().is_some());
+		assert!(steps.next().is_some());
+		assert!(steps.next().is_none());
+		assert!(steps.current().unwrap() == &(num(3) * num(3) - num(4) / num(2)));
+	}
+
+	#[test]
+	fn cancel() {
+		let flag = Arc::new(AtomicBool::new(false));
+		let fixture = bracket(num(1) + num(2)) * num(3) - num(4) / num(2);
+		let mut steps = Steps::new(&fixture, &LeftmostInnermost).cancel_on(flag.clone());
+
+		assert!(steps.next().is_some());
+		flag.store(true, Ordering::Relaxed);
+		assert!(steps.next().is_none());
+
+		flag.store(false, Ordering::Relaxed);
+		assert!(steps.next().is_some());
+	}
+
+	#[test]
+	fn failure() {
+		let fixture = num(10) / bracket(num(5) - num(5));
+		let act: Vec<Result<Step, StepFailure>> = Steps::new(&fixture, &LeftmostInnermost).collect();
+
+		assert_eq!(act.len(), 3);
+		assert!(act[0].is_ok());
+		assert!(act[1].is_ok());
+
+		let failure = act[2].as_ref().err().unwrap();
+		assert!(failure.path().is_root());
+		assert_eq!(failure.operation(), &Operation::Div);
+		failure.operands()[0].eq_i32(&10);
+		failure.operands()[1].eq_i32(&0);
+		assert!(failure.error().is_division_by_zero());
+		assert!(failure.expression() == &(num(10) / num(0)));
+		assert_eq!(failure.to_string(), "cannot divide by zero here");
+
+		let trace = StepTrace::trace(&fixture, &LeftmostInnermost);
+		assert_eq!(trace.len(), 2);
+		assert_eq!(trace.failure().unwrap().path(), failure.path());
+		assert!(StepTrace::trace(&(num(1) + num(2)), &LeftmostInnermost).failure().is_none());
+
+		let act = Step::reduce(&(num(i32::MAX) + num(1)), NodePath::root()).err().unwrap();
+		assert_eq!(act.to_string(), "cannot calculate here: OVERFLOW!");
+	}
+}
@@ -0,0 +1,238 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::binary_operation::{BinaryOperation, Operation};
+use crate::expression::Expression;
+use crate::node_path::{Direction, NodePath};
+use crate::step::{Step, StepFailure};
+
+// Why a chosen node cannot be reduced now.
+pub enum StepError {
+	InvalidPath(NodePath),
+	AlreadyNumber,
+	OperandsNotReady,
+	BracketNotReduced,
+	// An operand is still a bracket, which has to be calculated first.
+	BracketFirst(NodePath),
+	// The chosen operation, and the one that has to come first.
+	PrecedenceViolation(Operation, Operation),
+	Arithmetic(StepFailure),
+}
+
+fn operation_name(operation: &Operation) -> &'static str {
+	match operation {
+		Operation::Add => "addition",
+		Operation::Sub => "subtraction",
+		Operation::Mul => "multiplication",
+		Operation::Div => "division",
+	}
+}
+
+impl Clone for StepError {
+	fn clone(&self) -> Self {
+		match self {
+			StepError::InvalidPath(path) => StepError::InvalidPath(path.clone()),
+			StepError::AlreadyNumber => StepError::AlreadyNumber,
+			StepError::OperandsNotReady => StepError::OperandsNotReady,
+			StepError::BracketNotReduced => StepError::BracketNotReduced,
+			StepError::BracketFirst(path) => StepError::BracketFirst(path.clone()),
+			StepError::PrecedenceViolation(chosen, first) => {
+				StepError::PrecedenceViolation(chosen.clone(), first.clone())
+			}
+			StepError::Arithmetic(failure) => StepError::Arithmetic(failure.clone()),
+		}
+	}
+}
+
+impl Debug for StepError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			StepError::InvalidPath(path) => write!(f, "InvalidPath:{}", path),
+			StepError::AlreadyNumber => write!(f, "AlreadyNumber"),
+			StepError::OperandsNotReady => write!(f, "OperandsNotReady"),
+			StepError::BracketNotReduced => write!(f, "BracketNotReduced"),
+			StepError::BracketFirst(path) => write!(f, "BracketFirst:{}", path),
+			StepError::PrecedenceViolation(chosen, first) => {
+				write!(f, "PrecedenceViolation:{:?},{:?}", chosen, first)
+			}
+			StepError::Arithmetic(failure) => write!(f, "Arithmetic:{:?}", failure),
+		}
+	}
+}
+
+impl Display for StepError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			StepError::InvalidPath(path) => write!(f, "there is no node at {}", path),
+			StepError::AlreadyNumber => write!(f, "this is already a number"),
+			StepError::OperandsNotReady => write!(f, "operands are not yet numbers"),
+			StepError::BracketNotReduced => write!(f, "the content of the bracket is not yet a number"),
+			StepError::BracketFirst(_) => write!(f, "the bracket must be calculated first"),
+			StepError::PrecedenceViolation(chosen, first) => write!(
+				f,
+				"{} must come before {} here",
+				operation_name(first),
+				operation_name(chosen)
+			),
+			StepError::Arithmetic(failure) => write!(f, "{}", failure),
+		}
+	}
+}
+
+impl Error for StepError {}
+
+fn is_multiplicative(operation: &Operation) -> bool {
+	matches!(operation, Operation::Mul | Operation::Div)
+}
+
+// The node the chosen one is calculated within: the content of the innermost bracket
+// around it, or the whole expression.
+pub(crate) fn scope_of(path: &NodePath) -> NodePath {
+	let directions = path.directions();
+
+	match directions.iter().rposition(|d| *d == Direction::Inner) {
+		Some(idx) => NodePath::from(directions[..=idx].to_vec()),
+		None => NodePath::root(),
+	}
+}
+
+// An operation over two numbers never waits for the rest of the formula. One that is
+// not ready yet says what its operands are waiting for: a bracket, then × and ÷.
+fn pending_operand(bin: &BinaryOperation, path: &NodePath) -> StepError {
+	let operands = [(bin.left(), Direction::Left), (bin.right(), Direction::Right)];
+
+	if let Some((_, direction)) = operands.iter().find(|(e, _)| matches!(e, Expression::Bracket(_))) {
+		return StepError::BracketFirst(path.child(*direction));
+	}
+
+	if !is_multiplicative(bin.operation()) {
+		for (operand, _) in operands.iter() {
+			if let Expression::BinaryOperation(first) = operand {
+				if is_multiplicative(first.operation()) {
+					return StepError::PrecedenceViolation(bin.operation().clone(), first.operation().clone());
+				}
+			}
+		}
+	}
+
+	StepError::OperandsNotReady
+}
+
+// Reduces only the chosen node, if the rules of calculation allow it to go next.
+pub fn step_at(expression: &Expression, path: &NodePath) -> Result<Expression, StepError> {
+	let target = path.resolve(expression).ok_or_else(|| StepError::InvalidPath(path.clone()))?;
+
+	match target {
+		Expression::Number(_) => return Err(StepError::AlreadyNumber),
+		Expression::Variable(_) => return Err(StepError::OperandsNotReady),
+		Expression::Bracket(bracket) => {
+			if !bracket.expression().is_number() {
+				return Err(StepError::BracketNotReduced);
+			}
+		}
+		Expression::BinaryOperation(bin) => {
+			if !bin.left().is_number() || !bin.right().is_number() {
+				return Err(pending_operand(bin, path));
+			}
+		}
+	}
+
+	match Step::reduce(expression, path.clone()) {
+		Ok(Some(step)) => Ok(step.into_after()),
+		Ok(None) => Err(StepError::InvalidPath(path.clone())),
+		Err(failure) => Err(StepError::Arithmetic(failure)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::builder::{bracket, num};
+	use crate::expr;
+	use crate::step::{BracketRemoval, Steps};
+	use crate::step_strategy::LeftmostInnermost;
+
+	use super::*;
+
+	fn path(directions: &[Direction]) -> NodePath {
+		NodePath::from(directions.to_vec())
+	}
+
+	#[test]
+	fn ready() {
+		use Direction::*;
+
+		let fixture = expr!(1 + 2 * 3 - 4 / 2);
+		let act = step_at(&fixture, &path(&[Left, Right])).unwrap();
+		assert!(act == expr!(1 + 6 - 4 / 2));
+
+		let act = step_at(&fixture, &path(&[Right])).unwrap();
+		assert!(act == expr!(1 + 2 * 3 - 2));
+
+		let act = step_at(&(bracket(num(3)) * num(2)), &path(&[Left])).unwrap();
+		assert!(act == num(3) * num(2));
+	}
+
+	#[test]
+	fn not_ready() {
+		use Direction::*;
+
+		let act = step_at(&expr!(1 + 2 + 3), &NodePath::root()).err().unwrap();
+		assert!(matches!(act, StepError::OperandsNotReady));
+		assert_eq!(act.to_string(), "operands are not yet numbers");
+
+		let fixture = expr!(1 + 2 * 3 - 4 / 2);
+
+		let act = step_at(&fixture, &path(&[Left, Left])).err().unwrap();
+		assert!(matches!(act, StepError::AlreadyNumber));
+
+		let act = step_at(&fixture, &path(&[Inner])).err().unwrap();
+		assert!(matches!(act, StepError::InvalidPath(_)));
+
+		let act = step_at(&expr!({ 1 + 2 } * 3), &path(&[Left])).err().unwrap();
+		assert!(matches!(act, StepError::BracketNotReduced));
+	}
+
+	#[test]
+	fn order() {
+		use Direction::*;
+
+		// 1 + 2 does not depend on 3 * 4, only the subtraction over both does.
+		let fixture = expr!(1 + 2 - 3 * 4);
+		assert!(step_at(&fixture, &path(&[Left])).unwrap() == expr!(3 - 3 * 4));
+		assert!(step_at(&fixture, &path(&[Right])).is_ok());
+
+		let act = step_at(&fixture, &NodePath::root()).err().unwrap();
+		assert!(matches!(act, StepError::PrecedenceViolation(Operation::Sub, Operation::Mul)));
+		assert_eq!(act.to_string(), "multiplication must come before subtraction here");
+
+		let fixture = expr!({ 1 + 2 } * 3);
+		assert!(step_at(&fixture, &path(&[Left, Inner])).is_ok());
+
+		let act = step_at(&fixture, &NodePath::root()).err().unwrap();
+		assert!(matches!(&act, StepError::BracketFirst(p) if p == &path(&[Left])));
+		assert_eq!(act.to_string(), "the bracket must be calculated first");
+	}
+
+	#[test]
+	fn default_strategy() {
+		let fixtures = [
+			expr!(1 + 2 * 3 - 4 / 2),
+			expr!({ 1 + 2 } * { 3 - 4 } + 5),
+			expr!(1 - { 2 + 3 * { 4 - 1 } } / 5),
+		];
+
+		for fixture in fixtures.iter() {
+			for step in Steps::new(fixture, &LeftmostInnermost).bracket_removal(BracketRemoval::Separate) {
+				let step = step.unwrap();
+				assert!(step_at(step.before(), step.path()).unwrap() == *step.after());
+			}
+		}
+	}
+
+	#[test]
+	fn arithmetic() {
+		let act = step_at(&(num(1) / num(0)), &NodePath::root()).err().unwrap();
+		assert!(matches!(act, StepError::Arithmetic(_)));
+		assert_eq!(act.to_string(), "cannot divide by zero here");
+	}
+}
@@ -0,0 +1,196 @@
+use crate::binary_operation::Operation;
+use crate::expression::Expression;
+use crate::node_path::{Direction, NodePath};
+
+// Picks the next redex, which is either a bracket around a number or an operation over two numbers.
+pub trait StepStrategy {
+	fn select(&self, expression: &Expression) -> Option<NodePath>;
+}
+
+// Reduce the left child, then the right, then the node.
+pub struct LeftmostInnermost;
+
+// Innermost bracket first, then × and ÷, then + and −, each left to right as read off the formula.
+pub struct PrecedenceSweep;
+
+// Reduce the right child, then the left, then the node.
+pub struct Rightmost;
+
+impl StepStrategy for LeftmostInnermost {
+	fn select(&self, expression: &Expression) -> Option<NodePath> {
+		expression.leftmost_innermost_redex()
+	}
+}
+
+impl StepStrategy for Rightmost {
+	fn select(&self, expression: &Expression) -> Option<NodePath> {
+		let mut path = NodePath::root();
+		let mut current = expression;
+
+		loop {
+			match current {
+				Expression::Number(_) | Expression::Variable(_) => return None,
+				Expression::Bracket(bracket) => {
+					if bracket.expression().is_number() {
+						return Some(path);
+					}
+					
+					path.push(Direction::Inner);
+					current = bracket.expression();
+				}
+				Expression::BinaryOperation(bin) => {
+					if !bin.right().is_number() {
+						path.push(Direction::Right);
+						current = bin.right();
+					} else if !bin.left().is_number() {
+						path.push(Direction::Left);
+						current = bin.left();
+					} else {
+						return Some(path);
+					}
+				}
+			}
+		}
+	}
+}
+
+fn is_multiplicative(operation: &Operation) -> bool {
+	matches!(operation, Operation::Mul | Operation::Div)
+}
+
+// The leftmost bracket that has no bracket inside it.
+fn leftmost_innermost_bracket(expression: &Expression) -> Option<NodePath> {
+	let mut stack = vec![(expression, NodePath::root())];
+	let mut brackets = Vec::<NodePath>::new();
+
+	while let Some((expr, path)) = stack.pop() {
+		match expr {
+			Expression::Number(_) | Expression::Variable(_) => {}
+			Expression::Bracket(bracket) => {
+				stack.push((bracket.expression(), path.child(Direction::Inner)));
+				brackets.push(path);
+			}
+			Expression::BinaryOperation(bin) => {
+				stack.push((bin.right(), path.child(Direction::Right)));
+				stack.push((bin.left(), path.child(Direction::Left)));
+			}
+		}
+	}
+
+	brackets
+		.iter()
+		.find(|candidate| !brackets.iter().any(|p| p.depth() > candidate.depth() && p.starts_with(candidate)))
+		.cloned()
+}
+
+impl StepStrategy for PrecedenceSweep {
+	fn select(&self, expression: &Expression) -> Option<NodePath> {
+		let scope = match leftmost_innermost_bracket(expression) {
+			Some(bracket) => {
+				let inner = bracket.child(Direction::Inner);
+
+				if inner.resolve(expression)?.is_number() {
+					return Some(bracket);
+				}
+
+				inner
+			}
+			None => NodePath::root(),
+		};
+
+		let mut additive = None;
+		let mut stack = vec![(scope.resolve(expression)?, scope)];
+
+		// Right before left, so operations come out in the order they are written.
+		while let Some((expr, path)) = stack.pop() {
+			if let Expression::BinaryOperation(bin) = expr {
+				if bin.left().is_number() && bin.right().is_number() {
+					if is_multiplicative(bin.operation()) {
+						return Some(path);
+					} else if additive.is_none() {
+						additive = Some(path);
+					}
+				} else {
+					stack.push((bin.right(), path.child(Direction::Right)));
+					stack.push((bin.left(), path.child(Direction::Left)));
+				}
+			}
+		}
+
+		additive
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::arithmetic_expression::ArithmeticExpression;
+	use crate::builder::{bracket, num};
+	use crate::expr;
+
+	use super::*;
+
+	fn trace(expression: Expression, strategy: &dyn StepStrategy) -> Vec<String> {
+		let mut vec = Vec::<String>::new();
+		let mut current = expression;
+
+		while let Some(path) = strategy.select(&current) {
+			vec.push(path.to_string());
+			current = current.reduce_at(&path).unwrap();
+		}
+
+		vec
+	}
+
+	#[test]
+	fn leftmost_innermost() {
+		let act = trace(expr!(1 + 2 * 3 - 4 / 2), &LeftmostInnermost);
+		assert_eq!(act, vec!["/L/R", "/L", "/R", "/"]);
+
+		assert!(LeftmostInnermost.select(&num(1)).is_none());
+	}
+
+	#[test]
+	fn rightmost() {
+		let act = trace(expr!(1 + 2 * 3 - 4 / 2), &Rightmost);
+		assert_eq!(act, vec!["/R", "/L/R", "/L", "/"]);
+
+		assert_eq!(Rightmost.select(&bracket(num(1) + num(2))).unwrap().to_string(), "/I");
+		assert_eq!(Rightmost.select(&bracket(num(3))).unwrap().to_string(), "/");
+	}
+
+	#[test]
+	fn precedence_sweep() {
+		let act = trace(expr!(1 + 2 * 3 - 4 / 2), &PrecedenceSweep);
+		assert_eq!(act, vec!["/L/R", "/R", "/L", "/"]);
+
+		let act = trace(expr!({ 1 + 2 } * { 3 + 4 * 5 }), &PrecedenceSweep);
+		assert_eq!(act, vec!["/L/I", "/L", "/R/I/R", "/R/I", "/R", "/"]);
+
+		let act = trace(expr!({ 10 - { 2 + 3 } } * 2), &PrecedenceSweep);
+		assert_eq!(act, vec!["/L/I/R/I", "/L/I/R", "/L/I", "/L", "/"]);
+
+		assert!(PrecedenceSweep.select(&num(42)).is_none());
+	}
+
+	#[test]
+	fn same_result() {
+		let fixture = expr!({ 1 + 2 * 30 } - { { 42 + 4 - 5 } * { { 6 + 7 } / 2 } } * { 30 + 40 * { 20 + 4 - 1 } });
+		let expected = fixture.calc().unwrap();
+
+		for strategy in [&LeftmostInnermost as &dyn StepStrategy, &PrecedenceSweep, &Rightmost] {
+			let mut current = fixture.clone();
+
+			loop {
+				let (next, proceed) = current.step_calc_with(strategy);
+
+				if !proceed {
+					break;
+				}
+
+				current = next;
+			}
+
+			current.extract_as_number().number().eq_number(&expected);
+		}
+	}
+}
@@ -0,0 +1,59 @@
+use crate::arithmetic_expression::ArithmeticExpression;
+use crate::expression::Expression;
+use crate::number_value::{ArithmeticError, NumberResult};
+
+// An unknown such as the x of an equation. It has no value, so a formula with it
+// cannot be calculated and its steps stop at it.
+pub struct Variable(char);
+
+impl From<char> for Variable {
+	fn from(value: char) -> Self {
+		Variable(value)
+	}
+}
+
+impl ArithmeticExpression for Variable {
+	fn calc(&self) -> NumberResult {
+		Err(ArithmeticError::unknown(self.0))
+	}
+
+	fn to_expression(self) -> Expression {
+		Expression::from(self)
+	}
+
+	fn simplify(&self) -> Expression {
+		self.clone().to_expression()
+	}
+
+	fn step_calc(&self) -> (Expression, bool) {
+		(self.clone().to_expression(), false)
+	}
+}
+
+impl Variable {
+	pub fn name(&self) -> char {
+		self.0
+	}
+}
+
+impl Clone for Variable {
+	fn clone(&self) -> Self {
+		Variable(self.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn calc_test() {
+		let fixture = Variable::from('x');
+		let act = fixture.calc().unwrap_err();
+
+		assert!(act.is_unknown());
+		assert_eq!(act.message(), "UNKNOWN:x");
+		assert_eq!(fixture.name(), 'x');
+		assert!(!fixture.step_calc().1);
+	}
+}
@@ -0,0 +1,536 @@
+use crate::binary_operation::Operation;
+use crate::number_value::NumberValue;
+use crate::step::{Step, StepKind};
+
+// How far a single operation is broken down when showing the steps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Detail {
+	#[default]
+	Operation,
+	// Multi-digit operations are expanded into written calculation (筆算).
+	Written,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WrittenStep {
+	// One column of an addition or subtraction, the ones being place 0. The carry is what
+	// comes in from the column on the right, a borrow for subtraction, and carry_out what
+	// goes on to the left.
+	Column {
+		place: usize,
+		top: u32,
+		bottom: u32,
+		carry: u32,
+		digit: u32,
+		carry_out: u32,
+	},
+	// The multiplicand times one digit of the multiplier.
+	PartialProduct { place: usize, digit: u32, product: u64 },
+	// One digit of the quotient: the part divided, the digit, what is taken away and what is left.
+	Quotient {
+		place: usize,
+		part: u64,
+		digit: u32,
+		product: u64,
+		remainder: u64,
+	},
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowKind {
+	Carry,
+	Number,
+	Rule,
+}
+
+// One line of the layout. Every row of a calculation has the same number of cells,
+// one character each and empty where nothing is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrittenRow {
+	kind: RowKind,
+	cells: Vec<String>,
+}
+
+impl WrittenRow {
+	pub fn kind(&self) -> RowKind {
+		self.kind
+	}
+
+	pub fn cells(&self) -> &[String] {
+		&self.cells
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrittenCalculation {
+	operation: Operation,
+	left: u64,
+	right: u64,
+	steps: Vec<WrittenStep>,
+	result: u64,
+	remainder: u64,
+}
+
+fn digits(value: u64) -> Vec<u32> {
+	value.to_string().chars().rev().map(|c| c.to_digit(10).unwrap()).collect()
+}
+
+fn digit_at(value: u64, place: usize) -> u32 {
+	digits(value).get(place).copied().unwrap_or(0)
+}
+
+fn len(value: u64) -> usize {
+	value.to_string().len()
+}
+
+fn add(left: u64, right: u64) -> Vec<WrittenStep> {
+	let mut steps = vec![];
+	let mut carry = 0;
+
+	for place in 0..len(left).max(len(right)) {
+		let (top, bottom) = (digit_at(left, place), digit_at(right, place));
+		let sum = top + bottom + carry;
+
+		steps.push(WrittenStep::Column {
+			place,
+			top,
+			bottom,
+			carry,
+			digit: sum % 10,
+			carry_out: sum / 10,
+		});
+		carry = sum / 10;
+	}
+
+	steps
+}
+
+fn subtract(left: u64, right: u64) -> Vec<WrittenStep> {
+	let mut steps = vec![];
+	let mut borrow = 0;
+
+	for place in 0..len(left) {
+		let (top, bottom) = (digit_at(left, place), digit_at(right, place));
+		let carry_out = u32::from(top < bottom + borrow);
+
+		steps.push(WrittenStep::Column {
+			place,
+			top,
+			bottom,
+			carry: borrow,
+			digit: top + 10 * carry_out - bottom - borrow,
+			carry_out,
+		});
+		borrow = carry_out;
+	}
+
+	steps
+}
+
+fn multiply(left: u64, right: u64) -> Vec<WrittenStep> {
+	digits(right)
+		.into_iter()
+		.enumerate()
+		.map(|(place, digit)| WrittenStep::PartialProduct {
+			place,
+			digit,
+			product: left * u64::from(digit),
+		})
+		.collect()
+}
+
+fn divide(left: u64, right: u64) -> Vec<WrittenStep> {
+	let mut steps = vec![];
+	let mut remainder = 0;
+
+	for (place, digit) in digits(left).into_iter().enumerate().rev() {
+		let part = remainder * 10 + u64::from(digit);
+		let quotient = part / right;
+
+		remainder = part - quotient * right;
+		steps.push(WrittenStep::Quotient {
+			place,
+			part,
+			digit: quotient as u32,
+			product: quotient * right,
+			remainder,
+		});
+	}
+
+	steps
+}
+
+// Columns that carry or borrow.
+fn column_carries(steps: &[WrittenStep]) -> usize {
+	steps
+		.iter()
+		.filter(|s| matches!(s, WrittenStep::Column { carry_out, .. } if *carry_out > 0))
+		.count()
+}
+
+// Digits of the multiplicand whose product with the digit carries into the next place.
+fn product_carries(left: u64, digit: u32) -> usize {
+	let mut carry = 0;
+	let mut count = 0;
+
+	for top in digits(left) {
+		carry = (top * digit + carry) / 10;
+		count += usize::from(carry > 0);
+	}
+
+	count
+}
+
+// Cells of a row being laid out, right aligned on the given offset from the right edge.
+struct Grid {
+	width: usize,
+	rows: Vec<WrittenRow>,
+}
+
+impl Grid {
+	fn row(&mut self, kind: RowKind) -> &mut WrittenRow {
+		self.rows.push(WrittenRow {
+			kind,
+			cells: vec![String::default(); self.width],
+		});
+		self.rows.last_mut().unwrap()
+	}
+
+	fn put(&mut self, text: &str, offset: usize) {
+		let end = self.width - offset;
+		let row = self.rows.last_mut().unwrap();
+
+		for (idx, c) in text.chars().enumerate() {
+			row.cells[end - text.chars().count() + idx] = c.to_string();
+		}
+	}
+
+	fn number(&mut self, sign: &str, value: u64, offset: usize) {
+		self.row(RowKind::Number);
+		self.put(&value.to_string(), offset);
+
+		if !sign.is_empty() {
+			self.rows.last_mut().unwrap().cells[0] = sign.to_string();
+		}
+	}
+
+	fn rule(&mut self) {
+		self.row(RowKind::Rule);
+	}
+}
+
+impl WrittenCalculation {
+	// None when written calculation does not help: single digits, negative numbers,
+	// subtraction below zero or division by zero.
+	pub fn new(operation: &Operation, left: &NumberValue, right: &NumberValue) -> Option<Self> {
+		let (NumberValue::Integer(l), NumberValue::Integer(r)) = (left, right);
+		let (left, right) = (u64::try_from(*l).ok()?, u64::try_from(*r).ok()?);
+
+		let (steps, result, remainder) = match operation {
+			_ if left < 10 && right < 10 => return None,
+			Operation::Add => (add(left, right), left + right, 0),
+			Operation::Sub if left >= right => (subtract(left, right), left - right, 0),
+			Operation::Sub => return None,
+			Operation::Mul => (multiply(left, right), left * right, 0),
+			Operation::Div if right == 0 || left < 10 => return None,
+			Operation::Div => (divide(left, right), left / right, left % right),
+		};
+
+		Some(WrittenCalculation {
+			operation: operation.clone(),
+			left,
+			right,
+			steps,
+			result,
+			remainder,
+		})
+	}
+
+	pub fn operation(&self) -> &Operation {
+		&self.operation
+	}
+
+	pub fn steps(&self) -> &[WrittenStep] {
+		&self.steps
+	}
+
+	pub fn result(&self) -> u64 {
+		self.result
+	}
+
+	// What is left over by a division, 0 for the other operations.
+	pub fn remainder(&self) -> u64 {
+		self.remainder
+	}
+
+	// Every carry and borrow made on paper: in the columns, within each partial product
+	// and while adding them up, and in the subtractions of a division.
+	pub fn carries(&self) -> usize {
+		match self.operation {
+			Operation::Add | Operation::Sub => column_carries(&self.steps),
+			Operation::Mul => {
+				let mut count = 0;
+				let mut sum = 0;
+
+				for step in &self.steps {
+					if let WrittenStep::PartialProduct { place, digit, product } = step {
+						let shifted = product * 10u64.pow(*place as u32);
+
+						count += product_carries(self.left, *digit) + column_carries(&add(sum, shifted));
+						sum += shifted;
+					}
+				}
+
+				count
+			}
+			Operation::Div => self
+				.steps
+				.iter()
+				.map(|step| match step {
+					WrittenStep::Quotient { part, product, .. } => column_carries(&subtract(*part, *product)),
+					_ => 0,
+				})
+				.sum(),
+		}
+	}
+
+	// The calculation as it is written on paper, digits aligned in columns.
+	pub fn rows(&self) -> Vec<WrittenRow> {
+		match self.operation {
+			Operation::Add | Operation::Sub => self.column_rows(),
+			Operation::Mul => self.product_rows(),
+			Operation::Div => self.division_rows(),
+		}
+	}
+
+	fn column_rows(&self) -> Vec<WrittenRow> {
+		let width = len(self.left).max(len(self.right)) + 2;
+		let mut grid = Grid { width, rows: vec![] };
+
+		// Carried digits for addition, and the digits left after lending for subtraction.
+		let marks: Vec<(usize, u32)> = self
+			.steps
+			.iter()
+			.filter_map(|step| match step {
+				WrittenStep::Column {
+					place, top, carry, carry_out, ..
+				} if *carry > 0 => Some(match self.operation {
+					Operation::Add => (*place, *carry),
+					// The digit after lending to the right: a 0 borrows in turn and leaves 9,
+					// while the 9 of 295 − 99 leaves 8, not the 18 it then subtracts from.
+					_ => (*place, (top + 10 * carry_out - carry) % 10),
+				}),
+				_ => None,
+			})
+			.collect();
+
+		if !marks.is_empty() {
+			grid.row(RowKind::Carry);
+
+			for (place, mark) in marks {
+				grid.put(&mark.to_string(), place);
+			}
+		}
+
+		let sign = if self.operation == Operation::Add { "+" } else { "-" };
+		grid.number("", self.left, 0);
+		grid.number(sign, self.right, 0);
+		grid.rule();
+		grid.number("", self.result, 0);
+
+		grid.rows
+	}
+
+	fn product_rows(&self) -> Vec<WrittenRow> {
+		let width = len(self.left).max(len(self.right)).max(len(self.result)) + 2;
+		let mut grid = Grid { width, rows: vec![] };
+
+		grid.number("", self.left, 0);
+		grid.number("×", self.right, 0);
+		grid.rule();
+
+		if self.steps.len() > 1 {
+			for step in &self.steps {
+				if let WrittenStep::PartialProduct { place, product, .. } = step {
+					grid.number("", *product, *place);
+				}
+			}
+
+			grid.rule();
+		}
+
+		grid.number("", self.result, 0);
+
+		grid.rows
+	}
+
+	fn division_rows(&self) -> Vec<WrittenRow> {
+		let prefix = len(self.right) + 1;
+		let width = prefix + len(self.left) + 1;
+		let mut grid = Grid { width, rows: vec![] };
+
+		grid.number("", self.result, 0);
+		grid.rule();
+		grid.number("", self.left, 0);
+		grid.put(&format!("{})", self.right), len(self.left));
+
+		// Leading zeros of the quotient are not written.
+		let first = self
+			.steps
+			.iter()
+			.position(|s| matches!(s, WrittenStep::Quotient { digit, .. } if *digit > 0))
+			.unwrap_or(self.steps.len() - 1);
+
+		for (idx, step) in self.steps.iter().enumerate().skip(first) {
+			if let WrittenStep::Quotient { place, part, product, .. } = step {
+				if idx > first {
+					grid.number("", *part, *place);
+				}
+
+				grid.number("", *product, *place);
+				grid.rule();
+			}
+		}
+
+		grid.number("", self.remainder, 0);
+
+		grid.rows
+	}
+
+	pub fn to_text(&self) -> String {
+		self.rows()
+			.iter()
+			.map(|row| match row.kind {
+				RowKind::Rule => "-".repeat(row.cells.len()),
+				_ => row
+					.cells
+					.iter()
+					.map(|c| if c.is_empty() { " " } else { c.as_str() })
+					.collect::<String>()
+					.trim_end()
+					.to_string(),
+			})
+			.collect::<Vec<_>>()
+			.join("\n")
+	}
+}
+
+// The written calculation behind an evaluated operation, if it is worth writing out.
+pub fn written(step: &Step) -> Option<WrittenCalculation> {
+	match (step.kind(), step.operation(), step.operands()) {
+		(StepKind::OperationEvaluated, Some(operation), [left, right]) => WrittenCalculation::new(operation, left, right),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::expr;
+	use crate::step::StepTrace;
+	use crate::step_strategy::LeftmostInnermost;
+
+	use super::*;
+
+	fn calc(operation: Operation, left: i32, right: i32) -> Option<WrittenCalculation> {
+		WrittenCalculation::new(&operation, &NumberValue::from(left), &NumberValue::from(right))
+	}
+
+	#[test]
+	fn addition() {
+		let act = calc(Operation::Add, 47, 36).unwrap();
+
+		assert_eq!(act.result(), 83);
+		assert_eq!(
+			act.steps()[0],
+			WrittenStep::Column {
+				place: 0,
+				top: 7,
+				bottom: 6,
+				carry: 0,
+				digit: 3,
+				carry_out: 1
+			}
+		);
+		assert_eq!(act.to_text(), "  1\n  47\n+ 36\n----\n  83");
+
+		assert_eq!(calc(Operation::Add, 95, 7).unwrap().to_text(), "  1\n  95\n+  7\n----\n 102");
+	}
+
+	#[test]
+	fn subtraction() {
+		let act = calc(Operation::Sub, 52, 17).unwrap();
+		assert_eq!(act.to_text(), "  4\n  52\n- 17\n----\n  35");
+
+		// The hundreds lend to the tens, which lend on to the ones.
+		let act = calc(Operation::Sub, 100, 1).unwrap();
+		assert_eq!(act.result(), 99);
+		assert_eq!(act.to_text(), "  09\n  100\n-   1\n-----\n   99");
+
+		let act = calc(Operation::Sub, 295, 99).unwrap();
+		assert_eq!(act.to_text(), "  18\n  295\n-  99\n-----\n  196");
+
+		assert!(calc(Operation::Sub, 17, 52).is_none());
+	}
+
+	#[test]
+	fn multiplication() {
+		let act = calc(Operation::Mul, 47, 36).unwrap();
+
+		assert_eq!(act.result(), 1692);
+		assert_eq!(
+			act.steps(),
+			&[
+				WrittenStep::PartialProduct {
+					place: 0,
+					digit: 6,
+					product: 282
+				},
+				WrittenStep::PartialProduct {
+					place: 1,
+					digit: 3,
+					product: 141
+				},
+			]
+		);
+		assert_eq!(act.to_text(), "    47\n×   36\n------\n   282\n  141\n------\n  1692");
+		// Two in each partial product, none adding 282 and 1410.
+		assert_eq!(act.carries(), 4);
+
+		assert_eq!(calc(Operation::Mul, 47, 3).unwrap().to_text(), "   47\n×   3\n-----\n  141");
+		assert_eq!(calc(Operation::Mul, 95, 95).unwrap().carries(), 6);
+	}
+
+	#[test]
+	fn division() {
+		let act = calc(Operation::Div, 142, 3).unwrap();
+
+		assert_eq!(act.result(), 47);
+		assert_eq!(act.remainder(), 1);
+		assert_eq!(act.to_text(), "    47\n------\n 3)142\n   12\n------\n    22\n    21\n------\n     1");
+		assert_eq!(act.carries(), 0);
+
+		// 10 - 9 borrows for each of the three 3s.
+		assert_eq!(calc(Operation::Div, 1000, 3).unwrap().carries(), 3);
+
+		let act = calc(Operation::Div, 12, 50).unwrap();
+		assert_eq!(act.result(), 0);
+		assert_eq!(act.remainder(), 12);
+	}
+
+	#[test]
+	fn not_written() {
+		assert!(calc(Operation::Add, 3, 4).is_none());
+		assert!(calc(Operation::Add, -13, 4).is_none());
+		assert!(calc(Operation::Div, 13, 0).is_none());
+		assert!(calc(Operation::Div, 8, 20).is_none());
+	}
+
+	#[test]
+	fn from_step() {
+		let trace = StepTrace::trace(&expr!({ 47 + 36 } * 2), &LeftmostInnermost);
+		let act: Vec<_> = trace.iter().map(|s| written(s).map(|w| w.result())).collect();
+
+		// The bracket removal has no written form.
+		assert_eq!(act, vec![Some(83), None, Some(166)]);
+	}
+}